@@ -0,0 +1,60 @@
+// Downloads the prebuilt PDFium library the app renders PDFs with into
+// src-tauri/pdfium/, which tauri.conf.json bundles as a resource. The version
+// must match the `pdfium_*` feature pdfium-render is built with.
+import { execFileSync } from "node:child_process";
+import {
+  copyFileSync,
+  existsSync,
+  mkdirSync,
+  mkdtempSync,
+  rmSync,
+} from "node:fs";
+import { writeFile } from "node:fs/promises";
+import { tmpdir } from "node:os";
+import { join } from "node:path";
+
+const PDFIUM_VERSION = "7543";
+
+const platforms = {
+  "linux-x64": ["linux-x64", "lib/libpdfium.so"],
+  "linux-arm64": ["linux-arm64", "lib/libpdfium.so"],
+  "darwin-x64": ["mac-x64", "lib/libpdfium.dylib"],
+  "darwin-arm64": ["mac-arm64", "lib/libpdfium.dylib"],
+  "win32-x64": ["win-x64", "bin/pdfium.dll"],
+  "win32-arm64": ["win-arm64", "bin/pdfium.dll"],
+};
+
+const key = `${process.platform}-${process.arch}`;
+const platform = platforms[key];
+if (!platform) {
+  console.error(`PDFium is not available for ${key}`);
+  process.exit(1);
+}
+const [asset, library] = platform;
+const outDir = join(import.meta.dirname, "..", "src-tauri", "pdfium");
+const target = join(outDir, library.split("/").pop());
+
+if (existsSync(target)) {
+  process.exit(0);
+}
+
+const url = `https://github.com/bblanchon/pdfium-binaries/releases/download/chromium%2F${PDFIUM_VERSION}/pdfium-${asset}.tgz`;
+console.log(`Fetching ${url}`);
+const response = await fetch(url);
+if (!response.ok) {
+  console.error(
+    `Failed to download PDFium: ${response.status} ${response.statusText}`,
+  );
+  process.exit(1);
+}
+
+const workDir = mkdtempSync(join(tmpdir(), "pdfium-"));
+try {
+  const archive = join(workDir, "pdfium.tgz");
+  await writeFile(archive, Buffer.from(await response.arrayBuffer()));
+  execFileSync("tar", ["-xzf", archive, "-C", workDir, library]);
+  mkdirSync(outDir, { recursive: true });
+  copyFileSync(join(workDir, library), target);
+} finally {
+  rmSync(workDir, { recursive: true, force: true });
+}
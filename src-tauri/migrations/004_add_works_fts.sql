@@ -0,0 +1,34 @@
+-- Migration 004: Full-text index over work metadata
+-- The trigram tokenizer gives substring matching, which also works for
+-- Japanese titles that have no whitespace between words.
+
+CREATE VIRTUAL TABLE IF NOT EXISTS works_fts USING fts5(
+    title,
+    artist,
+    circle,
+    genre,
+    origin,
+    content = 'works',
+    content_rowid = 'id',
+    tokenize = 'trigram'
+);
+
+CREATE TRIGGER IF NOT EXISTS works_fts_after_insert AFTER INSERT ON works BEGIN
+    INSERT INTO works_fts (rowid, title, artist, circle, genre, origin)
+        VALUES (new.id, new.title, new.artist, new.circle, new.genre, new.origin);
+END;
+
+CREATE TRIGGER IF NOT EXISTS works_fts_after_delete AFTER DELETE ON works BEGIN
+    INSERT INTO works_fts (works_fts, rowid, title, artist, circle, genre, origin)
+        VALUES ('delete', old.id, old.title, old.artist, old.circle, old.genre, old.origin);
+END;
+
+CREATE TRIGGER IF NOT EXISTS works_fts_after_update
+    AFTER UPDATE OF title, artist, circle, genre, origin ON works BEGIN
+    INSERT INTO works_fts (works_fts, rowid, title, artist, circle, genre, origin)
+        VALUES ('delete', old.id, old.title, old.artist, old.circle, old.genre, old.origin);
+    INSERT INTO works_fts (rowid, title, artist, circle, genre, origin)
+        VALUES (new.id, new.title, new.artist, new.circle, new.genre, new.origin);
+END;
+
+INSERT INTO works_fts (works_fts) VALUES ('rebuild');
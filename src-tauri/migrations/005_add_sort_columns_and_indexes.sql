@@ -0,0 +1,13 @@
+-- Migration 005: Track last-read time and index every sortable column
+-- Secondary indexes carry the rowid, so each one also orders ties by id
+-- for keyset pagination.
+
+ALTER TABLE works ADD COLUMN last_read_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_works_artist       ON works(artist);
+CREATE INDEX IF NOT EXISTS idx_works_circle       ON works(circle);
+CREATE INDEX IF NOT EXISTS idx_works_genre        ON works(genre);
+CREATE INDEX IF NOT EXISTS idx_works_year         ON works(year);
+CREATE INDEX IF NOT EXISTS idx_works_page_count   ON works(page_count);
+CREATE INDEX IF NOT EXISTS idx_works_updated_at   ON works(updated_at);
+CREATE INDEX IF NOT EXISTS idx_works_last_read_at ON works(last_read_at);
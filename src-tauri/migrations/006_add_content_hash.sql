@@ -0,0 +1,5 @@
+-- Migration 006: Fingerprint work contents so moved works can be re-linked
+
+ALTER TABLE works ADD COLUMN content_hash TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_works_content_hash ON works(content_hash);
@@ -0,0 +1,10 @@
+-- Migration 007: Per-page content hashes for duplicate detection
+
+CREATE TABLE IF NOT EXISTS page_hashes (
+    work_id    INTEGER NOT NULL REFERENCES works(id) ON DELETE CASCADE,
+    page_index INTEGER NOT NULL,
+    hash       TEXT    NOT NULL,
+    PRIMARY KEY (work_id, page_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_page_hashes_hash ON page_hashes(hash);
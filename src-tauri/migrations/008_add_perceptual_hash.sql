@@ -0,0 +1,3 @@
+-- Migration 008: Perceptual hash of each work's first page for similarity search
+
+ALTER TABLE works ADD COLUMN perceptual_hash INTEGER;
@@ -0,0 +1,20 @@
+-- Migration 009: Persist bulk imports so they can be cancelled and resumed
+
+CREATE TABLE IF NOT EXISTS import_jobs (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    status     TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'cancelled', 'completed')),
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS import_job_items (
+    id       INTEGER PRIMARY KEY AUTOINCREMENT,
+    job_id   INTEGER NOT NULL REFERENCES import_jobs(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    request  TEXT    NOT NULL,
+    status   TEXT    NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'succeeded', 'failed')),
+    error    TEXT,
+    UNIQUE(job_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_import_job_items_status ON import_job_items(job_id, status);
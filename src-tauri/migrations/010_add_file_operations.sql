@@ -0,0 +1,14 @@
+-- Migration 010: Journal file operations so interrupted imports and moves can be recovered
+
+CREATE TABLE IF NOT EXISTS file_operations (
+    id             INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind           TEXT    NOT NULL CHECK (kind IN ('import', 'relocate')),
+    work_id        INTEGER,
+    source         TEXT    NOT NULL,
+    destination    TEXT    NOT NULL,
+    work_type      TEXT    NOT NULL,
+    delete_source  INTEGER NOT NULL DEFAULT 0,
+    job_item_id    INTEGER REFERENCES import_job_items(id) ON DELETE SET NULL,
+    state          TEXT    NOT NULL DEFAULT 'copying' CHECK (state IN ('copying', 'committed')),
+    created_at     TEXT    NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
@@ -0,0 +1,3 @@
+-- Migration 011: Remember whether a journaled file operation renamed or copied
+
+ALTER TABLE file_operations ADD COLUMN strategy TEXT NOT NULL DEFAULT 'copy' CHECK (strategy IN ('copy', 'rename'));
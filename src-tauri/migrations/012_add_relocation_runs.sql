@@ -0,0 +1,18 @@
+-- Migration 012: Record the last relocation run so it can be undone
+
+CREATE TABLE IF NOT EXISTS relocation_runs (
+    id           INTEGER PRIMARY KEY AUTOINCREMENT,
+    old_template TEXT,
+    new_template TEXT NOT NULL,
+    created_at   TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS relocation_run_items (
+    id       INTEGER PRIMARY KEY AUTOINCREMENT,
+    run_id   INTEGER NOT NULL REFERENCES relocation_runs(id) ON DELETE CASCADE,
+    work_id  INTEGER NOT NULL REFERENCES works(id) ON DELETE CASCADE,
+    old_path TEXT    NOT NULL,
+    new_path TEXT    NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_relocation_run_items_run ON relocation_run_items(run_id);
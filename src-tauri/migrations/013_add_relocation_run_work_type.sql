@@ -0,0 +1,3 @@
+-- Migration 013: Remember which work type's template a relocation run changed
+
+ALTER TABLE relocation_runs ADD COLUMN work_type TEXT NOT NULL DEFAULT 'folder';
@@ -0,0 +1,11 @@
+-- Migration 014: User-defined rules for reading metadata from folder names
+
+CREATE TABLE IF NOT EXISTS folder_name_rules (
+    id       INTEGER PRIMARY KEY AUTOINCREMENT,
+    name     TEXT    NOT NULL,
+    pattern  TEXT    NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    enabled  INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE INDEX IF NOT EXISTS idx_folder_name_rules_priority ON folder_name_rules(priority, id);
@@ -0,0 +1,3 @@
+-- Migration 015: Mark works registered in place outside the library's control
+
+ALTER TABLE works ADD COLUMN external INTEGER NOT NULL DEFAULT 0;
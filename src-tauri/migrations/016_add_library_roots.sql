@@ -0,0 +1,78 @@
+-- Migration 016: Named library roots, possibly on removable volumes
+-- A work under a root stores the root's id and a path relative to it, so
+-- the root can be remounted elsewhere without touching the works. Works
+-- outside every root keep an absolute path and a NULL root_id.
+-- The same relative path may exist under two roots, so `path` is only
+-- unique per root and `works` is recreated without its UNIQUE constraint.
+
+CREATE TABLE IF NOT EXISTS library_roots (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    name       TEXT NOT NULL UNIQUE,
+    path       TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+PRAGMA foreign_keys = OFF;
+
+CREATE TABLE works_new (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    title           TEXT    NOT NULL,
+    root_id         INTEGER REFERENCES library_roots(id),
+    path            TEXT    NOT NULL,
+    type            TEXT    NOT NULL CHECK (type IN ('image', 'pdf', 'archive', 'folder')),
+    page_count      INTEGER,
+    thumbnail       BLOB,
+    created_at      TEXT    NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at      TEXT    NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    artist          TEXT,
+    year            INTEGER,
+    genre           TEXT,
+    circle          TEXT,
+    origin          TEXT,
+    last_read_at    TEXT,
+    content_hash    TEXT,
+    perceptual_hash INTEGER,
+    external        INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO works_new (id, title, path, type, page_count, thumbnail, created_at, updated_at, artist, year, genre, circle, origin, last_read_at, content_hash, perceptual_hash, external)
+    SELECT id, title, path, type, page_count, thumbnail, created_at, updated_at, artist, year, genre, circle, origin, last_read_at, content_hash, perceptual_hash, external
+    FROM works;
+
+DROP TABLE works;
+ALTER TABLE works_new RENAME TO works;
+
+CREATE UNIQUE INDEX idx_works_location     ON works(IFNULL(root_id, 0), path);
+CREATE INDEX idx_works_root_id             ON works(root_id);
+CREATE INDEX idx_works_type                ON works(type);
+CREATE INDEX idx_works_title               ON works(title);
+CREATE INDEX idx_works_created_at          ON works(created_at);
+CREATE INDEX idx_works_artist              ON works(artist);
+CREATE INDEX idx_works_circle              ON works(circle);
+CREATE INDEX idx_works_genre               ON works(genre);
+CREATE INDEX idx_works_year                ON works(year);
+CREATE INDEX idx_works_page_count          ON works(page_count);
+CREATE INDEX idx_works_updated_at          ON works(updated_at);
+CREATE INDEX idx_works_last_read_at        ON works(last_read_at);
+CREATE INDEX idx_works_content_hash        ON works(content_hash);
+
+-- Dropping `works` took the full-text sync triggers with it.
+CREATE TRIGGER works_fts_after_insert AFTER INSERT ON works BEGIN
+    INSERT INTO works_fts (rowid, title, artist, circle, genre, origin)
+        VALUES (new.id, new.title, new.artist, new.circle, new.genre, new.origin);
+END;
+
+CREATE TRIGGER works_fts_after_delete AFTER DELETE ON works BEGIN
+    INSERT INTO works_fts (works_fts, rowid, title, artist, circle, genre, origin)
+        VALUES ('delete', old.id, old.title, old.artist, old.circle, old.genre, old.origin);
+END;
+
+CREATE TRIGGER works_fts_after_update
+    AFTER UPDATE OF title, artist, circle, genre, origin ON works BEGIN
+    INSERT INTO works_fts (works_fts, rowid, title, artist, circle, genre, origin)
+        VALUES ('delete', old.id, old.title, old.artist, old.circle, old.genre, old.origin);
+    INSERT INTO works_fts (rowid, title, artist, circle, genre, origin)
+        VALUES (new.id, new.title, new.artist, new.circle, new.genre, new.origin);
+END;
+
+PRAGMA foreign_keys = ON;
@@ -0,0 +1,9 @@
+-- Migration 017: Store relocation run items the way works are stored
+-- A path under a library root is kept as the root's id and a path relative
+-- to it, so a run can still be undone after the library root has changed or
+-- a root has been remounted elsewhere. Paths outside every root stay absolute
+-- with a NULL root id. Converting existing rows needs the roots' folders, so
+-- it is done in Rust once the columns exist.
+
+ALTER TABLE relocation_run_items ADD COLUMN old_root_id INTEGER REFERENCES library_roots(id) ON DELETE CASCADE;
+ALTER TABLE relocation_run_items ADD COLUMN new_root_id INTEGER REFERENCES library_roots(id) ON DELETE CASCADE;
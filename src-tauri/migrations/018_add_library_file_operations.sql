@@ -0,0 +1,24 @@
+-- Migration 018: Journal moves of the whole library tree
+-- `kind` only allowed imports and relocations, so the table is recreated
+-- with a third kind for moving the library root. Nothing references it.
+
+CREATE TABLE file_operations_new (
+    id             INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind           TEXT    NOT NULL CHECK (kind IN ('import', 'relocate', 'library')),
+    work_id        INTEGER,
+    source         TEXT    NOT NULL,
+    destination    TEXT    NOT NULL,
+    work_type      TEXT    NOT NULL,
+    delete_source  INTEGER NOT NULL DEFAULT 0,
+    job_item_id    INTEGER REFERENCES import_job_items(id) ON DELETE SET NULL,
+    state          TEXT    NOT NULL DEFAULT 'copying' CHECK (state IN ('copying', 'committed')),
+    created_at     TEXT    NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    strategy       TEXT    NOT NULL DEFAULT 'copy' CHECK (strategy IN ('copy', 'rename'))
+);
+
+INSERT INTO file_operations_new (id, kind, work_id, source, destination, work_type, delete_source, job_item_id, state, created_at, strategy)
+    SELECT id, kind, work_id, source, destination, work_type, delete_source, job_item_id, state, created_at, strategy
+    FROM file_operations;
+
+DROP TABLE file_operations;
+ALTER TABLE file_operations_new RENAME TO file_operations;
@@ -0,0 +1,105 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use zip::ZipArchive;
+
+use crate::error::AppError;
+use crate::scanner;
+
+fn open_archive(archive_path: &Path) -> Result<ZipArchive<File>, AppError> {
+    let file = File::open(archive_path)?;
+    Ok(ZipArchive::new(file)?)
+}
+
+/// The image entries of the archive in page order, with their entry indexes.
+fn sorted_pages(archive: &ZipArchive<File>) -> Vec<(usize, String)> {
+    let mut pages: Vec<(usize, String)> = (0..archive.len())
+        .filter_map(|index| Some((index, archive.name_for_index(index)?)))
+        .filter(|(_, name)| !name.ends_with('/') && !name.starts_with("__MACOSX/"))
+        .filter(|(_, name)| scanner::is_image_file(Path::new(name)))
+        .map(|(index, name)| (index, name.to_string()))
+        .collect();
+    pages.sort_by(|(_, a), (_, b)| natord::compare(a, b));
+    pages
+}
+
+struct PageIndex {
+    path: PathBuf,
+    len: u64,
+    modified: Option<SystemTime>,
+    pages: Arc<Vec<(usize, String)>>,
+}
+
+/// The page listing of the archive last read from. The viewer reads one
+/// archive page by page, so a single entry saves listing and sorting it again
+/// for every page. Only the listing is kept; the file is reopened each time so
+/// it can still be moved or deleted.
+static PAGE_INDEX: Mutex<Option<PageIndex>> = Mutex::new(None);
+
+/// Lists the archive's pages, reusing the cached listing while the file's
+/// size and modification time are unchanged.
+fn cached_pages(
+    archive_path: &Path,
+    archive: &ZipArchive<File>,
+) -> Result<Arc<Vec<(usize, String)>>, AppError> {
+    let metadata = std::fs::metadata(archive_path)?;
+    let (len, modified) = (metadata.len(), metadata.modified().ok());
+    let mut cached = PAGE_INDEX.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(index) = cached.as_ref() {
+        if index.path == archive_path && index.len == len && index.modified == modified {
+            return Ok(index.pages.clone());
+        }
+    }
+    let pages = Arc::new(sorted_pages(archive));
+    *cached = Some(PageIndex {
+        path: archive_path.to_path_buf(),
+        len,
+        modified,
+        pages: pages.clone(),
+    });
+    Ok(pages)
+}
+
+pub fn list_archive_pages(archive_path: &Path) -> Result<Vec<String>, AppError> {
+    let archive = open_archive(archive_path)?;
+    Ok(sorted_pages(&archive)
+        .into_iter()
+        .map(|(_, name)| name)
+        .collect())
+}
+
+/// Reads a single page out of the archive without extracting anything to disk.
+/// Returns the entry name alongside the data so callers can derive a content type.
+pub fn read_archive_page(
+    archive_path: &Path,
+    page_index: usize,
+) -> Result<(String, Vec<u8>), AppError> {
+    let mut archive = open_archive(archive_path)?;
+    let pages = cached_pages(archive_path, &archive)?;
+    let (index, name) = pages.get(page_index).ok_or(AppError::NotFound)?;
+    // The size in the entry header is not trusted for preallocation: a
+    // crafted archive could claim any size.
+    let mut data = Vec::new();
+    archive.by_index(*index)?.read_to_end(&mut data)?;
+    Ok((name.clone(), data))
+}
+
+/// Streams every page in page order, opening the archive only once.
+pub fn visit_archive_pages(
+    archive_path: &Path,
+    mut visit: impl FnMut(&mut dyn Read) -> Result<(), AppError>,
+) -> Result<(), AppError> {
+    let mut archive = open_archive(archive_path)?;
+    for (index, _) in sorted_pages(&archive) {
+        let mut entry = archive.by_index(index)?;
+        visit(&mut entry)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "tests/archive.rs"]
+mod tests;
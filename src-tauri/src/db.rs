@@ -1,9 +1,14 @@
 use std::path::Path;
 
+use rusqlite::types::Value;
 use rusqlite::Connection;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::error::AppError;
+use crate::roots;
+use crate::search::CompiledQuery;
+use crate::settings;
+use crate::tags::{self, TagGroup};
 
 pub fn open_db(app_data_dir: &Path) -> Result<Connection, AppError> {
     std::fs::create_dir_all(app_data_dir)?;
@@ -23,6 +28,21 @@ fn init_db(conn: &Connection) -> Result<(), AppError> {
     conn.execute_batch(include_str!("../migrations/001_create_initial_tables.sql"))?;
     apply_migration_002(conn)?;
     apply_migration_003(conn)?;
+    apply_migration_004(conn)?;
+    apply_migration_005(conn)?;
+    apply_migration_006(conn)?;
+    apply_migration_007(conn)?;
+    apply_migration_008(conn)?;
+    apply_migration_009(conn)?;
+    apply_migration_010(conn)?;
+    apply_migration_011(conn)?;
+    apply_migration_012(conn)?;
+    apply_migration_013(conn)?;
+    apply_migration_014(conn)?;
+    apply_migration_015(conn)?;
+    apply_migration_016(conn)?;
+    apply_migration_017(conn)?;
+    apply_migration_018(conn)?;
     Ok(())
 }
 
@@ -61,13 +81,243 @@ fn apply_migration_003(conn: &Connection) -> Result<(), AppError> {
     Ok(())
 }
 
+fn apply_migration_004(conn: &Connection) -> Result<(), AppError> {
+    let has_fts = conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type='table' AND name='works_fts'")?
+        .exists([])?;
+    // Migration 003 recreates `works`, which drops the sync triggers along with it.
+    let has_triggers = conn
+        .prepare(
+            "SELECT 1 FROM sqlite_master WHERE type='trigger' AND name='works_fts_after_insert'",
+        )?
+        .exists([])?;
+
+    if !has_fts || !has_triggers {
+        conn.execute_batch(include_str!("../migrations/004_add_works_fts.sql"))?;
+    }
+    Ok(())
+}
+
+fn apply_migration_005(conn: &Connection) -> Result<(), AppError> {
+    let has_last_read = conn
+        .prepare("SELECT 1 FROM pragma_table_info('works') WHERE name = 'last_read_at'")?
+        .exists([])?;
+
+    if !has_last_read {
+        conn.execute_batch(include_str!(
+            "../migrations/005_add_sort_columns_and_indexes.sql"
+        ))?;
+    }
+    Ok(())
+}
+
+fn apply_migration_006(conn: &Connection) -> Result<(), AppError> {
+    let has_content_hash = conn
+        .prepare("SELECT 1 FROM pragma_table_info('works') WHERE name = 'content_hash'")?
+        .exists([])?;
+
+    if !has_content_hash {
+        conn.execute_batch(include_str!("../migrations/006_add_content_hash.sql"))?;
+    }
+    Ok(())
+}
+
+fn apply_migration_007(conn: &Connection) -> Result<(), AppError> {
+    let has_page_hashes = conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type='table' AND name='page_hashes'")?
+        .exists([])?;
+
+    if !has_page_hashes {
+        conn.execute_batch(include_str!("../migrations/007_add_page_hashes.sql"))?;
+    }
+    Ok(())
+}
+
+fn apply_migration_008(conn: &Connection) -> Result<(), AppError> {
+    let has_perceptual_hash = conn
+        .prepare("SELECT 1 FROM pragma_table_info('works') WHERE name = 'perceptual_hash'")?
+        .exists([])?;
+
+    if !has_perceptual_hash {
+        conn.execute_batch(include_str!("../migrations/008_add_perceptual_hash.sql"))?;
+    }
+    Ok(())
+}
+
+fn apply_migration_009(conn: &Connection) -> Result<(), AppError> {
+    let has_import_jobs = conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type='table' AND name='import_jobs'")?
+        .exists([])?;
+
+    if !has_import_jobs {
+        conn.execute_batch(include_str!("../migrations/009_add_import_jobs.sql"))?;
+    }
+    Ok(())
+}
+
+fn apply_migration_010(conn: &Connection) -> Result<(), AppError> {
+    let has_file_operations = conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type='table' AND name='file_operations'")?
+        .exists([])?;
+
+    if !has_file_operations {
+        conn.execute_batch(include_str!("../migrations/010_add_file_operations.sql"))?;
+    }
+    Ok(())
+}
+
+fn apply_migration_011(conn: &Connection) -> Result<(), AppError> {
+    let has_strategy = conn
+        .prepare("SELECT 1 FROM pragma_table_info('file_operations') WHERE name = 'strategy'")?
+        .exists([])?;
+
+    if !has_strategy {
+        conn.execute_batch(include_str!(
+            "../migrations/011_add_file_operation_strategy.sql"
+        ))?;
+    }
+    Ok(())
+}
+
+fn apply_migration_012(conn: &Connection) -> Result<(), AppError> {
+    let has_relocation_runs = conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type='table' AND name='relocation_runs'")?
+        .exists([])?;
+
+    if !has_relocation_runs {
+        conn.execute_batch(include_str!("../migrations/012_add_relocation_runs.sql"))?;
+    }
+    Ok(())
+}
+
+fn apply_migration_013(conn: &Connection) -> Result<(), AppError> {
+    let has_work_type = conn
+        .prepare("SELECT 1 FROM pragma_table_info('relocation_runs') WHERE name = 'work_type'")?
+        .exists([])?;
+
+    if !has_work_type {
+        conn.execute_batch(include_str!(
+            "../migrations/013_add_relocation_run_work_type.sql"
+        ))?;
+    }
+    Ok(())
+}
+
+fn apply_migration_014(conn: &Connection) -> Result<(), AppError> {
+    let has_folder_name_rules = conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type='table' AND name='folder_name_rules'")?
+        .exists([])?;
+
+    if !has_folder_name_rules {
+        conn.execute_batch(include_str!("../migrations/014_add_folder_name_rules.sql"))?;
+    }
+    Ok(())
+}
+
+fn apply_migration_015(conn: &Connection) -> Result<(), AppError> {
+    let has_external = conn
+        .prepare("SELECT 1 FROM pragma_table_info('works') WHERE name = 'external'")?
+        .exists([])?;
+
+    if !has_external {
+        conn.execute_batch(include_str!("../migrations/015_add_external_works.sql"))?;
+    }
+    Ok(())
+}
+
+fn apply_migration_016(conn: &Connection) -> Result<(), AppError> {
+    let has_root_id = conn
+        .prepare("SELECT 1 FROM pragma_table_info('works') WHERE name = 'root_id'")?
+        .exists([])?;
+
+    if !has_root_id {
+        conn.execute_batch(include_str!("../migrations/016_add_library_roots.sql"))?;
+    }
+    Ok(())
+}
+
+fn apply_migration_017(conn: &Connection) -> Result<(), AppError> {
+    let has_run_item_roots = conn
+        .prepare(
+            "SELECT 1 FROM pragma_table_info('relocation_run_items') WHERE name = 'old_root_id'",
+        )?
+        .exists([])?;
+
+    if !has_run_item_roots {
+        conn.execute_batch(include_str!(
+            "../migrations/017_add_relocation_run_item_roots.sql"
+        ))?;
+    }
+    // The frontend's migrations may have added the columns already, so the
+    // path conversion is tracked by `user_version` rather than the schema.
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version < 17 && convert_paths_to_roots(conn)? {
+        conn.pragma_update(None, "user_version", 17)?;
+    }
+    Ok(())
+}
+
+/// Registers the library root as a root, which stores the paths of the works
+/// under it relative to it, and does the same for relocation run items.
+/// Returns false while the library root is not mounted, so the conversion is
+/// tried again on a later open.
+fn convert_paths_to_roots(conn: &Connection) -> Result<bool, AppError> {
+    let mut converted = true;
+    if let Some(library_root) = settings::get_library_root(conn)? {
+        if Path::new(&library_root).is_absolute() {
+            roots::ensure_root(conn, &library_root)?;
+            converted = roots::list_roots(conn)?
+                .iter()
+                .any(|root| Path::new(&library_root).starts_with(&root.path));
+        }
+    }
+
+    let items: Vec<(i64, String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, old_path, new_path FROM relocation_run_items WHERE old_root_id IS NULL AND new_root_id IS NULL",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    for (item_id, old_path, new_path) in items {
+        let (old_root_id, old_location) = roots::split_location(conn, &old_path)?;
+        let (new_root_id, new_location) = roots::split_location(conn, &new_path)?;
+        conn.execute(
+            "UPDATE relocation_run_items SET old_root_id = ?1, old_path = ?2, new_root_id = ?3, new_path = ?4 WHERE id = ?5",
+            rusqlite::params![old_root_id, old_location, new_root_id, new_location, item_id],
+        )?;
+    }
+    Ok(converted)
+}
+
+fn apply_migration_018(conn: &Connection) -> Result<(), AppError> {
+    let allows_library = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type='table' AND name='file_operations'",
+        [],
+        |row| row.get::<_, String>(0),
+    )?;
+
+    if !allows_library.contains("'library'") {
+        conn.execute_batch(include_str!(
+            "../migrations/018_add_library_file_operations.sql"
+        ))?;
+    }
+    Ok(())
+}
+
+/// Whether a work is registered at the absolute `path`, either relative to
+/// the root containing it or, for works added before that root, as is.
 pub fn path_exists(conn: &Connection, path: &str) -> Result<bool, AppError> {
-    let mut stmt = conn.prepare_cached("SELECT 1 FROM works WHERE path = ?1")?;
-    Ok(stmt.exists([path])?)
+    let (root_id, location) = roots::split_location(conn, path)?;
+    let mut stmt = conn.prepare_cached(
+        "SELECT 1 FROM works WHERE (IFNULL(root_id, 0) = IFNULL(?1, 0) AND path = ?2) OR (root_id IS NULL AND path = ?3)",
+    )?;
+    Ok(stmt.exists(rusqlite::params![root_id, location, path])?)
 }
 
 pub struct WorkRecord<'a> {
     pub title: &'a str,
+    /// Absolute; stored relative to the library root containing it, if any.
     pub path: &'a str,
     pub work_type: &'a str,
     pub page_count: i32,
@@ -77,14 +327,19 @@ pub struct WorkRecord<'a> {
     pub genre: Option<&'a str>,
     pub circle: Option<&'a str>,
     pub origin: Option<&'a str>,
+    pub content_hash: Option<&'a str>,
+    /// Registered where it is; the library never moves or deletes its files.
+    pub external: bool,
 }
 
 pub fn insert_work(conn: &Connection, record: &WorkRecord) -> Result<(), AppError> {
+    let (root_id, location) = roots::split_location(conn, record.path)?;
     conn.execute(
-        "INSERT INTO works (title, path, type, page_count, thumbnail, artist, year, genre, circle, origin) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        "INSERT INTO works (title, root_id, path, type, page_count, thumbnail, artist, year, genre, circle, origin, content_hash, external) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
         rusqlite::params![
             record.title,
-            record.path,
+            root_id,
+            location,
             record.work_type,
             record.page_count,
             record.thumbnail,
@@ -93,6 +348,8 @@ pub fn insert_work(conn: &Connection, record: &WorkRecord) -> Result<(), AppErro
             record.genre,
             record.circle,
             record.origin,
+            record.content_hash,
+            record.external,
         ],
     )?;
     Ok(())
@@ -106,6 +363,7 @@ pub struct WorkSummary {
     pub work_type: String,
     pub page_count: i32,
     pub created_at: String,
+    pub root_id: Option<i64>,
 }
 
 #[derive(Serialize)]
@@ -113,15 +371,49 @@ pub struct WorkSummary {
 pub struct WorkDetail {
     pub id: i64,
     pub title: String,
+    /// Absolute, resolved against the work's library root.
     pub path: String,
+    pub root_id: Option<i64>,
+    /// The work's library root is not mounted, so its files cannot be read.
+    pub offline: bool,
     pub work_type: String,
     pub page_count: i32,
     pub created_at: String,
+    pub updated_at: String,
     pub artist: Option<String>,
     pub year: Option<i32>,
     pub genre: Option<String>,
     pub circle: Option<String>,
     pub origin: Option<String>,
+    pub external: bool,
+    pub tags: Vec<TagGroup>,
+}
+
+fn sort_column(sort_by: &str) -> &'static str {
+    match sort_by {
+        "title" => "title",
+        "artist" => "artist",
+        "year" => "year",
+        "page_count" => "page_count",
+        "updated_at" => "updated_at",
+        "last_read_at" => "last_read_at",
+        _ => "created_at",
+    }
+}
+
+fn sort_direction(sort_order: &str) -> &'static str {
+    match sort_order {
+        "asc" => "ASC",
+        _ => "DESC",
+    }
+}
+
+/// Orders by the sort column with `id` as a tie-breaker so keyset cursors are stable.
+/// NULLs keep SQLite's ordering (first when ascending) so the column index stays usable.
+pub(crate) fn order_by_clause(sort_by: &str, sort_order: &str) -> String {
+    let column = sort_column(sort_by);
+    let order = sort_direction(sort_order);
+    format!("{} {}, id {}", column, order, order)
 }
 
 pub fn list_works(
@@ -129,17 +421,9 @@ pub fn list_works(
     sort_by: &str,
     sort_order: &str,
 ) -> Result<Vec<WorkSummary>, AppError> {
-    let column = match sort_by {
-        "title" => "title",
-        _ => "created_at",
-    };
-    let order = match sort_order {
-        "asc" => "ASC",
-        _ => "DESC",
-    };
     let sql = format!(
-        "SELECT id, title, type, page_count, created_at FROM works ORDER BY {} {}",
-        column, order
+        "SELECT id, title, type, page_count, created_at, root_id FROM works ORDER BY {}",
+        order_by_clause(sort_by, sort_order)
     );
     let mut stmt = conn.prepare(&sql)?;
     let rows = stmt.query_map([], |row| {
@@ -149,6 +433,7 @@ pub fn list_works(
             work_type: row.get(2)?,
             page_count: row.get(3)?,
             created_at: row.get(4)?,
+            root_id: row.get(5)?,
         })
     })?;
     let mut works = Vec::new();
@@ -158,6 +443,194 @@ pub fn list_works(
     Ok(works)
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum SortValue {
+    Integer(i64),
+    Text(String),
+}
+
+impl From<SortValue> for Value {
+    fn from(value: SortValue) -> Self {
+        match value {
+            SortValue::Integer(v) => Value::Integer(v),
+            SortValue::Text(v) => Value::Text(v),
+        }
+    }
+}
+
+/// Position after the last row of a page: the sort column value and id of that row.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkCursor {
+    pub value: Option<SortValue>,
+    pub id: i64,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkFilter {
+    pub work_type: Option<String>,
+    pub artist: Option<String>,
+    pub circle: Option<String>,
+    pub genre: Option<String>,
+    pub year_from: Option<i32>,
+    pub year_to: Option<i32>,
+    pub tag_id: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkPage {
+    pub works: Vec<WorkSummary>,
+    pub next_cursor: Option<WorkCursor>,
+}
+
+const MAX_PAGE_SIZE: u32 = 500;
+
+fn compile_filter(filter: &WorkFilter) -> CompiledQuery {
+    let mut conditions = Vec::new();
+    let mut params = Vec::new();
+    let mut bind = |value: Value| {
+        params.push(value);
+        format!("?{}", params.len())
+    };
+
+    let text_filters = [
+        ("type", &filter.work_type),
+        ("artist", &filter.artist),
+        ("circle", &filter.circle),
+        ("genre", &filter.genre),
+    ];
+    for (column, value) in text_filters {
+        if let Some(value) = value {
+            conditions.push(format!("{} = {}", column, bind(Value::Text(value.clone()))));
+        }
+    }
+    if let Some(from) = filter.year_from {
+        conditions.push(format!("year >= {}", bind(Value::Integer(from as i64))));
+    }
+    if let Some(to) = filter.year_to {
+        conditions.push(format!("year <= {}", bind(Value::Integer(to as i64))));
+    }
+    if let Some(tag_id) = filter.tag_id {
+        conditions.push(format!(
+            "id IN (SELECT work_id FROM works_tags WHERE tag_id = {})",
+            bind(Value::Integer(tag_id))
+        ));
+    }
+    CompiledQuery { conditions, params }
+}
+
+/// Restricts rows to those sorting strictly after the cursor, mirroring
+/// `order_by_clause` (NULLs sort lowest).
+fn cursor_condition(
+    column: &str,
+    ascending: bool,
+    cursor: &WorkCursor,
+    compiled: &mut CompiledQuery,
+) {
+    compiled.params.push(Value::Integer(cursor.id));
+    let id_param = format!("?{}", compiled.params.len());
+    let (cmp, id_cmp) = if ascending { (">", ">") } else { ("<", "<") };
+
+    let condition = match &cursor.value {
+        None if ascending => format!(
+            "(({col} IS NULL AND id {id_cmp} {id_param}) OR {col} IS NOT NULL)",
+            col = column
+        ),
+        None => format!("({col} IS NULL AND id {id_cmp} {id_param})", col = column),
+        Some(value) => {
+            compiled.params.push(value.clone().into());
+            let value_param = format!("?{}", compiled.params.len());
+            let after = format!(
+                "{col} {cmp} {value_param} OR ({col} = {value_param} AND id {id_cmp} {id_param})",
+                col = column
+            );
+            if ascending {
+                format!("({})", after)
+            } else {
+                format!("({} OR {} IS NULL)", after, column)
+            }
+        }
+    };
+    compiled.conditions.push(condition);
+}
+
+fn sort_value(value: Value) -> Option<SortValue> {
+    match value {
+        Value::Integer(v) => Some(SortValue::Integer(v)),
+        Value::Text(v) => Some(SortValue::Text(v)),
+        _ => None,
+    }
+}
+
+/// Returns up to `limit` works after `cursor`. `next_cursor` is `None` on the last page.
+pub fn list_works_page(
+    conn: &Connection,
+    filter: &WorkFilter,
+    sort_by: &str,
+    sort_order: &str,
+    limit: u32,
+    cursor: Option<&WorkCursor>,
+) -> Result<WorkPage, AppError> {
+    let column = sort_column(sort_by);
+    let limit = limit.clamp(1, MAX_PAGE_SIZE) as usize;
+
+    let mut compiled = compile_filter(filter);
+    if let Some(cursor) = cursor {
+        cursor_condition(column, sort_order == "asc", cursor, &mut compiled);
+    }
+    let sql = format!(
+        "SELECT id, title, type, page_count, created_at, root_id, {} FROM works WHERE {} ORDER BY {} LIMIT {}",
+        column,
+        compiled.where_clause(),
+        order_by_clause(sort_by, sort_order),
+        limit + 1
+    );
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(compiled.params.iter()), |row| {
+        Ok((
+            WorkSummary {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                work_type: row.get(2)?,
+                page_count: row.get(3)?,
+                created_at: row.get(4)?,
+                root_id: row.get(5)?,
+            },
+            row.get::<_, Value>(6)?,
+        ))
+    })?;
+    let mut works = Vec::new();
+    let mut last_value = None;
+    for row in rows {
+        let (work, value) = row?;
+        if works.len() == limit {
+            let next_cursor = last_value.map(|(id, value)| WorkCursor { value, id });
+            return Ok(WorkPage { works, next_cursor });
+        }
+        last_value = Some((work.id, sort_value(value)));
+        works.push(work);
+    }
+    Ok(WorkPage {
+        works,
+        next_cursor: None,
+    })
+}
+
+pub fn mark_work_read(conn: &Connection, work_id: i64) -> Result<(), AppError> {
+    let updated = conn.execute(
+        "UPDATE works SET last_read_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = ?1",
+        [work_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
 pub fn get_thumbnail(conn: &Connection, work_id: i64) -> Result<Vec<u8>, AppError> {
     let mut stmt = conn.prepare_cached("SELECT thumbnail FROM works WHERE id = ?1")?;
     let thumb: Option<Vec<u8>> =
@@ -169,25 +642,35 @@ pub fn get_thumbnail(conn: &Connection, work_id: i64) -> Result<Vec<u8>, AppErro
     thumb.ok_or(AppError::NotFound)
 }
 
-pub fn list_folder_works(conn: &Connection) -> Result<Vec<WorkDetail>, AppError> {
-    let mut stmt = conn.prepare_cached(
-        "SELECT id, title, path, type, page_count, created_at, artist, year, genre, circle, origin FROM works WHERE type = 'folder'",
-    )?;
-    let rows = stmt.query_map([], |row| {
-        Ok(WorkDetail {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            path: row.get(2)?,
-            work_type: row.get(3)?,
-            page_count: row.get(4)?,
-            created_at: row.get(5)?,
-            artist: row.get(6)?,
-            year: row.get(7)?,
-            genre: row.get(8)?,
-            circle: row.get(9)?,
-            origin: row.get(10)?,
-        })
-    })?;
+const WORK_DETAIL_QUERY: &str = "SELECT w.id, w.title, w.path, w.type, w.page_count, w.created_at, w.updated_at, w.artist, w.year, w.genre, w.circle, w.origin, w.external, w.root_id, r.path FROM works w LEFT JOIN library_roots r ON r.id = w.root_id";
+
+fn row_to_work_detail(row: &rusqlite::Row) -> rusqlite::Result<WorkDetail> {
+    let path: String = row.get(2)?;
+    let root_path: Option<String> = row.get(14)?;
+    Ok(WorkDetail {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        path: roots::resolve_location(root_path.as_deref(), &path),
+        root_id: row.get(13)?,
+        offline: root_path.is_some_and(|root| !roots::is_online(&root)),
+        work_type: row.get(3)?,
+        page_count: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+        artist: row.get(7)?,
+        year: row.get(8)?,
+        genre: row.get(9)?,
+        circle: row.get(10)?,
+        origin: row.get(11)?,
+        external: row.get(12)?,
+        tags: Vec::new(),
+    })
+}
+
+/// Every work with its metadata, without tags.
+pub fn list_all_works(conn: &Connection) -> Result<Vec<WorkDetail>, AppError> {
+    let mut stmt = conn.prepare_cached(&format!("{} ORDER BY w.id", WORK_DETAIL_QUERY))?;
+    let rows = stmt.query_map([], row_to_work_detail)?;
     let mut works = Vec::new();
     for row in rows {
         works.push(row?);
@@ -195,37 +678,112 @@ pub fn list_folder_works(conn: &Connection) -> Result<Vec<WorkDetail>, AppError>
     Ok(works)
 }
 
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkEdit {
+    pub title: String,
+    pub artist: Option<String>,
+    pub year: Option<i32>,
+    pub genre: Option<String>,
+    pub circle: Option<String>,
+    pub origin: Option<String>,
+}
+
+pub fn update_work_metadata(
+    conn: &Connection,
+    work_id: i64,
+    edit: &WorkEdit,
+) -> Result<(), AppError> {
+    let updated = conn.execute(
+        "UPDATE works SET title = ?1, artist = ?2, year = ?3, genre = ?4, circle = ?5, origin = ?6, updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = ?7",
+        rusqlite::params![
+            edit.title,
+            edit.artist,
+            edit.year,
+            edit.genre,
+            edit.circle,
+            edit.origin,
+            work_id,
+        ],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+/// Removes the row; `works_tags` and `playlist_items` rows go with it via ON DELETE CASCADE.
+pub fn delete_work(conn: &Connection, work_id: i64) -> Result<(), AppError> {
+    let deleted = conn.execute("DELETE FROM works WHERE id = ?1", [work_id])?;
+    if deleted == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+pub fn set_content_hash(
+    conn: &Connection,
+    work_id: i64,
+    content_hash: &str,
+) -> Result<(), AppError> {
+    conn.execute(
+        "UPDATE works SET content_hash = ?1 WHERE id = ?2",
+        rusqlite::params![content_hash, work_id],
+    )?;
+    Ok(())
+}
+
+pub fn update_page_count(conn: &Connection, work_id: i64, page_count: i32) -> Result<(), AppError> {
+    let updated = conn.execute(
+        "UPDATE works SET page_count = ?1, updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = ?2",
+        rusqlite::params![page_count, work_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+pub fn set_perceptual_hash(
+    conn: &Connection,
+    work_id: i64,
+    perceptual_hash: Option<i64>,
+) -> Result<(), AppError> {
+    conn.execute(
+        "UPDATE works SET perceptual_hash = ?1 WHERE id = ?2",
+        rusqlite::params![perceptual_hash, work_id],
+    )?;
+    Ok(())
+}
+
+pub fn update_thumbnail(conn: &Connection, work_id: i64, thumbnail: &[u8]) -> Result<(), AppError> {
+    conn.execute(
+        "UPDATE works SET thumbnail = ?1, updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = ?2",
+        rusqlite::params![thumbnail, work_id],
+    )?;
+    Ok(())
+}
+
+/// Records a work's new absolute path, relative to the root containing it.
 pub fn update_work_path(conn: &Connection, work_id: i64, new_path: &str) -> Result<(), AppError> {
+    let (root_id, location) = roots::split_location(conn, new_path)?;
     conn.execute(
-        "UPDATE works SET path = ?1 WHERE id = ?2",
-        rusqlite::params![new_path, work_id],
+        "UPDATE works SET root_id = ?1, path = ?2 WHERE id = ?3",
+        rusqlite::params![root_id, location, work_id],
     )?;
     Ok(())
 }
 
 pub fn get_work(conn: &Connection, work_id: i64) -> Result<WorkDetail, AppError> {
-    let mut stmt = conn.prepare_cached(
-        "SELECT id, title, path, type, page_count, created_at, artist, year, genre, circle, origin FROM works WHERE id = ?1",
-    )?;
-    stmt.query_row([work_id], |row| {
-        Ok(WorkDetail {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            path: row.get(2)?,
-            work_type: row.get(3)?,
-            page_count: row.get(4)?,
-            created_at: row.get(5)?,
-            artist: row.get(6)?,
-            year: row.get(7)?,
-            genre: row.get(8)?,
-            circle: row.get(9)?,
-            origin: row.get(10)?,
-        })
-    })
-    .map_err(|e| match e {
-        rusqlite::Error::QueryReturnedNoRows => AppError::NotFound,
-        other => AppError::Database(other),
-    })
+    let mut stmt = conn.prepare_cached(&format!("{} WHERE w.id = ?1", WORK_DETAIL_QUERY))?;
+    let mut work = stmt
+        .query_row([work_id], row_to_work_detail)
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => AppError::NotFound,
+            other => AppError::Database(other),
+        })?;
+    work.tags = tags::group_by_category(tags::list_work_tags(conn, work_id)?);
+    Ok(work)
 }
 
 #[cfg(test)]
@@ -0,0 +1,120 @@
+use std::collections::{BTreeMap, HashSet};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+use crate::error::AppError;
+use crate::hashing::WorkHashes;
+use crate::roots;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateMatch {
+    pub work_id: i64,
+    pub title: String,
+    pub path: String,
+    /// Distinct pages the candidate shares with the registered work.
+    pub matched_pages: usize,
+    /// True when both hold exactly the same set of pages (or the same file).
+    pub exact: bool,
+}
+
+/// Records a work's content hash and replaces its page hashes.
+pub fn store_work_hashes(
+    conn: &Connection,
+    work_id: i64,
+    hashes: &WorkHashes,
+) -> Result<(), AppError> {
+    db::set_content_hash(conn, work_id, &hashes.content_hash)?;
+    conn.execute("DELETE FROM page_hashes WHERE work_id = ?1", [work_id])?;
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO page_hashes (work_id, page_index, hash) VALUES (?1, ?2, ?3)",
+    )?;
+    for (page_index, hash) in hashes.page_hashes.iter().enumerate() {
+        stmt.execute(rusqlite::params![work_id, page_index as i64, hash])?;
+    }
+    Ok(())
+}
+
+pub fn has_page_hashes(conn: &Connection, work_id: i64) -> Result<bool, AppError> {
+    let mut stmt = conn.prepare_cached("SELECT 1 FROM page_hashes WHERE work_id = ?1")?;
+    Ok(stmt.exists([work_id])?)
+}
+
+pub fn has_any_page_hashes(conn: &Connection) -> Result<bool, AppError> {
+    let mut stmt = conn.prepare_cached("SELECT 1 FROM page_hashes LIMIT 1")?;
+    Ok(stmt.exists([])?)
+}
+
+/// Finds registered works sharing content with `hashes`, exact matches first,
+/// then by the number of shared pages.
+pub fn find_duplicates(
+    conn: &Connection,
+    hashes: &WorkHashes,
+) -> Result<Vec<DuplicateMatch>, AppError> {
+    let distinct: HashSet<&str> = hashes.page_hashes.iter().map(String::as_str).collect();
+    let mut matches: BTreeMap<i64, DuplicateMatch> = BTreeMap::new();
+
+    if !distinct.is_empty() {
+        let json =
+            serde_json::to_string(&distinct).map_err(|e| AppError::WorkError(e.to_string()))?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT w.id, w.title, w.path, COUNT(DISTINCT p.hash), (SELECT COUNT(DISTINCT hash) FROM page_hashes WHERE work_id = w.id), r.path FROM page_hashes p JOIN works w ON w.id = p.work_id LEFT JOIN library_roots r ON r.id = w.root_id WHERE p.hash IN (SELECT value FROM json_each(?1)) GROUP BY w.id",
+        )?;
+        let rows = stmt.query_map([json], |row| {
+            let matched: i64 = row.get(3)?;
+            let total: i64 = row.get(4)?;
+            let root_path: Option<String> = row.get(5)?;
+            Ok(DuplicateMatch {
+                work_id: row.get(0)?,
+                title: row.get(1)?,
+                path: roots::resolve_location(root_path.as_deref(), &row.get::<_, String>(2)?),
+                matched_pages: matched as usize,
+                exact: matched == total && matched as usize == distinct.len(),
+            })
+        })?;
+        for row in rows {
+            let found = row?;
+            matches.insert(found.work_id, found);
+        }
+    }
+
+    let mut stmt = conn.prepare_cached(
+        "SELECT w.id, w.title, w.path, r.path FROM works w LEFT JOIN library_roots r ON r.id = w.root_id WHERE w.content_hash = ?1",
+    )?;
+    let rows = stmt.query_map([&hashes.content_hash], |row| {
+        let root_path: Option<String> = row.get(3)?;
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            roots::resolve_location(root_path.as_deref(), &row.get::<_, String>(2)?),
+        ))
+    })?;
+    for row in rows {
+        let (work_id, title, path) = row?;
+        matches
+            .entry(work_id)
+            .and_modify(|m| m.exact = true)
+            .or_insert(DuplicateMatch {
+                work_id,
+                title,
+                path,
+                matched_pages: distinct.len(),
+                exact: true,
+            });
+    }
+
+    let mut matches: Vec<DuplicateMatch> = matches.into_values().collect();
+    matches.sort_by(|a, b| {
+        b.exact
+            .cmp(&a.exact)
+            .then(b.matched_pages.cmp(&a.matched_pages))
+            .then(a.work_id.cmp(&b.work_id))
+    });
+    Ok(matches)
+}
+
+#[cfg(test)]
+#[path = "tests/dedup.rs"]
+mod tests;
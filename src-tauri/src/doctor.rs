@@ -0,0 +1,355 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::ipc::Channel;
+use walkdir::WalkDir;
+
+use crate::archive;
+use crate::db::{self, WorkDetail};
+use crate::dedup;
+use crate::error::AppError;
+use crate::hashing;
+use crate::importer;
+use crate::pdf;
+use crate::roots;
+use crate::scanner;
+use crate::settings;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum DoctorProgress {
+    Checking {
+        current: usize,
+        total: usize,
+    },
+    #[serde(rename_all = "camelCase")]
+    Scanning {
+        scanned_dirs: usize,
+    },
+    #[serde(rename_all = "camelCase")]
+    Completed {
+        missing: usize,
+        orphaned: usize,
+        mismatched: usize,
+    },
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingWork {
+    pub work_id: i64,
+    pub title: String,
+    pub path: String,
+    /// An unregistered folder or file under the library root with identical contents.
+    pub relink_candidate: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedEntry {
+    pub path: String,
+    pub work_type: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PageCountMismatch {
+    pub work_id: i64,
+    pub title: String,
+    pub path: String,
+    pub recorded: i32,
+    pub actual: usize,
+}
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DoctorReport {
+    pub missing: Vec<MissingWork>,
+    pub orphaned: Vec<OrphanedEntry>,
+    pub page_count_mismatches: Vec<PageCountMismatch>,
+    /// Present works without content or page hashes, for `backfill_hashes`.
+    pub unhashed: Vec<i64>,
+}
+
+struct RegisteredWork {
+    id: i64,
+    title: String,
+    path: String,
+    work_type: String,
+    page_count: i32,
+    content_hash: Option<String>,
+    /// On an unmounted root: neither checked nor reported missing.
+    offline: bool,
+}
+
+fn list_registered_works(conn: &Connection) -> Result<Vec<RegisteredWork>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT w.id, w.title, w.path, w.type, IFNULL(w.page_count, 0), w.content_hash, r.path FROM works w LEFT JOIN library_roots r ON r.id = w.root_id ORDER BY w.id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let root_path: Option<String> = row.get(6)?;
+        Ok(RegisteredWork {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            path: roots::resolve_location(root_path.as_deref(), &row.get::<_, String>(2)?),
+            work_type: row.get(3)?,
+            page_count: row.get(4)?,
+            content_hash: row.get(5)?,
+            offline: root_path.is_some_and(|root| !roots::is_online(&root)),
+        })
+    })?;
+    let mut works = Vec::new();
+    for row in rows {
+        works.push(row?);
+    }
+    Ok(works)
+}
+
+/// Counts the pages currently on disk for a work of the given type.
+pub(crate) fn count_pages(path: &Path, work_type: &str) -> Result<usize, AppError> {
+    match work_type {
+        "folder" => Ok(importer::list_images_in_folder(path)?.len()),
+        "archive" => Ok(archive::list_archive_pages(path)?.len()),
+        "pdf" => pdf::page_count(path),
+        _ => Ok(1),
+    }
+}
+
+fn work_exists(path: &Path, work_type: &str) -> bool {
+    if work_type == "folder" {
+        path.is_dir()
+    } else {
+        path.is_file()
+    }
+}
+
+/// The folders to look for unregistered works in: every mounted root, and the
+/// library root unless a root already covers it.
+fn scan_roots(conn: &Connection) -> Result<Vec<PathBuf>, AppError> {
+    let roots = roots::list_roots(conn)?;
+    let mut scanned: Vec<PathBuf> = roots
+        .iter()
+        .filter(|root| root.online)
+        .map(|root| PathBuf::from(&root.path))
+        .collect();
+    if let Some(library_root) = settings::get_library_root(conn)? {
+        let library_root = PathBuf::from(library_root);
+        if library_root.is_dir()
+            && !roots
+                .iter()
+                .any(|root| library_root.starts_with(&root.path))
+        {
+            scanned.push(library_root);
+        }
+    }
+    Ok(scanned)
+}
+
+/// Finds folders with images and archive/PDF files under `root` that no work points at.
+/// Registered folder works are not descended into, and folders in `owned` are
+/// not reported.
+fn find_orphans(
+    root: &Path,
+    registered: &HashSet<&str>,
+    owned: &HashSet<&str>,
+    on_progress: &Channel<DoctorProgress>,
+) -> Vec<OrphanedEntry> {
+    let mut orphans = Vec::new();
+    let mut scanned_dirs = 0usize;
+
+    let walker = WalkDir::new(root).into_iter().filter_entry(|e| {
+        !(e.file_type().is_dir() && registered.contains(&*e.path().to_string_lossy()))
+    });
+    for entry in walker.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let work_type = if entry.file_type().is_dir() {
+            scanned_dirs += 1;
+            if scanned_dirs.is_multiple_of(50) {
+                let _ = on_progress.send(DoctorProgress::Scanning { scanned_dirs });
+            }
+            match importer::list_images_in_folder(path) {
+                Ok(images) if !images.is_empty() => "folder",
+                _ => continue,
+            }
+        } else if scanner::is_archive_file(path) {
+            "archive"
+        } else if scanner::is_pdf_file(path) {
+            "pdf"
+        } else {
+            continue;
+        };
+
+        let path_str = path.to_string_lossy().to_string();
+        if !registered.contains(path_str.as_str()) && !owned.contains(path_str.as_str()) {
+            orphans.push(OrphanedEntry {
+                path: path_str,
+                work_type: work_type.to_string(),
+            });
+        }
+    }
+    orphans
+}
+
+/// Checks every registered work against the disk and scans the mounted roots
+/// for unregistered works. Nothing is written; present works missing content or
+/// page hashes are listed for `backfill_hashes`.
+pub fn run_doctor(
+    app_data_dir: &Path,
+    on_progress: &Channel<DoctorProgress>,
+) -> Result<DoctorReport, AppError> {
+    let conn = db::open_db(app_data_dir)?;
+    let works = list_registered_works(&conn)?;
+    let total = works.len();
+    let mut report = DoctorReport::default();
+    let mut missing_works = Vec::new();
+
+    for (i, work) in works.iter().enumerate() {
+        let _ = on_progress.send(DoctorProgress::Checking {
+            current: i + 1,
+            total,
+        });
+
+        if work.offline {
+            continue;
+        }
+        let path = Path::new(&work.path);
+        if !work_exists(path, &work.work_type) {
+            missing_works.push(work);
+            continue;
+        }
+
+        let needs_page_hashes = work.work_type != "pdf" && !dedup::has_page_hashes(&conn, work.id)?;
+        if work.content_hash.is_none() || needs_page_hashes {
+            report.unhashed.push(work.id);
+        }
+
+        if let Ok(actual) = count_pages(path, &work.work_type) {
+            if actual as i32 != work.page_count {
+                report.page_count_mismatches.push(PageCountMismatch {
+                    work_id: work.id,
+                    title: work.title.clone(),
+                    path: work.path.clone(),
+                    recorded: work.page_count,
+                    actual,
+                });
+            }
+        }
+    }
+
+    let registered: HashSet<&str> = works.iter().map(|w| w.path.as_str()).collect();
+    // A single-image work lives in a folder that would otherwise look like an
+    // unregistered folder work.
+    let owned: HashSet<&str> = works
+        .iter()
+        .filter(|w| w.work_type == "image")
+        .filter_map(|w| Path::new(&w.path).parent()?.to_str())
+        .collect();
+    for root in scan_roots(&conn)? {
+        report
+            .orphaned
+            .extend(find_orphans(&root, &registered, &owned, on_progress));
+    }
+
+    // Only hash orphans when some missing work could actually match one.
+    let wanted: HashSet<&str> = missing_works
+        .iter()
+        .filter_map(|w| w.content_hash.as_deref())
+        .collect();
+    let mut orphan_hashes: HashMap<(String, String), String> = HashMap::new();
+    if !wanted.is_empty() {
+        for orphan in &report.orphaned {
+            if let Ok(hash) = hashing::hash_work_content(Path::new(&orphan.path), &orphan.work_type)
+            {
+                if wanted.contains(hash.as_str()) {
+                    orphan_hashes
+                        .entry((orphan.work_type.clone(), hash))
+                        .or_insert_with(|| orphan.path.clone());
+                }
+            }
+        }
+    }
+
+    for work in missing_works {
+        let relink_candidate = work.content_hash.as_ref().and_then(|hash| {
+            orphan_hashes
+                .get(&(work.work_type.clone(), hash.clone()))
+                .cloned()
+        });
+        report.missing.push(MissingWork {
+            work_id: work.id,
+            title: work.title.clone(),
+            path: work.path.clone(),
+            relink_candidate,
+        });
+    }
+
+    let _ = on_progress.send(DoctorProgress::Completed {
+        missing: report.missing.len(),
+        orphaned: report.orphaned.len(),
+        mismatched: report.page_count_mismatches.len(),
+    });
+    Ok(report)
+}
+
+/// Points a work at a new location, then refreshes its page count and content hash.
+pub fn relink_work(
+    conn: &Connection,
+    work_id: i64,
+    new_path: &Path,
+) -> Result<WorkDetail, AppError> {
+    let work = db::get_work(conn, work_id)?;
+    if !work_exists(new_path, &work.work_type) {
+        return Err(AppError::WorkError(format!(
+            "再リンク先が見つかりません: {}",
+            new_path.display()
+        )));
+    }
+    let new_path_str = new_path.to_string_lossy().to_string();
+    if new_path_str != work.path && db::path_exists(conn, &new_path_str)? {
+        return Err(AppError::WorkError(format!(
+            "既に登録されているパスです: {}",
+            new_path_str
+        )));
+    }
+
+    let page_count = count_pages(new_path, &work.work_type)?;
+    let hashes = hashing::hash_work(new_path, &work.work_type)?;
+    db::update_work_path(conn, work_id, &new_path_str)?;
+    db::update_page_count(conn, work_id, page_count as i32)?;
+    dedup::store_work_hashes(conn, work_id, &hashes)?;
+    db::get_work(conn, work_id)
+}
+
+/// Re-reads page counts (and content and page hashes) from disk for the given works.
+pub fn recount_pages(conn: &mut Connection, work_ids: &[i64]) -> Result<(), AppError> {
+    let tx = conn.transaction()?;
+    for &work_id in work_ids {
+        let work = db::get_work(&tx, work_id)?;
+        let path = Path::new(&work.path);
+        let page_count = count_pages(path, &work.work_type)?;
+        db::update_page_count(&tx, work_id, page_count as i32)?;
+        dedup::store_work_hashes(&tx, work_id, &hashing::hash_work(path, &work.work_type)?)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Fills in content and page hashes for the given works so they can be re-linked
+/// or matched as duplicates later. Works no longer on disk are skipped.
+pub fn backfill_hashes(conn: &mut Connection, work_ids: &[i64]) -> Result<(), AppError> {
+    let tx = conn.transaction()?;
+    for &work_id in work_ids {
+        let work = db::get_work(&tx, work_id)?;
+        if let Ok(hashes) = hashing::hash_work(Path::new(&work.path), &work.work_type) {
+            dedup::store_work_hashes(&tx, work_id, &hashes)?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "tests/doctor.rs"]
+mod tests;
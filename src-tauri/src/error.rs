@@ -11,6 +11,12 @@ pub enum AppError {
     #[error("Database error: {0}")]
     Database(#[from] rusqlite::Error),
 
+    #[error("Archive error: {0}")]
+    Archive(#[from] zip::result::ZipError),
+
+    #[error("PDF error: {0}")]
+    Pdf(String),
+
     #[error("WebP encode failed")]
     WebpEncode,
 
@@ -25,4 +31,31 @@ pub enum AppError {
 
     #[error("Relocation error: {0}")]
     RelocationError(String),
+
+    #[error("Work error: {0}")]
+    WorkError(String),
+
+    #[error("Import job error: {0}")]
+    ImportJobError(String),
+
+    #[error("Transfer error: {0}")]
+    TransferError(String),
+
+    #[error("Watch error: {0}")]
+    WatchError(String),
+
+    #[error("Tag error: {0}")]
+    TagError(String),
+
+    #[error("Playlist error: {0}")]
+    PlaylistError(String),
+
+    #[error("Search error: {0}")]
+    SearchError(String),
+
+    #[error("Folder name rule error: {0}")]
+    NameRuleError(String),
+
+    #[error("Library root error: {0}")]
+    LibraryRootError(String),
 }
@@ -0,0 +1,103 @@
+use std::io::Read;
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use crate::archive;
+use crate::error::AppError;
+use crate::importer;
+
+pub struct WorkHashes {
+    pub content_hash: String,
+    /// One hash per page in page order. Empty for PDFs, whose pages are not files.
+    pub page_hashes: Vec<String>,
+}
+
+/// Feeds everything from `reader` into each of `hashers`.
+fn update_from_reader(reader: &mut dyn Read, hashers: &mut [&mut Sha256]) -> Result<(), AppError> {
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        for hasher in hashers.iter_mut() {
+            hasher.update(&buf[..read]);
+        }
+    }
+    Ok(())
+}
+
+fn to_hex(hasher: Sha256) -> String {
+    format!("{:x}", hasher.finalize())
+}
+
+pub(crate) fn hash_file(path: &Path) -> Result<String, AppError> {
+    let mut hasher = Sha256::new();
+    update_from_reader(&mut std::fs::File::open(path)?, &mut [&mut hasher])?;
+    Ok(to_hex(hasher))
+}
+
+/// Hashes a work's contents and each of its pages in a single read.
+///
+/// The content hash is SHA-256 as lowercase hex. Folder works hash their images
+/// in page order, each prefixed by its length, so renaming pages or the folder
+/// keeps the hash while reordering or editing pages changes it. Single-file
+/// works hash the file itself.
+pub fn hash_work(path: &Path, work_type: &str) -> Result<WorkHashes, AppError> {
+    match work_type {
+        "folder" => {
+            let mut work_hasher = Sha256::new();
+            let mut page_hashes = Vec::new();
+            for image in importer::list_images_in_folder(path)? {
+                work_hasher.update(std::fs::metadata(&image)?.len().to_le_bytes());
+                let mut page_hasher = Sha256::new();
+                update_from_reader(
+                    &mut std::fs::File::open(&image)?,
+                    &mut [&mut work_hasher, &mut page_hasher],
+                )?;
+                page_hashes.push(to_hex(page_hasher));
+            }
+            Ok(WorkHashes {
+                content_hash: to_hex(work_hasher),
+                page_hashes,
+            })
+        }
+        "archive" => {
+            let mut page_hashes = Vec::new();
+            archive::visit_archive_pages(path, |reader| {
+                let mut page_hasher = Sha256::new();
+                update_from_reader(reader, &mut [&mut page_hasher])?;
+                page_hashes.push(to_hex(page_hasher));
+                Ok(())
+            })?;
+            Ok(WorkHashes {
+                content_hash: hash_file(path)?,
+                page_hashes,
+            })
+        }
+        "pdf" => Ok(WorkHashes {
+            content_hash: hash_file(path)?,
+            page_hashes: Vec::new(),
+        }),
+        _ => {
+            let content_hash = hash_file(path)?;
+            Ok(WorkHashes {
+                page_hashes: vec![content_hash.clone()],
+                content_hash,
+            })
+        }
+    }
+}
+
+/// The content hash alone; see [`hash_work`].
+pub fn hash_work_content(path: &Path, work_type: &str) -> Result<String, AppError> {
+    if work_type == "folder" {
+        return Ok(hash_work(path, work_type)?.content_hash);
+    }
+    hash_file(path)
+}
+
+#[cfg(test)]
+#[path = "tests/hashing.rs"]
+mod tests;
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::error::AppError;
+use crate::importer::ImportRequest;
+
+/// Cancel flags for jobs running in this process, keyed by job id. A job whose
+/// row says `running` but has no flag here was interrupted by a restart.
+static ACTIVE_JOBS: OnceLock<Mutex<HashMap<i64, Arc<AtomicBool>>>> = OnceLock::new();
+
+fn active_jobs() -> &'static Mutex<HashMap<i64, Arc<AtomicBool>>> {
+    ACTIVE_JOBS.get_or_init(Default::default)
+}
+
+/// Marks the job as running in this process and returns its cancel flag.
+/// Fails if the job is already running.
+pub fn activate(job_id: i64) -> Result<Arc<AtomicBool>, AppError> {
+    let mut jobs = active_jobs().lock().unwrap_or_else(|e| e.into_inner());
+    if jobs.contains_key(&job_id) {
+        return Err(AppError::ImportJobError(
+            "この取り込みジョブは実行中です".to_string(),
+        ));
+    }
+    let flag = Arc::new(AtomicBool::new(false));
+    jobs.insert(job_id, flag.clone());
+    Ok(flag)
+}
+
+pub fn deactivate(job_id: i64) {
+    let mut jobs = active_jobs().lock().unwrap_or_else(|e| e.into_inner());
+    jobs.remove(&job_id);
+}
+
+fn is_active(job_id: i64) -> bool {
+    let jobs = active_jobs().lock().unwrap_or_else(|e| e.into_inner());
+    jobs.contains_key(&job_id)
+}
+
+/// Asks a running job to stop after the item it is currently importing.
+pub fn request_cancel(job_id: i64) -> Result<(), AppError> {
+    let jobs = active_jobs().lock().unwrap_or_else(|e| e.into_inner());
+    let flag = jobs.get(&job_id).ok_or(AppError::NotFound)?;
+    flag.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportJob {
+    pub id: i64,
+    pub status: String,
+    /// Whether the job is running in this process right now.
+    pub active: bool,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub created_at: String,
+}
+
+pub struct PendingItem {
+    pub id: i64,
+    pub request: ImportRequest,
+}
+
+fn job_error(e: serde_json::Error) -> AppError {
+    AppError::ImportJobError(e.to_string())
+}
+
+pub fn create_job(conn: &mut Connection, requests: &[ImportRequest]) -> Result<i64, AppError> {
+    let tx = conn.transaction()?;
+    tx.execute("INSERT INTO import_jobs DEFAULT VALUES", [])?;
+    let job_id = tx.last_insert_rowid();
+    {
+        let mut stmt = tx.prepare_cached(
+            "INSERT INTO import_job_items (job_id, position, request) VALUES (?1, ?2, ?3)",
+        )?;
+        for (position, request) in requests.iter().enumerate() {
+            let json = serde_json::to_string(request).map_err(job_error)?;
+            stmt.execute(rusqlite::params![job_id, position as i64, json])?;
+        }
+    }
+    tx.commit()?;
+    Ok(job_id)
+}
+
+pub fn get_job(conn: &Connection, job_id: i64) -> Result<ImportJob, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT j.id, j.status, COUNT(i.id), COUNT(CASE WHEN i.status = 'succeeded' THEN 1 END), COUNT(CASE WHEN i.status = 'failed' THEN 1 END), j.created_at FROM import_jobs j LEFT JOIN import_job_items i ON i.job_id = j.id WHERE j.id = ?1 GROUP BY j.id",
+    )?;
+    stmt.query_row([job_id], |row| {
+        Ok(ImportJob {
+            id: row.get(0)?,
+            status: row.get(1)?,
+            active: false,
+            total: row.get::<_, i64>(2)? as usize,
+            succeeded: row.get::<_, i64>(3)? as usize,
+            failed: row.get::<_, i64>(4)? as usize,
+            created_at: row.get(5)?,
+        })
+    })
+    .map(|job| ImportJob {
+        active: is_active(job.id),
+        ..job
+    })
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => AppError::NotFound,
+        other => AppError::Database(other),
+    })
+}
+
+/// Jobs that still have work left: running (possibly interrupted) or cancelled.
+pub fn list_unfinished_jobs(conn: &Connection) -> Result<Vec<ImportJob>, AppError> {
+    let ids: Vec<i64> = {
+        let mut stmt = conn
+            .prepare_cached("SELECT id FROM import_jobs WHERE status != 'completed' ORDER BY id")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    ids.into_iter().map(|id| get_job(conn, id)).collect()
+}
+
+pub fn pending_items(conn: &Connection, job_id: i64) -> Result<Vec<PendingItem>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, request FROM import_job_items WHERE job_id = ?1 AND status = 'pending' ORDER BY position",
+    )?;
+    let rows = stmt.query_map([job_id], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+    })?;
+    let mut items = Vec::new();
+    for row in rows {
+        let (id, json) = row?;
+        items.push(PendingItem {
+            id,
+            request: serde_json::from_str(&json).map_err(job_error)?,
+        });
+    }
+    Ok(items)
+}
+
+pub fn finish_item(conn: &Connection, item_id: i64, error: Option<&str>) -> Result<(), AppError> {
+    let status = if error.is_some() {
+        "failed"
+    } else {
+        "succeeded"
+    };
+    conn.execute(
+        "UPDATE import_job_items SET status = ?1, error = ?2 WHERE id = ?3",
+        rusqlite::params![status, error, item_id],
+    )?;
+    Ok(())
+}
+
+pub fn set_job_status(conn: &Connection, job_id: i64, status: &str) -> Result<(), AppError> {
+    let updated = conn.execute(
+        "UPDATE import_jobs SET status = ?1, updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = ?2",
+        rusqlite::params![status, job_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+pub fn delete_job(conn: &Connection, job_id: i64) -> Result<(), AppError> {
+    if is_active(job_id) {
+        return Err(AppError::ImportJobError(
+            "この取り込みジョブは実行中です".to_string(),
+        ));
+    }
+    let deleted = conn.execute("DELETE FROM import_jobs WHERE id = ?1", [job_id])?;
+    if deleted == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "tests/import_jobs.rs"]
+mod tests;
@@ -1,17 +1,28 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
 
 use serde::{Deserialize, Serialize};
 use tauri::ipc::Channel;
 use walkdir::WalkDir;
 
+use crate::archive;
 use crate::db::{self, WorkRecord};
+use crate::dedup::{self, DuplicateMatch};
 use crate::error::AppError;
+use crate::hashing::{self, WorkHashes};
+use crate::import_jobs;
+use crate::journal::{self, FileOperation, OperationKind};
+use crate::name_rules::FolderNameParser;
+use crate::pdf;
 use crate::scanner;
-use crate::settings;
+use crate::settings::{self, ExtraFiles};
 use crate::template::{self, WorkMetadata};
 use crate::thumbnail;
+use crate::transfer::{self, MoveStrategy};
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ImportRequest {
     pub source_path: String,
@@ -22,6 +33,9 @@ pub struct ImportRequest {
     pub circle: Option<String>,
     pub origin: Option<String>,
     pub mode: ImportMode,
+    /// Import even when a registered work has exactly the same pages.
+    #[serde(default)]
+    pub allow_duplicates: bool,
 }
 
 #[derive(Serialize)]
@@ -29,53 +43,107 @@ pub struct ImportRequest {
 pub struct ImportResult {
     pub destination_path: String,
     pub page_count: usize,
+    /// Registered works sharing pages with the imported one.
+    pub duplicates: Vec<DuplicateMatch>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+/// Metadata read from a folder name. Everything but the title is only set
+/// when the matching rule captured it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ParsedMetadata {
     pub title: String,
     pub artist: Option<String>,
+    pub circle: Option<String>,
+    pub year: Option<i32>,
+    pub genre: Option<String>,
+    pub origin: Option<String>,
+    pub event: Option<String>,
+    pub language: Option<String>,
 }
 
-#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum ImportMode {
     Copy,
     Move,
+    /// Registers a work that already sits under the library root where it is.
+    Adopt,
+    /// Registers a work anywhere as an external one: it is indexed where it is,
+    /// and relocation and deletion never touch its files.
+    Reference,
+}
+
+impl ImportMode {
+    /// Whether the work is registered at its source path instead of being
+    /// transferred into the library.
+    fn in_place(self) -> bool {
+        matches!(self, ImportMode::Adopt | ImportMode::Reference)
+    }
 }
 
+/// Reads a folder name with the built-in rules only. Imports use the rules
+/// configured in the database through `FolderNameParser::load`.
 pub fn parse_folder_name(folder_name: &str) -> ParsedMetadata {
-    // Pattern: [artist] title
-    if let Some(rest) = folder_name.strip_prefix('[') {
-        if let Some(close) = rest.find(']') {
-            let artist = rest[..close].trim();
-            let title = rest[close + 1..].trim();
-            if !artist.is_empty() && !title.is_empty() {
-                return ParsedMetadata {
-                    title: title.to_string(),
-                    artist: Some(artist.to_string()),
-                };
-            }
+    FolderNameParser::builtin().parse(folder_name).metadata
+}
+
+impl From<WorkMetadata> for ParsedMetadata {
+    fn from(metadata: WorkMetadata) -> Self {
+        ParsedMetadata {
+            title: metadata.title,
+            artist: metadata.artist,
+            circle: metadata.circle,
+            year: metadata.year,
+            genre: metadata.genre,
+            origin: metadata.origin,
+            event: None,
+            language: None,
         }
     }
+}
 
-    // Pattern: artist - title
-    if let Some(sep_pos) = folder_name.find(" - ") {
-        let artist = folder_name[..sep_pos].trim();
-        let title = folder_name[sep_pos + 3..].trim();
-        if !artist.is_empty() && !title.is_empty() {
-            return ParsedMetadata {
-                title: title.to_string(),
-                artist: Some(artist.to_string()),
-            };
-        }
+/// The work type a source path would be imported as.
+fn source_work_type(source: &Path) -> &'static str {
+    if source.is_file() && scanner::is_archive_file(source) {
+        "archive"
+    } else if source.is_file() && scanner::is_pdf_file(source) {
+        "pdf"
+    } else {
+        "folder"
     }
+}
 
-    ParsedMetadata {
-        title: folder_name.to_string(),
-        artist: None,
+/// Reads the metadata of a work to import. A work under the library root is
+/// read back from its path with the template of its type; anything else, or a
+/// path that does not fit the template, from its name.
+pub fn read_source_metadata(
+    conn: &rusqlite::Connection,
+    parser: &FolderNameParser,
+    source: &Path,
+) -> Result<ParsedMetadata, AppError> {
+    let work_type = source_work_type(source);
+    let base = if work_type == "folder" {
+        source.to_path_buf()
+    } else {
+        source.with_extension("")
+    };
+    if let (Some(library_root), Some(template_str)) = (
+        settings::get_library_root(conn)?,
+        settings::get_effective_template(conn, work_type)?,
+    ) {
+        if let Some(metadata) =
+            template::parse_work_path(Path::new(&library_root), &template_str, &base)
+        {
+            return Ok(metadata.into());
+        }
     }
+    let name = base
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    Ok(parser.parse(&name).metadata)
 }
 
 pub fn list_images_in_folder(folder_path: &Path) -> Result<Vec<PathBuf>, AppError> {
@@ -92,40 +160,65 @@ pub fn list_images_in_folder(folder_path: &Path) -> Result<Vec<PathBuf>, AppErro
     Ok(images)
 }
 
+/// The path a work would be imported to, before any collision suffix. Single-file
+/// works keep `extension`; folders pass an empty one.
 pub fn preview_import_path(
     library_root: &Path,
     template_str: &str,
     metadata: &WorkMetadata,
+    extension: &str,
 ) -> String {
     let path = template::resolve_work_path(library_root, template_str, metadata);
-    path.to_string_lossy().to_string()
-}
-
-pub fn import_work(request: &ImportRequest, app_data_dir: &Path) -> Result<ImportResult, AppError> {
-    let source = Path::new(&request.source_path);
-    if !source.is_dir() {
-        return Err(AppError::ImportError(
-            "ソースパスがディレクトリではありません".to_string(),
-        ));
-    }
-
-    let images = list_images_in_folder(source)?;
-    if images.is_empty() {
-        return Err(AppError::ImportError(
-            "フォルダ内に画像ファイルがありません".to_string(),
-        ));
+    if extension.is_empty() {
+        path.to_string_lossy().to_string()
+    } else {
+        format!("{}.{}", path.to_string_lossy(), extension)
     }
+}
 
-    let conn = db::open_db(app_data_dir)?;
+/// Previews where `source` would be imported with the template and type label
+/// of its work type, as `import_work` would place it.
+pub fn preview_source_destination(
+    conn: &rusqlite::Connection,
+    source: &Path,
+    metadata: &WorkMetadata,
+) -> Result<String, AppError> {
+    let work_type = source_work_type(source);
+    let (library_root, template_str) = load_destination_settings(conn, work_type)?;
+    let metadata = WorkMetadata {
+        work_type: Some(settings::resolve_type_label(conn, work_type)?),
+        ..metadata.clone()
+    };
+    let extension = if work_type == "folder" {
+        String::new()
+    } else {
+        source
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default()
+    };
+    Ok(preview_import_path(
+        Path::new(&library_root),
+        &template_str,
+        &metadata,
+        &extension,
+    ))
+}
 
-    let library_root = settings::get_library_root(&conn)?
+fn load_destination_settings(
+    conn: &rusqlite::Connection,
+    work_type: &str,
+) -> Result<(String, String), AppError> {
+    let library_root = settings::get_library_root(conn)?
         .ok_or_else(|| AppError::ImportError("ライブラリルートが設定されていません".to_string()))?;
-    let template_str = settings::get_directory_template(&conn)?.ok_or_else(|| {
+    let template_str = settings::get_effective_template(conn, work_type)?.ok_or_else(|| {
         AppError::ImportError("ディレクトリテンプレートが設定されていません".to_string())
     })?;
+    Ok((library_root, template_str))
+}
 
-    let type_label = settings::resolve_type_label(&conn, "folder")?;
-    let metadata = WorkMetadata {
+fn request_to_metadata(request: &ImportRequest, type_label: String) -> WorkMetadata {
+    WorkMetadata {
         title: request.title.clone(),
         artist: request.artist.clone(),
         year: request.year,
@@ -133,64 +226,437 @@ pub fn import_work(request: &ImportRequest, app_data_dir: &Path) -> Result<Impor
         circle: request.circle.clone(),
         origin: request.origin.clone(),
         work_type: Some(type_label),
-    };
+    }
+}
 
-    let dest =
-        template::resolve_unique_work_path(Path::new(&library_root), &template_str, &metadata);
+/// Looks up registered works with the same pages. Exact duplicates are refused
+/// unless the request allows them; partial overlaps are returned as warnings.
+fn check_duplicates(
+    conn: &rusqlite::Connection,
+    request: &ImportRequest,
+    hashes: &WorkHashes,
+) -> Result<Vec<DuplicateMatch>, AppError> {
+    let duplicates = dedup::find_duplicates(conn, hashes)?;
+    if !request.allow_duplicates {
+        if let Some(exact) = duplicates.iter().find(|d| d.exact) {
+            return Err(AppError::ImportError(format!(
+                "既に登録されている作品と同じ内容です: {}",
+                exact.title
+            )));
+        }
+    }
+    Ok(duplicates)
+}
+
+fn register_work(
+    conn: &rusqlite::Connection,
+    record: &WorkRecord,
+    hashes: &WorkHashes,
+    operation_id: Option<i64>,
+) -> Result<(), AppError> {
+    let tx = conn.unchecked_transaction()?;
+    db::insert_work(&tx, record)?;
+    let work_id = tx.last_insert_rowid();
+    dedup::store_work_hashes(&tx, work_id, hashes)?;
+    db::set_perceptual_hash(
+        &tx,
+        work_id,
+        thumbnail::perceptual_hash(record.thumbnail).ok(),
+    )?;
+    if let Some(operation_id) = operation_id {
+        journal::mark_committed(&tx, operation_id, work_id)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Where staging journals its copies: the import's own connection, or the bulk
+/// import writer when staging runs on a worker.
+trait ImportJournal {
+    fn begin(&self, operation: FileOperation) -> Result<i64, AppError>;
+    fn complete(&self, operation_id: i64);
+}
+
+impl ImportJournal for rusqlite::Connection {
+    fn begin(&self, operation: FileOperation) -> Result<i64, AppError> {
+        journal::begin(self, &operation)
+    }
+
+    fn complete(&self, operation_id: i64) {
+        // A leftover entry is harmless; startup recovery clears it.
+        let _ = journal::complete(self, operation_id);
+    }
+}
+
+/// An import whose files are already copied (or renamed) into the library but
+/// which is not registered yet. An adopted work stays where it is, so its
+/// source and destination are the same and nothing is journaled.
+struct StagedImport {
+    work_type: &'static str,
+    source: PathBuf,
+    dest: PathBuf,
+    page_count: usize,
+    thumbnail: Vec<u8>,
+    hashes: WorkHashes,
+    delete_source: bool,
+    strategy: MoveStrategy,
+    in_place: bool,
+    operation_id: i64,
+}
+
+impl StagedImport {
+    fn operation(&self) -> FileOperation {
+        FileOperation {
+            kind: OperationKind::Import,
+            work_id: None,
+            source: self.source.clone(),
+            destination: self.dest.clone(),
+            work_type: self.work_type.to_string(),
+            delete_source: self.delete_source,
+            job_item_id: None,
+            strategy: self.strategy,
+        }
+    }
+
+    /// Journals the transfer into the claimed destination and carries it out:
+    /// a rename when the strategy allows, otherwise (or if renaming fails) `copy`.
+    fn start(
+        mut self,
+        journal: &dyn ImportJournal,
+        copy: impl FnOnce(&Path) -> Result<(), AppError>,
+    ) -> Result<Self, AppError> {
+        if paths_overlap(&self.source, &self.dest) {
+            self.remove_copy();
+            return Err(AppError::ImportError(
+                "取り込み元と取り込み先が重複しています".to_string(),
+            ));
+        }
+        match journal.begin(self.operation()) {
+            Ok(operation_id) => self.operation_id = operation_id,
+            Err(e) => {
+                self.remove_copy();
+                return Err(e);
+            }
+        }
 
-    if paths_overlap(source, &dest) {
+        if self.strategy == MoveStrategy::Rename {
+            if transfer::rename_onto(&self.source, &self.dest).is_ok() {
+                return Ok(self);
+            }
+            // Most likely another filesystem after all; copy under a fresh entry.
+            journal.complete(self.operation_id);
+            self.strategy = MoveStrategy::Copy;
+            match journal.begin(self.operation()) {
+                Ok(operation_id) => self.operation_id = operation_id,
+                Err(e) => {
+                    self.remove_copy();
+                    return Err(e);
+                }
+            }
+        }
+
+        // Always copy first (even in Move mode) to avoid data loss on failure
+        if let Err(e) = copy(&self.dest) {
+            self.abandon(journal);
+            return Err(e);
+        }
+        Ok(self)
+    }
+
+    fn remove_copy(&self) {
+        if self.work_type == "folder" {
+            let _ = std::fs::remove_dir_all(&self.dest);
+        } else {
+            let _ = std::fs::remove_file(&self.dest);
+        }
+    }
+
+    fn abandon(&self, journal: &dyn ImportJournal) {
+        if self.in_place {
+            return;
+        }
+        match self.strategy {
+            MoveStrategy::Rename => {
+                // If the work cannot be put back, leave the entry for startup recovery.
+                if std::fs::rename(&self.dest, &self.source).is_err() {
+                    return;
+                }
+            }
+            MoveStrategy::Copy => self.remove_copy(),
+        }
+        journal.complete(self.operation_id);
+    }
+
+    // Delete source files only after successful DB registration
+    fn finish(&self, journal: &dyn ImportJournal) {
+        if self.in_place {
+            return;
+        }
+        if self.delete_source && self.strategy == MoveStrategy::Copy {
+            journal::remove_copied_source(&self.source, &self.dest, self.work_type);
+        }
+        journal.complete(self.operation_id);
+    }
+}
+
+/// Moves on the same filesystem are renamed, provided the rename takes along
+/// nothing that a copy would leave behind.
+fn choose_strategy(
+    request: &ImportRequest,
+    source: &Path,
+    dest: &Path,
+    whole: bool,
+) -> MoveStrategy {
+    let same_filesystem = dest
+        .parent()
+        .is_some_and(|parent| transfer::same_filesystem(source, parent));
+    if request.mode == ImportMode::Move && whole && same_filesystem {
+        MoveStrategy::Rename
+    } else {
+        MoveStrategy::Copy
+    }
+}
+
+pub fn import_work(request: &ImportRequest, app_data_dir: &Path) -> Result<ImportResult, AppError> {
+    let conn = db::open_db(app_data_dir)?;
+    let staged = stage_import(&conn, request, &conn)?;
+    let result = commit_import(&conn, request, &staged);
+    settle_import(&conn, &staged, &result);
+    result
+}
+
+/// Reads the source, refuses exact duplicates and copies it to a newly claimed
+/// destination. Only reads from the database, so any number of imports can be
+/// staged at once.
+fn stage_import(
+    conn: &rusqlite::Connection,
+    request: &ImportRequest,
+    journal: &dyn ImportJournal,
+) -> Result<StagedImport, AppError> {
+    let source = Path::new(&request.source_path);
+    if source.is_file() {
+        if scanner::is_archive_file(source) {
+            return stage_file_work(conn, request, journal, source, "archive");
+        }
+        if scanner::is_pdf_file(source) {
+            return stage_file_work(conn, request, journal, source, "pdf");
+        }
+    }
+    if !source.is_dir() {
         return Err(AppError::ImportError(
-            "取り込み元と取り込み先が重複しています".to_string(),
+            "ソースパスがディレクトリではありません".to_string(),
         ));
     }
 
-    let thumb = thumbnail::generate_thumbnail(&images[0])?;
+    let images = list_images_in_folder(source)?;
+    if images.is_empty() {
+        return Err(AppError::ImportError(
+            "フォルダ内に画像ファイルがありません".to_string(),
+        ));
+    }
 
-    std::fs::create_dir_all(&dest)?;
+    let hashes = hashing::hash_work(source, "folder")?;
+    check_duplicates(conn, request, &hashes)?;
 
-    let rollback = |dest: &Path| {
-        let _ = std::fs::remove_dir_all(dest);
-    };
+    let thumb = thumbnail::generate_thumbnail(&images[0])?;
+    if request.mode.in_place() {
+        return stage_in_place(conn, request, "folder", source, images.len(), thumb, hashes);
+    }
 
-    // Always copy first (even in Move mode) to avoid data loss on failure
-    if let Err(e) = copy_images_to_dest(&images, &dest) {
-        rollback(&dest);
-        return Err(e);
+    let (library_root, template_str) = load_destination_settings(conn, "folder")?;
+    let type_label = settings::resolve_type_label(conn, "folder")?;
+    let metadata = request_to_metadata(request, type_label);
+    let files = list_work_files(source, &settings::get_extra_files(conn)?)?;
+
+    let dest =
+        template::claim_unique_work_path(Path::new(&library_root), &template_str, &metadata)?;
+    let whole = transfer::carries_whole_folder(source, &files);
+    let verify = request.mode == ImportMode::Move;
+    StagedImport {
+        work_type: "folder",
+        source: source.to_path_buf(),
+        strategy: choose_strategy(request, source, &dest, whole),
+        dest,
+        page_count: images.len(),
+        thumbnail: thumb,
+        hashes,
+        delete_source: request.mode == ImportMode::Move,
+        in_place: false,
+        operation_id: 0,
     }
+    .start(journal, |dest| {
+        copy_work_files(source, &files, dest, verify)
+    })
+}
 
-    let dest_str = dest.to_string_lossy().to_string();
-    let page_count = images.len();
+/// Registers a staged import. Duplicates are checked again here because another
+/// import with the same pages may have been registered since it was staged.
+fn commit_import(
+    conn: &rusqlite::Connection,
+    request: &ImportRequest,
+    staged: &StagedImport,
+) -> Result<ImportResult, AppError> {
+    let duplicates = check_duplicates(conn, request, &staged.hashes)?;
+    let dest_str = staged.dest.to_string_lossy().to_string();
 
-    if let Err(e) = db::insert_work(
-        &conn,
+    register_work(
+        conn,
         &WorkRecord {
             title: &request.title,
             path: &dest_str,
-            work_type: "folder",
-            page_count: page_count as i32,
-            thumbnail: &thumb,
+            work_type: staged.work_type,
+            page_count: staged.page_count as i32,
+            thumbnail: &staged.thumbnail,
             artist: request.artist.as_deref(),
             year: request.year,
             genre: request.genre.as_deref(),
             circle: request.circle.as_deref(),
             origin: request.origin.as_deref(),
+            content_hash: Some(&staged.hashes.content_hash),
+            external: request.mode == ImportMode::Reference,
         },
-    ) {
-        rollback(&dest);
-        return Err(e);
+        &staged.hashes,
+        (!staged.in_place).then_some(staged.operation_id),
+    )?;
+
+    Ok(ImportResult {
+        destination_path: dest_str,
+        page_count: staged.page_count,
+        duplicates,
+    })
+}
+
+/// Removes the copy when registration failed, or the source when a move succeeded.
+fn settle_import(
+    conn: &rusqlite::Connection,
+    staged: &StagedImport,
+    result: &Result<ImportResult, AppError>,
+) {
+    match result {
+        Ok(_) => staged.finish(conn),
+        Err(_) => staged.abandon(conn),
     }
+}
 
-    // Delete source files only after successful DB registration
-    if request.mode == ImportMode::Move {
-        for image in &images {
-            let _ = std::fs::remove_file(image);
+/// Counts the pages of a single-file work and renders its thumbnail from the first page.
+pub(crate) fn inspect_file_work(
+    source: &Path,
+    work_type: &str,
+) -> Result<(usize, Vec<u8>), AppError> {
+    if work_type == "pdf" {
+        let page_count = pdf::page_count(source)?;
+        if page_count == 0 {
+            return Err(AppError::ImportError(
+                "PDF にページがありません".to_string(),
+            ));
         }
-        let _ = std::fs::remove_dir(source);
+        let first_page = pdf::render_first_page(source)?;
+        let thumb = thumbnail::generate_thumbnail_from_image(&first_page)?;
+        return Ok((page_count, thumb));
     }
 
-    Ok(ImportResult {
-        destination_path: dest_str,
+    let pages = archive::list_archive_pages(source)?;
+    if pages.is_empty() {
+        return Err(AppError::ImportError(
+            "アーカイブ内に画像ファイルがありません".to_string(),
+        ));
+    }
+    let (_, first_page) = archive::read_archive_page(source, 0)?;
+    let thumb = thumbnail::generate_thumbnail_from_bytes(&first_page)?;
+    Ok((pages.len(), thumb))
+}
+
+fn stage_file_work(
+    conn: &rusqlite::Connection,
+    request: &ImportRequest,
+    journal: &dyn ImportJournal,
+    source: &Path,
+    work_type: &'static str,
+) -> Result<StagedImport, AppError> {
+    let (page_count, thumb) = inspect_file_work(source, work_type)?;
+
+    let hashes = hashing::hash_work(source, work_type)?;
+    check_duplicates(conn, request, &hashes)?;
+    if request.mode.in_place() {
+        return stage_in_place(conn, request, work_type, source, page_count, thumb, hashes);
+    }
+
+    let (library_root, template_str) = load_destination_settings(conn, work_type)?;
+    let type_label = settings::resolve_type_label(conn, work_type)?;
+    let metadata = request_to_metadata(request, type_label);
+
+    let extension = source
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let dest = template::claim_unique_file_path(
+        Path::new(&library_root),
+        &template_str,
+        &metadata,
+        &extension,
+    )?;
+    StagedImport {
+        work_type,
+        source: source.to_path_buf(),
+        strategy: choose_strategy(request, source, &dest, true),
+        dest,
+        page_count,
+        thumbnail: thumb,
+        hashes,
+        delete_source: request.mode == ImportMode::Move,
+        in_place: false,
+        operation_id: 0,
+    }
+    .start(journal, |dest| {
+        if request.mode == ImportMode::Move {
+            transfer::copy_verified(source, dest)
+        } else {
+            std::fs::copy(source, dest)
+                .map(|_| ())
+                .map_err(AppError::from)
+        }
+    })
+}
+
+/// Stages a work that is registered where it already is: under the library
+/// root when adopted, anywhere when referenced. Nothing is copied, so nothing
+/// is journaled or cleaned up.
+fn stage_in_place(
+    conn: &rusqlite::Connection,
+    request: &ImportRequest,
+    work_type: &'static str,
+    source: &Path,
+    page_count: usize,
+    thumbnail: Vec<u8>,
+    hashes: WorkHashes,
+) -> Result<StagedImport, AppError> {
+    if request.mode == ImportMode::Adopt {
+        let library_root = settings::get_library_root(conn)?.ok_or_else(|| {
+            AppError::ImportError("ライブラリルートが設定されていません".to_string())
+        })?;
+        let library_root = Path::new(&library_root);
+        if source == library_root || !source.starts_with(library_root) {
+            return Err(AppError::ImportError(
+                "その場で登録できるのはライブラリルート内の作品のみです".to_string(),
+            ));
+        }
+    }
+    if db::path_exists(conn, &source.to_string_lossy())? {
+        return Err(AppError::ImportError(
+            "この作品は既に登録されています".to_string(),
+        ));
+    }
+    Ok(StagedImport {
+        work_type,
+        source: source.to_path_buf(),
+        dest: source.to_path_buf(),
         page_count,
+        thumbnail,
+        hashes,
+        delete_source: false,
+        strategy: MoveStrategy::Copy,
+        in_place: true,
+        operation_id: 0,
     })
 }
 
@@ -198,13 +664,53 @@ fn paths_overlap(a: &Path, b: &Path) -> bool {
     a.starts_with(b) || b.starts_with(a)
 }
 
-fn copy_images_to_dest(images: &[PathBuf], dest: &Path) -> Result<(), AppError> {
-    for image in images {
-        let file_name = image
-            .file_name()
-            .ok_or_else(|| AppError::ImportError("無効なファイル名".to_string()))?;
-        let dest_file = dest.join(file_name);
-        std::fs::copy(image, &dest_file)?;
+/// Lists the files making up a folder work, relative to the folder: its
+/// top-level images plus whatever `extra` carries from the rest of the tree.
+pub(crate) fn list_work_files(folder: &Path, extra: &ExtraFiles) -> Result<Vec<PathBuf>, AppError> {
+    let mut files: Vec<PathBuf> = list_images_in_folder(folder)?
+        .iter()
+        .filter_map(|image| image.strip_prefix(folder).ok().map(Path::to_path_buf))
+        .collect();
+    if *extra == ExtraFiles::None {
+        return Ok(files);
+    }
+    for entry in WalkDir::new(folder)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(folder) else {
+            continue;
+        };
+        let top_level_image = entry.depth() == 1 && scanner::is_image_file(relative);
+        if !top_level_image && extra.carries(relative) {
+            files.push(relative.to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+/// Copies `files` (relative to `source`) into `dest`. With `verify`, each copy is
+/// checked against its original because the original is deleted afterwards.
+fn copy_work_files(
+    source: &Path,
+    files: &[PathBuf],
+    dest: &Path,
+    verify: bool,
+) -> Result<(), AppError> {
+    for file in files {
+        let dest_file = dest.join(file);
+        if let Some(parent) = dest_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if verify {
+            transfer::copy_verified(&source.join(file), &dest_file)?;
+        } else {
+            std::fs::copy(source.join(file), &dest_file)?;
+        }
     }
     Ok(())
 }
@@ -214,9 +720,14 @@ fn copy_images_to_dest(images: &[PathBuf], dest: &Path) -> Result<(), AppError>
 pub struct DiscoveredFolder {
     pub path: String,
     pub folder_name: String,
+    /// "folder" for a folder of images, or "archive" / "pdf" for a single file.
+    pub work_type: String,
     pub image_count: usize,
     pub parsed_metadata: ParsedMetadata,
     pub already_registered: bool,
+    /// Registered works with the same images, exact matches first. Such folders
+    /// are usually originals that were already copied into the library.
+    pub duplicates: Vec<DuplicateMatch>,
 }
 
 #[derive(Serialize, Clone)]
@@ -232,21 +743,30 @@ pub fn discover_image_folders(
     on_progress: &Channel<DiscoverProgress>,
 ) -> Result<Vec<DiscoveredFolder>, AppError> {
     let conn = db::open_db(app_data_dir)?;
+    // Hashing every folder is wasted work when there is nothing to compare against.
+    let library_has_hashes = dedup::has_any_page_hashes(&conn)?;
+    let parser = FolderNameParser::load(&conn)?;
     let mut folders = Vec::new();
     let mut scanned_dirs = 0usize;
 
     for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
-        if !entry.file_type().is_dir() {
-            continue;
-        }
-
-        scanned_dirs += 1;
-        if scanned_dirs.is_multiple_of(50) {
-            let _ = on_progress.send(DiscoverProgress::Scanning { scanned_dirs });
-        }
-
         let dir_path = entry.path();
-        let image_count = count_direct_images(dir_path);
+        let (work_type, image_count) = if entry.file_type().is_dir() {
+            scanned_dirs += 1;
+            if scanned_dirs.is_multiple_of(50) {
+                let _ = on_progress.send(DiscoverProgress::Scanning { scanned_dirs });
+            }
+            ("folder", count_direct_images(dir_path))
+        } else if scanner::is_archive_file(dir_path) {
+            (
+                "archive",
+                archive::list_archive_pages(dir_path).map_or(0, |pages| pages.len()),
+            )
+        } else if scanner::is_pdf_file(dir_path) {
+            ("pdf", pdf::page_count(dir_path).unwrap_or(0))
+        } else {
+            continue;
+        };
         if image_count == 0 {
             continue;
         }
@@ -259,14 +779,24 @@ pub fn discover_image_folders(
 
         let path_str = dir_path.to_string_lossy().to_string();
         let already_registered = db::path_exists(&conn, &path_str)?;
-        let parsed_metadata = parse_folder_name(&folder_name);
+        let parsed_metadata = read_source_metadata(&conn, &parser, dir_path)?;
+        let duplicates = if already_registered || !library_has_hashes {
+            Vec::new()
+        } else {
+            match hashing::hash_work(dir_path, work_type) {
+                Ok(hashes) => dedup::find_duplicates(&conn, &hashes)?,
+                Err(_) => Vec::new(),
+            }
+        };
 
         folders.push(DiscoveredFolder {
             path: path_str,
             folder_name,
+            work_type: work_type.to_string(),
             image_count,
             parsed_metadata,
             already_registered,
+            duplicates,
         });
     }
 
@@ -293,7 +823,9 @@ fn count_direct_images(dir: &Path) -> usize {
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum BulkImportProgress {
+    #[serde(rename_all = "camelCase")]
     Started {
+        job_id: i64,
         total: usize,
     },
     Importing {
@@ -305,6 +837,11 @@ pub enum BulkImportProgress {
         succeeded: usize,
         failed: usize,
     },
+    Cancelled {
+        succeeded: usize,
+        failed: usize,
+        remaining: usize,
+    },
     #[serde(rename_all = "camelCase")]
     Error {
         title: String,
@@ -315,42 +852,272 @@ pub enum BulkImportProgress {
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BulkImportSummary {
+    pub job_id: i64,
     pub succeeded: usize,
     pub failed: usize,
+    pub cancelled: bool,
 }
 
+/// Records the requests as a new import job and runs it.
 pub fn bulk_import(
     requests: &[ImportRequest],
     app_data_dir: &Path,
     on_progress: &Channel<BulkImportProgress>,
 ) -> Result<BulkImportSummary, AppError> {
-    let total = requests.len();
-    let _ = on_progress.send(BulkImportProgress::Started { total });
+    let mut conn = db::open_db(app_data_dir)?;
+    let job_id = import_jobs::create_job(&mut conn, requests)?;
+    drop(conn);
+    run_import_job(job_id, app_data_dir, on_progress)
+}
+
+/// Runs the pending items of an import job. Items finished by an earlier run
+/// are skipped but still counted. The job stops between items once
+/// [`import_jobs::request_cancel`] is called, leaving the rest pending.
+pub fn run_import_job(
+    job_id: i64,
+    app_data_dir: &Path,
+    on_progress: &Channel<BulkImportProgress>,
+) -> Result<BulkImportSummary, AppError> {
+    let cancel = import_jobs::activate(job_id)?;
+    let result = run_active_job(job_id, app_data_dir, on_progress, &cancel);
+    import_jobs::deactivate(job_id);
+    result
+}
+
+fn job_interrupted() -> AppError {
+    AppError::ImportJobError("取り込みジョブが中断されました".to_string())
+}
+
+enum WorkerMessage {
+    /// A copy the worker is about to make, to be journaled by the writer.
+    Begin {
+        index: usize,
+        operation: FileOperation,
+        reply: mpsc::Sender<Result<i64, AppError>>,
+    },
+    /// A journaled copy that was discarded before registration.
+    Complete {
+        operation_id: i64,
+    },
+    /// A staged import waiting for the writer to register it.
+    Staged {
+        index: usize,
+        staged: StagedImport,
+        reply: mpsc::Sender<Result<ImportResult, AppError>>,
+    },
+    Finished {
+        index: usize,
+        error: Option<String>,
+    },
+}
 
-    let mut succeeded = 0usize;
-    let mut failed = 0usize;
+/// Journals through the bulk import writer on behalf of a worker.
+struct WriterJournal<'a> {
+    index: usize,
+    writer: &'a mpsc::Sender<WorkerMessage>,
+}
 
-    for (i, request) in requests.iter().enumerate() {
-        let _ = on_progress.send(BulkImportProgress::Importing {
-            current: i + 1,
-            total,
-            title: request.title.clone(),
+impl ImportJournal for WriterJournal<'_> {
+    fn begin(&self, operation: FileOperation) -> Result<i64, AppError> {
+        let (reply, response) = mpsc::channel();
+        self.writer
+            .send(WorkerMessage::Begin {
+                index: self.index,
+                operation,
+                reply,
+            })
+            .map_err(|_| job_interrupted())?;
+        response.recv().map_err(|_| job_interrupted())?
+    }
+
+    fn complete(&self, operation_id: i64) {
+        let _ = self.writer.send(WorkerMessage::Complete { operation_id });
+    }
+}
+
+/// Stages items until none are left or the job is cancelled. Registration goes
+/// through the writer so that every insert uses the same connection.
+fn import_worker(
+    conn: &rusqlite::Connection,
+    items: &[import_jobs::PendingItem],
+    next: &AtomicUsize,
+    cancel: &AtomicBool,
+    writer: &mpsc::Sender<WorkerMessage>,
+) {
+    while !cancel.load(Ordering::SeqCst) {
+        let index = next.fetch_add(1, Ordering::SeqCst);
+        let Some(item) = items.get(index) else {
+            return;
+        };
+        let journal = WriterJournal { index, writer };
+        let result = stage_import(conn, &item.request, &journal).and_then(|staged| {
+            let (reply, response) = mpsc::channel();
+            writer
+                .send(WorkerMessage::Staged {
+                    index,
+                    staged,
+                    reply,
+                })
+                .map_err(|_| job_interrupted())?;
+            response.recv().map_err(|_| job_interrupted())?
         });
+        let error = result.err().map(|e| e.to_string());
+        if writer
+            .send(WorkerMessage::Finished { index, error })
+            .is_err()
+        {
+            return;
+        }
+    }
+}
 
-        match import_work(request, app_data_dir) {
-            Ok(_) => succeeded += 1,
-            Err(e) => {
-                let _ = on_progress.send(BulkImportProgress::Error {
-                    title: request.title.clone(),
-                    message: e.to_string(),
-                });
-                failed += 1;
+fn run_active_job(
+    job_id: i64,
+    app_data_dir: &Path,
+    on_progress: &Channel<BulkImportProgress>,
+    cancel: &AtomicBool,
+) -> Result<BulkImportSummary, AppError> {
+    let conn = db::open_db(app_data_dir)?;
+    let job = import_jobs::get_job(&conn, job_id)?;
+    if job.status == "completed" {
+        return Err(AppError::ImportJobError(
+            "この取り込みジョブは完了しています".to_string(),
+        ));
+    }
+    import_jobs::set_job_status(&conn, job_id, "running")?;
+    let items = import_jobs::pending_items(&conn, job_id)?;
+    let workers = settings::get_import_workers(&conn)?.min(items.len().max(1));
+    let readers = (0..workers)
+        .map(|_| db::open_db(app_data_dir))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let total = job.total;
+    let mut succeeded = job.succeeded;
+    let mut failed = job.failed;
+    let _ = on_progress.send(BulkImportProgress::Started { job_id, total });
+
+    let next = AtomicUsize::new(0);
+    let mut finished = 0usize;
+    // Items finish out of order; progress is reported in request order.
+    let mut unreported: BTreeMap<usize, Option<String>> = BTreeMap::new();
+    let mut next_report = 0usize;
+
+    // Set when recording progress fails; the job then winds down like a cancel.
+    let mut failure: Option<AppError> = None;
+
+    std::thread::scope(|scope| {
+        let (writer, messages) = mpsc::channel();
+        for reader in readers {
+            let writer = writer.clone();
+            let (items, next) = (&items, &next);
+            scope.spawn(move || import_worker(&reader, items, next, cancel, &writer));
+        }
+        drop(writer);
+
+        for message in messages {
+            match message {
+                WorkerMessage::Begin {
+                    index,
+                    operation,
+                    reply,
+                } => {
+                    let result = match failure {
+                        Some(_) => Err(job_interrupted()),
+                        None => journal::begin(
+                            &conn,
+                            &FileOperation {
+                                job_item_id: Some(items[index].id),
+                                ..operation
+                            },
+                        ),
+                    };
+                    let _ = reply.send(result);
+                }
+                WorkerMessage::Complete { operation_id } => {
+                    let _ = journal::complete(&conn, operation_id);
+                }
+                WorkerMessage::Staged {
+                    index,
+                    staged,
+                    reply,
+                } => {
+                    let result = match failure {
+                        Some(_) => Err(job_interrupted()),
+                        None => commit_import(&conn, &items[index].request, &staged),
+                    };
+                    settle_import(&conn, &staged, &result);
+                    let _ = reply.send(result);
+                }
+                WorkerMessage::Finished { index, error } => {
+                    if failure.is_some() {
+                        continue;
+                    }
+                    if let Err(e) =
+                        import_jobs::finish_item(&conn, items[index].id, error.as_deref())
+                    {
+                        // Stop handing out items; the rest stay pending for a resume.
+                        cancel.store(true, Ordering::SeqCst);
+                        failure = Some(e);
+                        continue;
+                    }
+                    finished += 1;
+                    unreported.insert(index, error);
+                    while let Some(error) = unreported.remove(&next_report) {
+                        let title = items[next_report].request.title.clone();
+                        let _ = on_progress.send(BulkImportProgress::Importing {
+                            current: succeeded + failed + 1,
+                            total,
+                            title: title.clone(),
+                        });
+                        match error {
+                            None => succeeded += 1,
+                            Some(message) => {
+                                let _ =
+                                    on_progress.send(BulkImportProgress::Error { title, message });
+                                failed += 1;
+                            }
+                        }
+                        next_report += 1;
+                    }
+                }
             }
         }
+    });
+    if let Some(e) = failure {
+        return Err(e);
     }
 
+    if finished < items.len() {
+        // Cancelled items may finish out of order; count what was left unreported.
+        for error in unreported.into_values() {
+            match error {
+                None => succeeded += 1,
+                Some(_) => failed += 1,
+            }
+        }
+        import_jobs::set_job_status(&conn, job_id, "cancelled")?;
+        let remaining = items.len() - finished;
+        let _ = on_progress.send(BulkImportProgress::Cancelled {
+            succeeded,
+            failed,
+            remaining,
+        });
+        return Ok(BulkImportSummary {
+            job_id,
+            succeeded,
+            failed,
+            cancelled: true,
+        });
+    }
+
+    import_jobs::set_job_status(&conn, job_id, "completed")?;
     let _ = on_progress.send(BulkImportProgress::Completed { succeeded, failed });
-    Ok(BulkImportSummary { succeeded, failed })
+    Ok(BulkImportSummary {
+        job_id,
+        succeeded,
+        failed,
+        cancelled: false,
+    })
 }
 
 #[cfg(test)]
@@ -0,0 +1,251 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+use walkdir::WalkDir;
+
+use crate::db;
+use crate::error::AppError;
+use crate::import_jobs;
+use crate::relocator;
+use crate::roots;
+use crate::transfer::MoveStrategy;
+
+/// What a journaled copy is for. Every kind copies to `destination`, switches
+/// the database over, then optionally deletes `source`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OperationKind {
+    Import,
+    Relocate,
+    /// Moving the whole library tree; `source` and `destination` are the old
+    /// and new library roots and there is no single work.
+    Library,
+}
+
+impl OperationKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            OperationKind::Import => "import",
+            OperationKind::Relocate => "relocate",
+            OperationKind::Library => "library",
+        }
+    }
+}
+
+pub struct FileOperation {
+    pub kind: OperationKind,
+    pub work_id: Option<i64>,
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub work_type: String,
+    pub delete_source: bool,
+    pub job_item_id: Option<i64>,
+    pub strategy: MoveStrategy,
+}
+
+/// Records a copy that is about to start. Must be called before anything is
+/// written to `destination`.
+pub fn begin(conn: &Connection, operation: &FileOperation) -> Result<i64, AppError> {
+    conn.execute(
+        "INSERT INTO file_operations (kind, work_id, source, destination, work_type, delete_source, job_item_id, strategy) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        rusqlite::params![
+            operation.kind.as_str(),
+            operation.work_id,
+            operation.source.to_string_lossy(),
+            operation.destination.to_string_lossy(),
+            operation.work_type,
+            operation.delete_source,
+            operation.job_item_id,
+            operation.strategy.as_str(),
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Marks the copy as registered. Call inside the transaction that points the
+/// work at `destination`, so the two can never disagree.
+pub fn mark_committed(conn: &Connection, operation_id: i64, work_id: i64) -> Result<(), AppError> {
+    conn.execute(
+        "UPDATE file_operations SET state = 'committed', work_id = ?1 WHERE id = ?2",
+        rusqlite::params![work_id, operation_id],
+    )?;
+    Ok(())
+}
+
+/// Marks a library tree move as registered. Like `mark_committed`, call inside
+/// the transaction that points the works at `destination`.
+pub fn mark_library_committed(conn: &Connection, operation_id: i64) -> Result<(), AppError> {
+    conn.execute(
+        "UPDATE file_operations SET state = 'committed' WHERE id = ?1",
+        [operation_id],
+    )?;
+    Ok(())
+}
+
+/// Records that a rename failed and the operation fell back to copying.
+pub fn set_strategy(
+    conn: &Connection,
+    operation_id: i64,
+    strategy: MoveStrategy,
+) -> Result<(), AppError> {
+    conn.execute(
+        "UPDATE file_operations SET strategy = ?1 WHERE id = ?2",
+        rusqlite::params![strategy.as_str(), operation_id],
+    )?;
+    Ok(())
+}
+
+/// Forgets an operation once its files are in their final state.
+pub fn complete(conn: &Connection, operation_id: i64) -> Result<(), AppError> {
+    conn.execute("DELETE FROM file_operations WHERE id = ?1", [operation_id])?;
+    Ok(())
+}
+
+/// Deletes the source of a committed copy. Only files that exist at the
+/// destination with the same size are removed, so a truncated copy never costs
+/// the original and files that were not carried over stay where they are.
+pub(crate) fn remove_copied_source(source: &Path, destination: &Path, work_type: &str) {
+    let copied = |from: &Path, to: &Path| match (std::fs::metadata(from), std::fs::metadata(to)) {
+        (Ok(a), Ok(b)) => a.len() == b.len(),
+        _ => false,
+    };
+
+    if work_type == "folder" {
+        // Deepest entries first, so emptied subfolders can go too; anything
+        // that was not carried over keeps its folder alive.
+        for entry in WalkDir::new(source)
+            .contents_first(true)
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            let path = entry.path();
+            if entry.file_type().is_dir() {
+                let _ = std::fs::remove_dir(path);
+            } else if let Ok(relative) = path.strip_prefix(source) {
+                if copied(path, &destination.join(relative)) {
+                    let _ = std::fs::remove_file(path);
+                }
+            }
+        }
+    } else if copied(source, destination) {
+        let _ = std::fs::remove_file(source);
+    }
+}
+
+struct JournalEntry {
+    id: i64,
+    kind: String,
+    source: PathBuf,
+    destination: PathBuf,
+    work_type: String,
+    delete_source: bool,
+    job_item_id: Option<i64>,
+    committed: bool,
+    renamed: bool,
+}
+
+fn list_entries(conn: &Connection) -> Result<Vec<JournalEntry>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, kind, source, destination, work_type, delete_source, job_item_id, state, strategy FROM file_operations ORDER BY id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(JournalEntry {
+            id: row.get(0)?,
+            kind: row.get(1)?,
+            source: PathBuf::from(row.get::<_, String>(2)?),
+            destination: PathBuf::from(row.get::<_, String>(3)?),
+            work_type: row.get(4)?,
+            delete_source: row.get(5)?,
+            job_item_id: row.get(6)?,
+            committed: row.get::<_, String>(7)? == "committed",
+            renamed: row.get::<_, String>(8)? == MoveStrategy::Rename.as_str(),
+        })
+    })?;
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row?);
+    }
+    Ok(entries)
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct RecoverySummary {
+    pub rolled_forward: usize,
+    pub rolled_back: usize,
+}
+
+/// Undoes an uncommitted rename. The source still existing means the rename
+/// never happened, so only the claimed placeholder (if any) is removed.
+fn roll_back_rename(entry: &JournalEntry) {
+    if entry.source.exists() {
+        if entry.work_type == "folder" {
+            let _ = std::fs::remove_dir(&entry.destination);
+        } else if std::fs::metadata(&entry.destination).is_ok_and(|m| m.len() == 0) {
+            let _ = std::fs::remove_file(&entry.destination);
+        }
+    } else if entry.destination.exists() {
+        let _ = std::fs::rename(&entry.destination, &entry.source);
+    }
+}
+
+/// Discards an uncommitted copy, leaving the source as it was.
+fn roll_back_copy(conn: &Connection, entry: &JournalEntry) -> Result<(), AppError> {
+    // Never touch a path some work is registered at.
+    if db::path_exists(conn, &entry.destination.to_string_lossy())? {
+        return Ok(());
+    }
+    if entry.work_type == "folder" {
+        let _ = std::fs::remove_dir_all(&entry.destination);
+    } else {
+        let _ = std::fs::remove_file(&entry.destination);
+    }
+    Ok(())
+}
+
+/// Prunes the empty directories left above `path`, up to the root it lies in.
+fn cleanup_empty_ancestors(conn: &Connection, path: &Path) -> Result<(), AppError> {
+    let (root_id, _) = roots::split_location(conn, &path.to_string_lossy())?;
+    if let Some(root) = relocator::cleanup_boundary(conn, root_id)? {
+        relocator::cleanup_empty_ancestors(path, &root);
+    }
+    Ok(())
+}
+
+/// Finishes operations left behind by a crash. Committed ones are rolled
+/// forward by deleting what is left of the source; the rest are rolled back by
+/// discarding the partial copy or renaming the work back, leaving the source as
+/// it was. Must run before any import or relocation starts.
+pub fn recover(conn: &Connection) -> Result<RecoverySummary, AppError> {
+    let mut summary = RecoverySummary::default();
+
+    for entry in list_entries(conn)? {
+        if entry.committed {
+            if entry.delete_source && !entry.renamed {
+                remove_copied_source(&entry.source, &entry.destination, &entry.work_type);
+            }
+            if entry.kind == OperationKind::Relocate.as_str() {
+                cleanup_empty_ancestors(conn, &entry.source)?;
+            }
+            if let Some(item_id) = entry.job_item_id {
+                import_jobs::finish_item(conn, item_id, None)?;
+            }
+            summary.rolled_forward += 1;
+        } else {
+            if entry.renamed {
+                roll_back_rename(&entry);
+            } else {
+                roll_back_copy(conn, &entry)?;
+            }
+            if entry.kind != OperationKind::Library.as_str() {
+                cleanup_empty_ancestors(conn, &entry.destination)?;
+            }
+            summary.rolled_back += 1;
+        }
+        complete(conn, entry.id)?;
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+#[path = "tests/journal.rs"]
+mod tests;
@@ -1,186 +1,808 @@
+mod archive;
 mod db;
+mod dedup;
+mod doctor;
 mod error;
+mod hashing;
+mod import_jobs;
 mod importer;
+mod journal;
+mod name_rules;
+mod pdf;
+mod playlists;
 mod relocator;
+mod roots;
 mod scanner;
+mod search;
 mod settings;
+mod similar;
+mod tags;
 mod template;
 mod thumbnail;
+mod transfer;
 mod viewer;
+mod watcher;
+mod works;
 
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::Mutex;
 
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 use tauri_plugin_sql::{Migration, MigrationKind};
 
 use db::{WorkDetail, WorkSummary};
+use import_jobs::ImportJob;
 use importer::{
     BulkImportProgress, BulkImportSummary, DiscoverProgress, DiscoveredFolder, ImportResult,
     ParsedMetadata,
 };
-use relocator::{RelocationPreview, RelocationProgress};
+use name_rules::{FolderNameMatch, NameRule, NameRuleInput};
+use playlists::Playlist;
+use relocator::{LastRelocation, LibraryMoveProgress, RelocationPreview, RelocationProgress};
+use roots::LibraryRoot;
 use serde::Serialize;
+use tags::Tag;
 use template::WorkMetadata;
+use watcher::LibraryWatcher;
+
+#[derive(Default)]
+struct WatcherState(Mutex<Option<LibraryWatcher>>);
+
+/// (Re)starts the filesystem watcher so it picks up the current library root.
+/// Changes are forwarded to the frontend as `library-changed` events.
+fn restart_watcher(app: &tauri::AppHandle) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let state = app.state::<WatcherState>();
+    let mut current = state.0.lock().map_err(|e| e.to_string())?;
+    // Stop the old watcher first so its thread is not still processing events.
+    current.take();
+
+    let emitter = app.clone();
+    let watcher = LibraryWatcher::start(app_data_dir, move |changes| {
+        let _ = emitter.emit("library-changed", changes);
+    })
+    .map_err(|e| e.to_string())?;
+    *current = Some(watcher);
+    Ok(())
+}
+
+/// Restarts the watcher if works were registered where it is not watching.
+fn refresh_watcher(app: &tauri::AppHandle) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+    let stale = {
+        let state = app.state::<WatcherState>();
+        let current = state.0.lock().map_err(|e| e.to_string())?;
+        match current.as_ref() {
+            Some(watcher) => watcher.is_stale(&conn).map_err(|e| e.to_string())?,
+            None => true,
+        }
+    };
+    if stale {
+        restart_watcher(app)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn list_works(
+    app: tauri::AppHandle,
+    sort_by: String,
+    sort_order: String,
+) -> Result<Vec<WorkSummary>, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        db::list_works(&conn, &sort_by, &sort_order).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn list_works_page(
+    app: tauri::AppHandle,
+    filter: db::WorkFilter,
+    sort_by: String,
+    sort_order: String,
+    limit: u32,
+    cursor: Option<db::WorkCursor>,
+) -> Result<db::WorkPage, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        db::list_works_page(
+            &conn,
+            &filter,
+            &sort_by,
+            &sort_order,
+            limit,
+            cursor.as_ref(),
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn mark_work_read(app: tauri::AppHandle, work_id: i64) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        db::mark_work_read(&conn, work_id).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn search_works(
+    app: tauri::AppHandle,
+    query: String,
+    sort_by: String,
+    sort_order: String,
+) -> Result<Vec<WorkSummary>, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        search::search_works(&conn, &query, &sort_by, &sort_order).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn find_similar_works(
+    app: tauri::AppHandle,
+    threshold: u32,
+) -> Result<Vec<similar::SimilarCluster>, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        similar::find_similar_works(&conn, threshold).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn get_thumbnail(app: tauri::AppHandle, work_id: i64) -> Result<Vec<u8>, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        db::get_thumbnail(&conn, work_id).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn get_work(app: tauri::AppHandle, work_id: i64) -> Result<WorkDetail, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        db::get_work(&conn, work_id).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn update_work(
+    app: tauri::AppHandle,
+    work_id: i64,
+    edit: db::WorkEdit,
+) -> Result<WorkDetail, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let mut conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        works::update_work(&mut conn, work_id, &edit).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn delete_works(
+    app: tauri::AppHandle,
+    work_ids: Vec<i64>,
+    mode: works::DeleteMode,
+) -> Result<works::DeleteSummary, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let mut conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        works::delete_works(&mut conn, &work_ids, mode).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn list_tags(app: tauri::AppHandle) -> Result<Vec<Tag>, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        tags::list_tags(&conn).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn create_tag(
+    app: tauri::AppHandle,
+    name: String,
+    category: Option<String>,
+) -> Result<Tag, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        tags::create_tag(&conn, &name, category.as_deref()).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn update_tag(
+    app: tauri::AppHandle,
+    tag_id: i64,
+    name: String,
+    category: Option<String>,
+) -> Result<Tag, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        tags::update_tag(&conn, tag_id, &name, category.as_deref()).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn delete_tag(app: tauri::AppHandle, tag_id: i64) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        tags::delete_tag(&conn, tag_id).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn add_work_tags(
+    app: tauri::AppHandle,
+    work_ids: Vec<i64>,
+    tag_id: i64,
+) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let mut conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        tags::add_tag_to_works(&mut conn, &work_ids, tag_id).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn remove_work_tags(
+    app: tauri::AppHandle,
+    work_ids: Vec<i64>,
+    tag_id: i64,
+) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let mut conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        tags::remove_tag_from_works(&mut conn, &work_ids, tag_id).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn merge_tags(
+    app: tauri::AppHandle,
+    source_tag_id: i64,
+    target_tag_id: i64,
+) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let mut conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        tags::merge_tags(&mut conn, source_tag_id, target_tag_id).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn list_playlists(app: tauri::AppHandle) -> Result<Vec<Playlist>, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        playlists::list_playlists(&conn).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn create_playlist(app: tauri::AppHandle, name: String) -> Result<Playlist, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        playlists::create_playlist(&conn, &name).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn rename_playlist(
+    app: tauri::AppHandle,
+    playlist_id: i64,
+    name: String,
+) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        playlists::rename_playlist(&conn, playlist_id, &name).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn delete_playlist(app: tauri::AppHandle, playlist_id: i64) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        playlists::delete_playlist(&conn, playlist_id).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn list_playlist_works(
+    app: tauri::AppHandle,
+    playlist_id: i64,
+) -> Result<Vec<WorkSummary>, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        playlists::list_playlist_works(&conn, playlist_id).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn add_playlist_works(
+    app: tauri::AppHandle,
+    playlist_id: i64,
+    work_ids: Vec<i64>,
+) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let mut conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        playlists::add_works_to_playlist(&mut conn, playlist_id, &work_ids)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn remove_playlist_works(
+    app: tauri::AppHandle,
+    playlist_id: i64,
+    work_ids: Vec<i64>,
+) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let mut conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        playlists::remove_works_from_playlist(&mut conn, playlist_id, &work_ids)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn reorder_playlist(
+    app: tauri::AppHandle,
+    playlist_id: i64,
+    work_ids: Vec<i64>,
+) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let mut conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        playlists::reorder_playlist(&mut conn, playlist_id, &work_ids).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn next_work(
+    app: tauri::AppHandle,
+    playlist_id: i64,
+    work_id: i64,
+) -> Result<Option<i64>, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        playlists::next_work_id(&conn, playlist_id, work_id).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppSettings {
+    library_root: Option<String>,
+    directory_template: Option<String>,
+    type_templates: BTreeMap<String, String>,
+    type_label_image: String,
+    type_label_folder: String,
+    import_workers: usize,
+    extra_files_mode: String,
+    extra_file_extensions: Vec<String>,
+}
+
+#[tauri::command]
+async fn get_settings(app: tauri::AppHandle) -> Result<AppSettings, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        let library_root = settings::get_library_root(&conn).map_err(|e| e.to_string())?;
+        let directory_template =
+            settings::get_directory_template(&conn).map_err(|e| e.to_string())?;
+        let mut type_templates = BTreeMap::new();
+        for &work_type in &settings::WORK_TYPES[1..] {
+            if let Some(template) =
+                settings::get_type_template(&conn, work_type).map_err(|e| e.to_string())?
+            {
+                type_templates.insert(work_type.to_string(), template);
+            }
+        }
+        let type_label_image = settings::get_type_label_image(&conn).map_err(|e| e.to_string())?;
+        let type_label_folder =
+            settings::get_type_label_folder(&conn).map_err(|e| e.to_string())?;
+        let import_workers = settings::get_import_workers(&conn).map_err(|e| e.to_string())?;
+        let extra_files_mode = settings::get_extra_files_mode(&conn).map_err(|e| e.to_string())?;
+        let extra_file_extensions =
+            settings::get_extra_file_extensions(&conn).map_err(|e| e.to_string())?;
+        Ok(AppSettings {
+            library_root,
+            directory_template,
+            type_templates,
+            type_label_image,
+            type_label_folder,
+            import_workers,
+            extra_files_mode,
+            extra_file_extensions,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Switches the library to `path`, optionally moving the whole library tree
+/// there along with every work in it.
+#[tauri::command]
+async fn change_library_root(
+    app: tauri::AppHandle,
+    path: String,
+    move_files: bool,
+    on_progress: tauri::ipc::Channel<LibraryMoveProgress>,
+) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        relocator::change_library_root(&conn, &PathBuf::from(&path), move_files, &on_progress)
+            .map_err(|e| e.to_string())?;
+        if let Err(e) = restart_watcher(&app) {
+            eprintln!("Failed to restart library watcher: {}", e);
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Trims a template entered for a work type. Only types other than folder may
+/// be left empty, meaning they fall back to the folder template.
+fn normalize_type_template(work_type: &str, template: &str) -> Result<Option<String>, String> {
+    let trimmed = template.trim();
+    if trimmed.is_empty() && work_type != "folder" {
+        return Ok(None);
+    }
+    template::validate_template(trimmed).map_err(|e| e.to_string())?;
+    Ok(Some(trimmed.to_string()))
+}
+
+#[tauri::command]
+async fn set_directory_template(
+    app: tauri::AppHandle,
+    work_type: String,
+    template: String,
+) -> Result<(), String> {
+    let trimmed = template.trim().to_string();
+    let template = if trimmed.is_empty() {
+        None
+    } else {
+        template::validate_template(&trimmed).map_err(|e| e.to_string())?;
+        Some(trimmed)
+    };
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        settings::set_type_template(&conn, &work_type, template.as_deref())
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn validate_template(template: String) -> Result<(), String> {
+    template::validate_template(&template).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_type_labels(
+    app: tauri::AppHandle,
+    image_label: String,
+    folder_label: String,
+) -> Result<(), String> {
+    let image_label = image_label.trim().to_string();
+    let folder_label = folder_label.trim().to_string();
+    if image_label.is_empty() || folder_label.is_empty() {
+        return Err("ラベルは空にできません".to_string());
+    }
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        settings::set_type_label_image(&conn, &image_label).map_err(|e| e.to_string())?;
+        settings::set_type_label_folder(&conn, &folder_label).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn set_import_workers(app: tauri::AppHandle, workers: usize) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        settings::set_import_workers(&conn, workers).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
 #[tauri::command]
-async fn list_works(
+async fn set_extra_files(
     app: tauri::AppHandle,
-    sort_by: String,
-    sort_order: String,
-) -> Result<Vec<WorkSummary>, String> {
+    mode: String,
+    extensions: Vec<String>,
+) -> Result<(), String> {
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     tokio::task::spawn_blocking(move || {
         let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
-        db::list_works(&conn, &sort_by, &sort_order).map_err(|e| e.to_string())
+        settings::set_extra_files(&conn, &mode, &extensions).map_err(|e| e.to_string())
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-async fn get_thumbnail(app: tauri::AppHandle, work_id: i64) -> Result<Vec<u8>, String> {
+async fn preview_template(
+    app: tauri::AppHandle,
+    template: String,
+    work_type: String,
+) -> Result<String, String> {
+    template::validate_template(&template).map_err(|e| e.to_string())?;
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     tokio::task::spawn_blocking(move || {
         let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
-        db::get_thumbnail(&conn, work_id).map_err(|e| e.to_string())
+        let type_label =
+            settings::resolve_type_label(&conn, &work_type).map_err(|e| e.to_string())?;
+        let mut metadata = template::sample_metadata();
+        metadata.work_type = Some(type_label);
+        Ok(template::render_template(&template, &metadata))
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-async fn get_work(app: tauri::AppHandle, work_id: i64) -> Result<WorkDetail, String> {
+async fn parse_folder_name(
+    app: tauri::AppHandle,
+    folder_name: String,
+) -> Result<ParsedMetadata, String> {
+    Ok(test_folder_name_rules(app, folder_name).await?.metadata)
+}
+
+#[tauri::command]
+async fn read_import_metadata(
+    app: tauri::AppHandle,
+    source_path: String,
+) -> Result<ParsedMetadata, String> {
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     tokio::task::spawn_blocking(move || {
         let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
-        db::get_work(&conn, work_id).map_err(|e| e.to_string())
+        let parser = name_rules::FolderNameParser::load(&conn).map_err(|e| e.to_string())?;
+        importer::read_source_metadata(&conn, &parser, std::path::Path::new(&source_path))
+            .map_err(|e| e.to_string())
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct AppSettings {
-    library_root: Option<String>,
-    directory_template: Option<String>,
-    type_label_image: String,
-    type_label_folder: String,
+#[tauri::command]
+async fn test_folder_name_rules(
+    app: tauri::AppHandle,
+    folder_name: String,
+) -> Result<FolderNameMatch, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        let parser = name_rules::FolderNameParser::load(&conn).map_err(|e| e.to_string())?;
+        Ok(parser.parse(&folder_name))
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-async fn get_settings(app: tauri::AppHandle) -> Result<AppSettings, String> {
+async fn list_folder_name_rules(app: tauri::AppHandle) -> Result<Vec<NameRule>, String> {
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     tokio::task::spawn_blocking(move || {
         let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
-        let library_root = settings::get_library_root(&conn).map_err(|e| e.to_string())?;
-        let directory_template =
-            settings::get_directory_template(&conn).map_err(|e| e.to_string())?;
-        let type_label_image = settings::get_type_label_image(&conn).map_err(|e| e.to_string())?;
-        let type_label_folder =
-            settings::get_type_label_folder(&conn).map_err(|e| e.to_string())?;
-        Ok(AppSettings {
-            library_root,
-            directory_template,
-            type_label_image,
-            type_label_folder,
-        })
+        name_rules::list_rules(&conn).map_err(|e| e.to_string())
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-async fn set_library_root(app: tauri::AppHandle, path: String) -> Result<(), String> {
+async fn create_folder_name_rule(
+    app: tauri::AppHandle,
+    rule: NameRuleInput,
+) -> Result<NameRule, String> {
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     tokio::task::spawn_blocking(move || {
         let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
-        settings::set_library_root(&conn, &path).map_err(|e| e.to_string())
+        name_rules::create_rule(&conn, &rule).map_err(|e| e.to_string())
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-async fn set_directory_template(app: tauri::AppHandle, template: String) -> Result<(), String> {
-    let trimmed = template.trim().to_string();
-    if !trimmed.is_empty() {
-        template::validate_template(&trimmed).map_err(|e| e.to_string())?;
-    }
+async fn update_folder_name_rule(
+    app: tauri::AppHandle,
+    rule_id: i64,
+    rule: NameRuleInput,
+) -> Result<NameRule, String> {
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     tokio::task::spawn_blocking(move || {
         let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
-        settings::set_directory_template(&conn, &trimmed).map_err(|e| e.to_string())
+        name_rules::update_rule(&conn, rule_id, &rule).map_err(|e| e.to_string())
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-async fn validate_template(template: String) -> Result<(), String> {
-    template::validate_template(&template).map_err(|e| e.to_string())
+async fn delete_folder_name_rule(app: tauri::AppHandle, rule_id: i64) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        name_rules::delete_rule(&conn, rule_id).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-async fn set_type_labels(
+async fn list_library_roots(app: tauri::AppHandle) -> Result<Vec<LibraryRoot>, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        roots::list_roots(&conn).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn add_library_root(
     app: tauri::AppHandle,
-    image_label: String,
-    folder_label: String,
-) -> Result<(), String> {
-    let image_label = image_label.trim().to_string();
-    let folder_label = folder_label.trim().to_string();
-    if image_label.is_empty() || folder_label.is_empty() {
-        return Err("ラベルは空にできません".to_string());
-    }
+    name: String,
+    path: String,
+) -> Result<LibraryRoot, String> {
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     tokio::task::spawn_blocking(move || {
         let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
-        settings::set_type_label_image(&conn, &image_label).map_err(|e| e.to_string())?;
-        settings::set_type_label_folder(&conn, &folder_label).map_err(|e| e.to_string())
+        let root = roots::create_root(&conn, &name, &path).map_err(|e| e.to_string())?;
+        if let Err(e) = restart_watcher(&app) {
+            eprintln!("Failed to restart library watcher: {}", e);
+        }
+        Ok(root)
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-async fn preview_template(app: tauri::AppHandle, template: String) -> Result<String, String> {
-    template::validate_template(&template).map_err(|e| e.to_string())?;
+async fn rename_library_root(
+    app: tauri::AppHandle,
+    root_id: i64,
+    name: String,
+) -> Result<LibraryRoot, String> {
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     tokio::task::spawn_blocking(move || {
         let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
-        let folder_label = settings::get_type_label_folder(&conn).map_err(|e| e.to_string())?;
-        let mut metadata = template::sample_metadata();
-        metadata.work_type = Some(folder_label);
-        Ok(template::render_template(&template, &metadata))
+        roots::rename_root(&conn, root_id, &name).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Points a root at its volume's new mount point; the watcher is restarted
+/// so works under it are watched at their new location.
+#[tauri::command]
+async fn rebase_library_root(
+    app: tauri::AppHandle,
+    root_id: i64,
+    path: String,
+) -> Result<LibraryRoot, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        let root = roots::rebase_root(&conn, root_id, &path).map_err(|e| e.to_string())?;
+        if let Err(e) = restart_watcher(&app) {
+            eprintln!("Failed to restart library watcher: {}", e);
+        }
+        Ok(root)
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-async fn parse_folder_name(folder_name: String) -> Result<ParsedMetadata, String> {
-    Ok(importer::parse_folder_name(&folder_name))
+async fn remove_library_root(app: tauri::AppHandle, root_id: i64) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        roots::delete_root(&conn, root_id).map_err(|e| e.to_string())?;
+        if let Err(e) = restart_watcher(&app) {
+            eprintln!("Failed to restart library watcher: {}", e);
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
 async fn preview_import_path(
     app: tauri::AppHandle,
+    source_path: String,
     metadata: WorkMetadata,
 ) -> Result<String, String> {
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     tokio::task::spawn_blocking(move || {
         let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
-        let library_root = settings::get_library_root(&conn)
-            .map_err(|e| e.to_string())?
-            .ok_or_else(|| "ライブラリルートが設定されていません".to_string())?;
-        let template_str = settings::get_directory_template(&conn)
-            .map_err(|e| e.to_string())?
-            .ok_or_else(|| "ディレクトリテンプレートが設定されていません".to_string())?;
-        Ok(importer::preview_import_path(
-            std::path::Path::new(&library_root),
-            &template_str,
-            &metadata,
-        ))
+        importer::preview_source_destination(&conn, &PathBuf::from(source_path), &metadata)
+            .map_err(|e| e.to_string())
     })
     .await
     .map_err(|e| e.to_string())?
@@ -193,7 +815,11 @@ async fn import_work(
 ) -> Result<ImportResult, String> {
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     tokio::task::spawn_blocking(move || {
-        importer::import_work(&request, &app_data_dir).map_err(|e| e.to_string())
+        let result = importer::import_work(&request, &app_data_dir).map_err(|e| e.to_string())?;
+        if let Err(e) = refresh_watcher(&app) {
+            eprintln!("Failed to restart library watcher: {}", e);
+        }
+        Ok(result)
     })
     .await
     .map_err(|e| e.to_string())?
@@ -215,6 +841,56 @@ async fn discover_folders(
     .map_err(|e| e.to_string())?
 }
 
+#[tauri::command]
+async fn run_doctor(
+    app: tauri::AppHandle,
+    on_progress: tauri::ipc::Channel<doctor::DoctorProgress>,
+) -> Result<doctor::DoctorReport, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        doctor::run_doctor(&app_data_dir, &on_progress).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn relink_work(
+    app: tauri::AppHandle,
+    work_id: i64,
+    new_path: String,
+) -> Result<WorkDetail, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        doctor::relink_work(&conn, work_id, &PathBuf::from(new_path)).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn recount_pages(app: tauri::AppHandle, work_ids: Vec<i64>) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let mut conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        doctor::recount_pages(&mut conn, &work_ids).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn backfill_hashes(app: tauri::AppHandle, work_ids: Vec<i64>) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let mut conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        doctor::backfill_hashes(&mut conn, &work_ids).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
 #[tauri::command]
 async fn bulk_import(
     app: tauri::AppHandle,
@@ -223,7 +899,58 @@ async fn bulk_import(
 ) -> Result<BulkImportSummary, String> {
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     tokio::task::spawn_blocking(move || {
-        importer::bulk_import(&requests, &app_data_dir, &on_progress).map_err(|e| e.to_string())
+        let summary = importer::bulk_import(&requests, &app_data_dir, &on_progress)
+            .map_err(|e| e.to_string())?;
+        if let Err(e) = refresh_watcher(&app) {
+            eprintln!("Failed to restart library watcher: {}", e);
+        }
+        Ok(summary)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn resume_import_job(
+    app: tauri::AppHandle,
+    job_id: i64,
+    on_progress: tauri::ipc::Channel<BulkImportProgress>,
+) -> Result<BulkImportSummary, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let summary = importer::run_import_job(job_id, &app_data_dir, &on_progress)
+            .map_err(|e| e.to_string())?;
+        if let Err(e) = refresh_watcher(&app) {
+            eprintln!("Failed to restart library watcher: {}", e);
+        }
+        Ok(summary)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn cancel_import_job(job_id: i64) -> Result<(), String> {
+    import_jobs::request_cancel(job_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_import_jobs(app: tauri::AppHandle) -> Result<Vec<ImportJob>, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        import_jobs::list_unfinished_jobs(&conn).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn delete_import_job(app: tauri::AppHandle, job_id: i64) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        import_jobs::delete_job(&conn, job_id).map_err(|e| e.to_string())
     })
     .await
     .map_err(|e| e.to_string())?
@@ -232,18 +959,23 @@ async fn bulk_import(
 #[tauri::command]
 async fn preview_relocation(
     app: tauri::AppHandle,
+    work_type: String,
     new_template: String,
 ) -> Result<Vec<RelocationPreview>, String> {
-    let trimmed = new_template.trim().to_string();
-    template::validate_template(&trimmed).map_err(|e| e.to_string())?;
+    let new_template = normalize_type_template(&work_type, &new_template)?;
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     tokio::task::spawn_blocking(move || {
         let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
         let library_root = settings::get_library_root(&conn)
             .map_err(|e| e.to_string())?
             .ok_or_else(|| "ライブラリルートが設定されていません".to_string())?;
-        relocator::preview_relocation(&conn, std::path::Path::new(&library_root), &trimmed)
-            .map_err(|e| e.to_string())
+        relocator::preview_relocation(
+            &conn,
+            std::path::Path::new(&library_root),
+            &work_type,
+            new_template.as_deref(),
+        )
+        .map_err(|e| e.to_string())
     })
     .await
     .map_err(|e| e.to_string())?
@@ -252,15 +984,44 @@ async fn preview_relocation(
 #[tauri::command]
 async fn relocate_works(
     app: tauri::AppHandle,
+    work_type: String,
     new_template: String,
     on_progress: tauri::ipc::Channel<RelocationProgress>,
 ) -> Result<(), String> {
-    let trimmed = new_template.trim().to_string();
-    template::validate_template(&trimmed).map_err(|e| e.to_string())?;
+    let new_template = normalize_type_template(&work_type, &new_template)?;
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     tokio::task::spawn_blocking(move || {
-        relocator::execute_relocation(&app_data_dir, &trimmed, &on_progress)
-            .map_err(|e| e.to_string())
+        relocator::execute_relocation(
+            &app_data_dir,
+            &work_type,
+            new_template.as_deref(),
+            &on_progress,
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn get_last_relocation(app: tauri::AppHandle) -> Result<Option<LastRelocation>, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_db(&app_data_dir).map_err(|e| e.to_string())?;
+        relocator::last_relocation(&conn).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn undo_relocation(
+    app: tauri::AppHandle,
+    on_progress: tauri::ipc::Channel<RelocationProgress>,
+) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || {
+        relocator::undo_last_relocation(&app_data_dir, &on_progress).map_err(|e| e.to_string())
     })
     .await
     .map_err(|e| e.to_string())?
@@ -287,6 +1048,96 @@ pub fn run() {
             sql: include_str!("../migrations/003_allow_folder_work_type.sql"),
             kind: MigrationKind::Up,
         },
+        Migration {
+            version: 4,
+            description: "add_works_fts",
+            sql: include_str!("../migrations/004_add_works_fts.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 5,
+            description: "add_sort_columns_and_indexes",
+            sql: include_str!("../migrations/005_add_sort_columns_and_indexes.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 6,
+            description: "add_content_hash",
+            sql: include_str!("../migrations/006_add_content_hash.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 7,
+            description: "add_page_hashes",
+            sql: include_str!("../migrations/007_add_page_hashes.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "add_perceptual_hash",
+            sql: include_str!("../migrations/008_add_perceptual_hash.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "add_import_jobs",
+            sql: include_str!("../migrations/009_add_import_jobs.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 10,
+            description: "add_file_operations",
+            sql: include_str!("../migrations/010_add_file_operations.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 11,
+            description: "add_file_operation_strategy",
+            sql: include_str!("../migrations/011_add_file_operation_strategy.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 12,
+            description: "add_relocation_runs",
+            sql: include_str!("../migrations/012_add_relocation_runs.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 13,
+            description: "add_relocation_run_work_type",
+            sql: include_str!("../migrations/013_add_relocation_run_work_type.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 14,
+            description: "add_folder_name_rules",
+            sql: include_str!("../migrations/014_add_folder_name_rules.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 15,
+            description: "add_external_works",
+            sql: include_str!("../migrations/015_add_external_works.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 16,
+            description: "add_library_roots",
+            sql: include_str!("../migrations/016_add_library_roots.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 17,
+            description: "add_relocation_run_item_roots",
+            sql: include_str!("../migrations/017_add_relocation_run_item_roots.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 18,
+            description: "add_library_file_operations",
+            sql: include_str!("../migrations/018_add_library_file_operations.sql"),
+            kind: MigrationKind::Up,
+        },
     ];
 
     tauri::Builder::default()
@@ -298,6 +1149,31 @@ pub fn run() {
         )
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            if let Ok(resource_dir) = app.path().resource_dir() {
+                pdf::set_library_dir(resource_dir.join("pdfium"));
+            }
+            app.manage(WatcherState::default());
+            // Finish imports and moves interrupted by a crash before anything
+            // else touches the library.
+            if let Ok(app_data_dir) = app.path().app_data_dir() {
+                match db::open_db(&app_data_dir).and_then(|conn| journal::recover(&conn)) {
+                    Ok(summary) if summary.rolled_forward + summary.rolled_back > 0 => eprintln!(
+                        "Recovered interrupted file operations: {} rolled forward, {} rolled back",
+                        summary.rolled_forward, summary.rolled_back
+                    ),
+                    Ok(_) => {}
+                    Err(e) => eprintln!("Failed to recover interrupted file operations: {}", e),
+                }
+            }
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = restart_watcher(&handle) {
+                    eprintln!("Failed to start library watcher: {}", e);
+                }
+            });
+            Ok(())
+        })
         .register_uri_scheme_protocol("sharaku", |ctx, request| {
             let uri = request.uri().to_string();
             match viewer::parse_view_uri(&uri) {
@@ -318,21 +1194,66 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             list_works,
+            list_works_page,
+            mark_work_read,
+            search_works,
+            find_similar_works,
             get_thumbnail,
             get_work,
+            update_work,
+            delete_works,
             get_settings,
-            set_library_root,
+            change_library_root,
             set_directory_template,
             set_type_labels,
+            set_import_workers,
+            set_extra_files,
             validate_template,
             preview_template,
             parse_folder_name,
+            read_import_metadata,
+            test_folder_name_rules,
+            list_folder_name_rules,
+            create_folder_name_rule,
+            update_folder_name_rule,
+            delete_folder_name_rule,
+            list_library_roots,
+            add_library_root,
+            rename_library_root,
+            rebase_library_root,
+            remove_library_root,
             preview_import_path,
             import_work,
             discover_folders,
             bulk_import,
+            resume_import_job,
+            cancel_import_job,
+            list_import_jobs,
+            delete_import_job,
+            run_doctor,
+            relink_work,
+            recount_pages,
+            backfill_hashes,
             preview_relocation,
             relocate_works,
+            get_last_relocation,
+            undo_relocation,
+            list_tags,
+            create_tag,
+            update_tag,
+            delete_tag,
+            add_work_tags,
+            remove_work_tags,
+            merge_tags,
+            list_playlists,
+            create_playlist,
+            rename_playlist,
+            delete_playlist,
+            list_playlist_works,
+            add_playlist_works,
+            remove_playlist_works,
+            reorder_playlist,
+            next_work,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,259 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::importer::ParsedMetadata;
+
+/// Capture group names a rule may use, one per `ParsedMetadata` field.
+const FIELD_NAMES: &[&str] = &[
+    "title", "artist", "circle", "year", "genre", "origin", "event", "language",
+];
+
+/// Rules tried after the user's own, oldest convention last.
+const BUILTIN_RULES: &[(&str, &str)] = &[
+    (
+        "同人誌 (イベント) [サークル (作者)] タイトル (原作) [言語]",
+        r"^(?:\((?P<event>[^)]+)\)\s*)?\[(?P<circle>[^\]()]+?)\s*\((?P<artist>[^)]+)\)\]\s*(?P<title>.+?)(?:\s*\((?P<origin>[^)]+)\))?(?:\s*\[(?P<language>[^\]]+)\])?$",
+    ),
+    (
+        "[作者] タイトル",
+        r"^\[(?P<artist>[^\]]+)\]\s*(?P<title>.+)$",
+    ),
+    ("作者 - タイトル", r"^(?P<artist>.+?) - (?P<title>.+)$"),
+];
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NameRule {
+    pub id: i64,
+    pub name: String,
+    pub pattern: String,
+    /// Lower values are tried first.
+    pub priority: i64,
+    pub enabled: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NameRuleInput {
+    pub name: String,
+    pub pattern: String,
+    pub priority: i64,
+    pub enabled: bool,
+}
+
+/// Which rule a folder name matched, and what it yielded.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderNameMatch {
+    /// `None` when no rule matched and the whole name became the title.
+    pub rule: Option<String>,
+    pub builtin: bool,
+    pub metadata: ParsedMetadata,
+}
+
+/// Compiles a rule pattern. Every named group must be a metadata field, and
+/// `title` is required.
+pub fn compile_pattern(pattern: &str) -> Result<Regex, AppError> {
+    let regex = Regex::new(pattern)
+        .map_err(|e| AppError::NameRuleError(format!("正規表現が不正です: {}", e)))?;
+    let mut has_title = false;
+    for name in regex.capture_names().flatten() {
+        if !FIELD_NAMES.contains(&name) {
+            return Err(AppError::NameRuleError(format!(
+                "未知のグループ名です: {} (使用可能: {})",
+                name,
+                FIELD_NAMES.join(", ")
+            )));
+        }
+        has_title |= name == "title";
+    }
+    if !has_title {
+        return Err(AppError::NameRuleError(
+            "(?P<title>...) のグループは必須です".to_string(),
+        ));
+    }
+    Ok(regex)
+}
+
+fn normalize_input(input: &NameRuleInput) -> Result<NameRuleInput, AppError> {
+    let name = input.name.trim();
+    if name.is_empty() {
+        return Err(AppError::NameRuleError(
+            "ルール名は空にできません".to_string(),
+        ));
+    }
+    compile_pattern(&input.pattern)?;
+    Ok(NameRuleInput {
+        name: name.to_string(),
+        pattern: input.pattern.clone(),
+        priority: input.priority,
+        enabled: input.enabled,
+    })
+}
+
+fn row_to_rule(row: &rusqlite::Row) -> rusqlite::Result<NameRule> {
+    Ok(NameRule {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        pattern: row.get(2)?,
+        priority: row.get(3)?,
+        enabled: row.get(4)?,
+    })
+}
+
+pub fn get_rule(conn: &Connection, rule_id: i64) -> Result<NameRule, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, name, pattern, priority, enabled FROM folder_name_rules WHERE id = ?1",
+    )?;
+    stmt.query_row([rule_id], row_to_rule).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => AppError::NotFound,
+        other => AppError::Database(other),
+    })
+}
+
+/// Lists the user's rules in the order they are tried.
+pub fn list_rules(conn: &Connection) -> Result<Vec<NameRule>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, name, pattern, priority, enabled FROM folder_name_rules ORDER BY priority, id",
+    )?;
+    let rows = stmt.query_map([], row_to_rule)?;
+    let mut rules = Vec::new();
+    for row in rows {
+        rules.push(row?);
+    }
+    Ok(rules)
+}
+
+pub fn create_rule(conn: &Connection, input: &NameRuleInput) -> Result<NameRule, AppError> {
+    let input = normalize_input(input)?;
+    conn.execute(
+        "INSERT INTO folder_name_rules (name, pattern, priority, enabled) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![input.name, input.pattern, input.priority, input.enabled],
+    )?;
+    get_rule(conn, conn.last_insert_rowid())
+}
+
+pub fn update_rule(
+    conn: &Connection,
+    rule_id: i64,
+    input: &NameRuleInput,
+) -> Result<NameRule, AppError> {
+    let input = normalize_input(input)?;
+    let updated = conn.execute(
+        "UPDATE folder_name_rules SET name = ?1, pattern = ?2, priority = ?3, enabled = ?4 WHERE id = ?5",
+        rusqlite::params![input.name, input.pattern, input.priority, input.enabled, rule_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound);
+    }
+    get_rule(conn, rule_id)
+}
+
+pub fn delete_rule(conn: &Connection, rule_id: i64) -> Result<(), AppError> {
+    let deleted = conn.execute("DELETE FROM folder_name_rules WHERE id = ?1", [rule_id])?;
+    if deleted == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+struct CompiledRule {
+    name: String,
+    builtin: bool,
+    regex: Regex,
+}
+
+fn builtin_rules() -> &'static [CompiledRule] {
+    static RULES: OnceLock<Vec<CompiledRule>> = OnceLock::new();
+    RULES.get_or_init(|| {
+        BUILTIN_RULES
+            .iter()
+            .map(|(name, pattern)| CompiledRule {
+                name: name.to_string(),
+                builtin: true,
+                regex: Regex::new(pattern).expect("built-in folder name rule"),
+            })
+            .collect()
+    })
+}
+
+/// Reads metadata from folder names with the enabled user rules in priority
+/// order, then the built-in ones.
+pub struct FolderNameParser {
+    rules: Vec<CompiledRule>,
+}
+
+impl FolderNameParser {
+    pub fn load(conn: &Connection) -> Result<Self, AppError> {
+        let mut rules = Vec::new();
+        for rule in list_rules(conn)? {
+            if !rule.enabled {
+                continue;
+            }
+            // Patterns are checked when saved; one that no longer compiles is skipped.
+            if let Ok(regex) = compile_pattern(&rule.pattern) {
+                rules.push(CompiledRule {
+                    name: rule.name,
+                    builtin: false,
+                    regex,
+                });
+            }
+        }
+        Ok(FolderNameParser { rules })
+    }
+
+    /// A parser with only the built-in rules.
+    pub fn builtin() -> Self {
+        FolderNameParser { rules: Vec::new() }
+    }
+
+    pub fn parse(&self, folder_name: &str) -> FolderNameMatch {
+        for rule in self.rules.iter().chain(builtin_rules()) {
+            if let Some(metadata) = apply_rule(&rule.regex, folder_name) {
+                return FolderNameMatch {
+                    rule: Some(rule.name.clone()),
+                    builtin: rule.builtin,
+                    metadata,
+                };
+            }
+        }
+        FolderNameMatch {
+            rule: None,
+            builtin: false,
+            metadata: ParsedMetadata {
+                title: folder_name.to_string(),
+                ..ParsedMetadata::default()
+            },
+        }
+    }
+}
+
+/// Applies one rule. It only counts as a match when the title is not empty.
+fn apply_rule(regex: &Regex, folder_name: &str) -> Option<ParsedMetadata> {
+    let captures = regex.captures(folder_name)?;
+    let field = |name: &str| {
+        captures
+            .name(name)
+            .map(|m| m.as_str().trim())
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    Some(ParsedMetadata {
+        title: field("title")?,
+        artist: field("artist"),
+        circle: field("circle"),
+        year: field("year").and_then(|year| year.parse().ok()),
+        genre: field("genre"),
+        origin: field("origin"),
+        event: field("event"),
+        language: field("language"),
+    })
+}
+
+#[cfg(test)]
+#[path = "tests/name_rules.rs"]
+mod tests;
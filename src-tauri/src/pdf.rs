@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, RgbaImage};
+use pdfium_render::prelude::{PdfRenderConfig, Pdfium, PdfiumError};
+
+use crate::error::AppError;
+
+const VIEW_MAX_WIDTH: i32 = 2400;
+const VIEW_MAX_HEIGHT: i32 = 3200;
+const VIEW_JPEG_QUALITY: u8 = 90;
+const THUMBNAIL_SOURCE_MAX_WIDTH: i32 = 400;
+const THUMBNAIL_SOURCE_MAX_HEIGHT: i32 = 560;
+
+static LIBRARY_DIR: OnceLock<PathBuf> = OnceLock::new();
+static PDFIUM: OnceLock<Pdfium> = OnceLock::new();
+
+/// Registers the directory holding the bundled PDFium library. Must be called
+/// before the first PDF is opened; the system library is used as a fallback.
+pub fn set_library_dir(dir: PathBuf) {
+    let _ = LIBRARY_DIR.set(dir);
+}
+
+/// Binds PDFium on first use. A failure is reported but not remembered, so
+/// the next PDF tries again.
+fn pdfium() -> Result<&'static Pdfium, AppError> {
+    if let Some(pdfium) = PDFIUM.get() {
+        return Ok(pdfium);
+    }
+    let bundled = LIBRARY_DIR
+        .get()
+        .map(Pdfium::pdfium_platform_library_name_at_path);
+    let bindings = match bundled.as_ref().map(Pdfium::bind_to_library) {
+        Some(Ok(bindings)) => bindings,
+        bundled_result => Pdfium::bind_to_system_library().map_err(|e| {
+            let cause = match bundled_result {
+                Some(Err(bundled_error)) => bundled_error,
+                _ => e,
+            };
+            AppError::Pdf(format!(
+                "PDFium ライブラリを読み込めません ({}): {}",
+                bundled.as_deref().map_or_else(
+                    || "同梱ライブラリの場所が未設定".to_string(),
+                    |path| path.display().to_string()
+                ),
+                cause
+            ))
+        })?,
+    };
+    Ok(PDFIUM.get_or_init(|| Pdfium::new(bindings)))
+}
+
+fn pdf_error(e: PdfiumError) -> AppError {
+    AppError::Pdf(e.to_string())
+}
+
+pub fn page_count(pdf_path: &Path) -> Result<usize, AppError> {
+    let document = pdfium()?
+        .load_pdf_from_file(pdf_path, None)
+        .map_err(pdf_error)?;
+    Ok(document.pages().len() as usize)
+}
+
+pub fn render_page(
+    pdf_path: &Path,
+    page_index: usize,
+    max_width: i32,
+    max_height: i32,
+) -> Result<DynamicImage, AppError> {
+    let document = pdfium()?
+        .load_pdf_from_file(pdf_path, None)
+        .map_err(pdf_error)?;
+    let pages = document.pages();
+    if page_index >= pages.len() as usize {
+        return Err(AppError::NotFound);
+    }
+    let page = pages.get(page_index as u16).map_err(pdf_error)?;
+
+    let config = PdfRenderConfig::new()
+        .set_maximum_width(max_width)
+        .set_maximum_height(max_height);
+    let bitmap = page.render_with_config(&config).map_err(pdf_error)?;
+
+    let rgba = RgbaImage::from_raw(
+        bitmap.width() as u32,
+        bitmap.height() as u32,
+        bitmap.as_rgba_bytes(),
+    )
+    .ok_or_else(|| AppError::Pdf("レンダリング結果が不正です".to_string()))?;
+    Ok(DynamicImage::ImageRgba8(rgba))
+}
+
+/// Rasterizes a page at viewing resolution and encodes it as JPEG.
+pub fn render_page_as_jpeg(pdf_path: &Path, page_index: usize) -> Result<Vec<u8>, AppError> {
+    let img = render_page(pdf_path, page_index, VIEW_MAX_WIDTH, VIEW_MAX_HEIGHT)?;
+    let mut data = Vec::new();
+    let encoder = JpegEncoder::new_with_quality(&mut data, VIEW_JPEG_QUALITY);
+    img.to_rgb8().write_with_encoder(encoder)?;
+    Ok(data)
+}
+
+/// Rasterizes the first page at a size suited to thumbnail generation.
+pub fn render_first_page(pdf_path: &Path) -> Result<DynamicImage, AppError> {
+    render_page(
+        pdf_path,
+        0,
+        THUMBNAIL_SOURCE_MAX_WIDTH,
+        THUMBNAIL_SOURCE_MAX_HEIGHT,
+    )
+}
+
+#[cfg(test)]
+#[path = "tests/pdf.rs"]
+mod tests;
@@ -0,0 +1,257 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::db::WorkSummary;
+use crate::error::AppError;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Playlist {
+    pub id: i64,
+    pub name: String,
+    pub item_count: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+fn normalize_name(name: &str) -> Result<String, AppError> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err(AppError::PlaylistError(
+            "プレイリスト名は空にできません".to_string(),
+        ));
+    }
+    Ok(trimmed.to_string())
+}
+
+fn touch_playlist(conn: &Connection, playlist_id: i64) -> Result<(), AppError> {
+    let updated = conn.execute(
+        "UPDATE playlists SET updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = ?1",
+        [playlist_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+pub fn get_playlist(conn: &Connection, playlist_id: i64) -> Result<Playlist, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT p.id, p.name, (SELECT COUNT(*) FROM playlist_items i WHERE i.playlist_id = p.id), p.created_at, p.updated_at FROM playlists p WHERE p.id = ?1",
+    )?;
+    stmt.query_row([playlist_id], |row| {
+        Ok(Playlist {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            item_count: row.get(2)?,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+        })
+    })
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => AppError::NotFound,
+        other => AppError::Database(other),
+    })
+}
+
+pub fn list_playlists(conn: &Connection) -> Result<Vec<Playlist>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT p.id, p.name, (SELECT COUNT(*) FROM playlist_items i WHERE i.playlist_id = p.id), p.created_at, p.updated_at FROM playlists p ORDER BY p.name, p.id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Playlist {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            item_count: row.get(2)?,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+        })
+    })?;
+    let mut playlists = Vec::new();
+    for row in rows {
+        playlists.push(row?);
+    }
+    Ok(playlists)
+}
+
+pub fn create_playlist(conn: &Connection, name: &str) -> Result<Playlist, AppError> {
+    let name = normalize_name(name)?;
+    conn.execute("INSERT INTO playlists (name) VALUES (?1)", [&name])?;
+    get_playlist(conn, conn.last_insert_rowid())
+}
+
+pub fn rename_playlist(conn: &Connection, playlist_id: i64, name: &str) -> Result<(), AppError> {
+    let name = normalize_name(name)?;
+    let updated = conn.execute(
+        "UPDATE playlists SET name = ?1, updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = ?2",
+        rusqlite::params![name, playlist_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+pub fn delete_playlist(conn: &Connection, playlist_id: i64) -> Result<(), AppError> {
+    let deleted = conn.execute("DELETE FROM playlists WHERE id = ?1", [playlist_id])?;
+    if deleted == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+pub fn list_playlist_works(
+    conn: &Connection,
+    playlist_id: i64,
+) -> Result<Vec<WorkSummary>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT w.id, w.title, w.type, w.page_count, w.created_at, w.root_id FROM playlist_items i JOIN works w ON w.id = i.work_id WHERE i.playlist_id = ?1 ORDER BY i.position",
+    )?;
+    let rows = stmt.query_map([playlist_id], |row| {
+        Ok(WorkSummary {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            work_type: row.get(2)?,
+            page_count: row.get(3)?,
+            created_at: row.get(4)?,
+            root_id: row.get(5)?,
+        })
+    })?;
+    let mut works = Vec::new();
+    for row in rows {
+        works.push(row?);
+    }
+    Ok(works)
+}
+
+fn playlist_work_ids(conn: &Connection, playlist_id: i64) -> Result<Vec<i64>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT work_id FROM playlist_items WHERE playlist_id = ?1 ORDER BY position",
+    )?;
+    let rows = stmt.query_map([playlist_id], |row| row.get(0))?;
+    let mut ids = Vec::new();
+    for row in rows {
+        ids.push(row?);
+    }
+    Ok(ids)
+}
+
+/// Rewrites positions as 0..n in the given order. Positions are first moved to
+/// negative values so the UNIQUE(playlist_id, position) constraint never trips mid-update.
+fn write_positions(conn: &Connection, playlist_id: i64, work_ids: &[i64]) -> Result<(), AppError> {
+    conn.execute(
+        "UPDATE playlist_items SET position = -position - 1 WHERE playlist_id = ?1",
+        [playlist_id],
+    )?;
+    let mut stmt = conn.prepare_cached(
+        "UPDATE playlist_items SET position = ?1 WHERE playlist_id = ?2 AND work_id = ?3",
+    )?;
+    for (position, work_id) in work_ids.iter().enumerate() {
+        stmt.execute(rusqlite::params![position as i64, playlist_id, work_id])?;
+    }
+    Ok(())
+}
+
+/// Renumbers positions as 0..n, closing gaps left when works are deleted.
+pub(crate) fn compact_positions(conn: &Connection, playlist_id: i64) -> Result<(), AppError> {
+    let work_ids = playlist_work_ids(conn, playlist_id)?;
+    write_positions(conn, playlist_id, &work_ids)
+}
+
+pub(crate) fn playlists_containing(conn: &Connection, work_id: i64) -> Result<Vec<i64>, AppError> {
+    let mut stmt =
+        conn.prepare_cached("SELECT playlist_id FROM playlist_items WHERE work_id = ?1")?;
+    let rows = stmt.query_map([work_id], |row| row.get(0))?;
+    let mut ids = Vec::new();
+    for row in rows {
+        ids.push(row?);
+    }
+    Ok(ids)
+}
+
+/// Appends works to the end of the playlist. Works already in the playlist are skipped.
+pub fn add_works_to_playlist(
+    conn: &mut Connection,
+    playlist_id: i64,
+    work_ids: &[i64],
+) -> Result<(), AppError> {
+    let tx = conn.transaction()?;
+    touch_playlist(&tx, playlist_id)?;
+    let mut next_position: i64 = tx.query_row(
+        "SELECT COALESCE(MAX(position) + 1, 0) FROM playlist_items WHERE playlist_id = ?1",
+        [playlist_id],
+        |row| row.get(0),
+    )?;
+    for &work_id in work_ids {
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO playlist_items (playlist_id, work_id, position) VALUES (?1, ?2, ?3)",
+            rusqlite::params![playlist_id, work_id, next_position],
+        )?;
+        if inserted > 0 {
+            next_position += 1;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn remove_works_from_playlist(
+    conn: &mut Connection,
+    playlist_id: i64,
+    work_ids: &[i64],
+) -> Result<(), AppError> {
+    let tx = conn.transaction()?;
+    touch_playlist(&tx, playlist_id)?;
+    for &work_id in work_ids {
+        tx.execute(
+            "DELETE FROM playlist_items WHERE playlist_id = ?1 AND work_id = ?2",
+            rusqlite::params![playlist_id, work_id],
+        )?;
+    }
+    let remaining = playlist_work_ids(&tx, playlist_id)?;
+    write_positions(&tx, playlist_id, &remaining)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Reorders the playlist to match `work_ids`, which must list every item exactly once.
+pub fn reorder_playlist(
+    conn: &mut Connection,
+    playlist_id: i64,
+    work_ids: &[i64],
+) -> Result<(), AppError> {
+    let tx = conn.transaction()?;
+    touch_playlist(&tx, playlist_id)?;
+
+    let mut current = playlist_work_ids(&tx, playlist_id)?;
+    let mut requested = work_ids.to_vec();
+    current.sort_unstable();
+    requested.sort_unstable();
+    if current != requested {
+        return Err(AppError::PlaylistError(
+            "並び順がプレイリストの内容と一致しません".to_string(),
+        ));
+    }
+
+    write_positions(&tx, playlist_id, work_ids)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Returns the work following `work_id` in the playlist, or `None` at the end.
+pub fn next_work_id(
+    conn: &Connection,
+    playlist_id: i64,
+    work_id: i64,
+) -> Result<Option<i64>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT next.work_id FROM playlist_items cur JOIN playlist_items next ON next.playlist_id = cur.playlist_id AND next.position > cur.position WHERE cur.playlist_id = ?1 AND cur.work_id = ?2 ORDER BY next.position LIMIT 1",
+    )?;
+    Ok(stmt
+        .query_row(rusqlite::params![playlist_id, work_id], |row| row.get(0))
+        .optional()?)
+}
+
+#[cfg(test)]
+#[path = "tests/playlists.rs"]
+mod tests;
@@ -1,13 +1,18 @@
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
+use rusqlite::{Connection, OptionalExtension};
 use serde::Serialize;
 use tauri::ipc::Channel;
 
 use crate::db::{self, WorkDetail};
 use crate::error::AppError;
 use crate::importer;
-use crate::settings;
+use crate::journal::{self, FileOperation, OperationKind};
+use crate::roots;
+use crate::settings::{self, ExtraFiles};
 use crate::template::{self, WorkMetadata};
+use crate::transfer::{self, MoveStrategy};
 
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase", tag = "type")]
@@ -21,6 +26,13 @@ pub enum RelocationProgress {
         title: String,
     },
     #[serde(rename_all = "camelCase")]
+    Moved {
+        current: usize,
+        total: usize,
+        title: String,
+        strategy: MoveStrategy,
+    },
+    #[serde(rename_all = "camelCase")]
     Completed {
         relocated: usize,
         skipped: usize,
@@ -30,16 +42,35 @@ pub enum RelocationProgress {
     Error { message: String },
 }
 
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum LibraryMoveProgress {
+    /// A file of the library tree has been copied. Not sent when the tree is
+    /// moved by renaming its folder.
+    #[serde(rename_all = "camelCase")]
+    Copied {
+        current: usize,
+        total: usize,
+        file: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Completed {
+        works: usize,
+        strategy: MoveStrategy,
+    },
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RelocationPreview {
     pub work_id: i64,
     pub title: String,
+    pub work_type: String,
     pub old_path: String,
     pub new_path: String,
 }
 
-fn work_detail_to_metadata(work: &WorkDetail, type_label: &str) -> WorkMetadata {
+fn work_detail_to_metadata(work: &WorkDetail) -> WorkMetadata {
     WorkMetadata {
         title: work.title.clone(),
         artist: work.artist.clone(),
@@ -47,27 +78,133 @@ fn work_detail_to_metadata(work: &WorkDetail, type_label: &str) -> WorkMetadata
         genre: work.genre.clone(),
         circle: work.circle.clone(),
         origin: work.origin.clone(),
-        work_type: Some(type_label.to_string()),
+        work_type: None,
     }
 }
 
+/// The template and `{type}` label that place each work type. Types without
+/// any template are left where they are.
+pub(crate) struct Placement {
+    rules: HashMap<String, (String, String)>,
+}
+
+impl Placement {
+    /// Placement under the templates as currently saved.
+    pub(crate) fn current(conn: &Connection) -> Result<Self, AppError> {
+        Self::load(conn, None)
+    }
+
+    /// Placement after the template of `work_type` is replaced by `template`;
+    /// `None` removes the type's own template.
+    pub(crate) fn with_template(
+        conn: &Connection,
+        work_type: &str,
+        template: Option<&str>,
+    ) -> Result<Self, AppError> {
+        Self::load(conn, Some((work_type, template)))
+    }
+
+    fn load(conn: &Connection, change: Option<(&str, Option<&str>)>) -> Result<Self, AppError> {
+        let own_template = |work_type: &str| -> Result<Option<String>, AppError> {
+            match change {
+                Some((changed, template)) if changed == work_type => {
+                    Ok(template.map(str::to_string))
+                }
+                _ => settings::get_type_template(conn, work_type),
+            }
+        };
+        let folder_template = own_template("folder")?;
+        let mut rules = HashMap::new();
+        for &work_type in settings::WORK_TYPES {
+            let template = own_template(work_type)?.or_else(|| folder_template.clone());
+            if let Some(template) = template.filter(|t| !t.is_empty()) {
+                let label = settings::resolve_type_label(conn, work_type)?;
+                rules.insert(work_type.to_string(), (template, label));
+            }
+        }
+        Ok(Placement { rules })
+    }
+
+    pub(crate) fn template(&self, work_type: &str) -> Option<&str> {
+        self.rules
+            .get(work_type)
+            .map(|(template, _)| template.as_str())
+    }
+
+    /// Templated path for a work, without the extension single-file works carry.
+    pub(crate) fn base_path(
+        &self,
+        library_root: &Path,
+        work_type: &str,
+        mut metadata: WorkMetadata,
+    ) -> Option<PathBuf> {
+        let (template, label) = self.rules.get(work_type)?;
+        metadata.work_type = Some(label.clone());
+        Some(template::resolve_work_path(
+            library_root,
+            template,
+            &metadata,
+        ))
+    }
+}
+
+/// The extension a single-file work keeps when it is relocated.
+pub(crate) fn work_extension(work_type: &str, path: &Path) -> Option<String> {
+    if work_type == "folder" {
+        return None;
+    }
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+}
+
+/// `base` with the `i`-th collision suffix and then the extension, if any.
+fn numbered_path(base: &Path, extension: Option<&str>, i: u32) -> PathBuf {
+    let base_name = base.file_name().unwrap().to_string_lossy();
+    let mut name = if i == 0 {
+        base_name.to_string()
+    } else {
+        format!("{}_{:04x}", base_name, i)
+    };
+    if let Some(extension) = extension {
+        name.push('.');
+        name.push_str(extension);
+    }
+    base.with_file_name(name)
+}
+
+/// Whether relocation may move a work. External works stay where they were
+/// registered, and works on a library root stay on that root's volume unless
+/// they are inside the library root; offline ones cannot be moved at all.
+pub(crate) fn is_relocatable(work: &WorkDetail, library_root: &Path) -> bool {
+    !work.external
+        && !work.offline
+        && (work.root_id.is_none() || Path::new(&work.path).starts_with(library_root))
+}
+
 fn compute_relocation_plan(
     works: &[WorkDetail],
     library_root: &Path,
-    new_template: &str,
-    type_label: &str,
+    placement: &Placement,
 ) -> Vec<RelocationPreview> {
-    let mut used_paths: std::collections::HashSet<String> = std::collections::HashSet::new();
+    let mut used_paths: HashSet<String> = HashSet::new();
     let mut previews = Vec::new();
 
     for work in works {
-        let metadata = work_detail_to_metadata(work, type_label);
-        let base_path = template::resolve_work_path(library_root, new_template, &metadata);
+        if !is_relocatable(work, library_root) {
+            continue;
+        }
+        let Some(base) =
+            placement.base_path(library_root, &work.work_type, work_detail_to_metadata(work))
+        else {
+            continue;
+        };
+        let extension = work_extension(&work.work_type, Path::new(&work.path));
+        let base_path = numbered_path(&base, extension.as_deref(), 0);
         let base_str = base_path.to_string_lossy().to_string();
 
         let new_path =
             if used_paths.contains(&base_str) || (base_path.exists() && base_str != work.path) {
-                make_unique_path(&base_path, &used_paths)
+                make_unique_path(&base, extension.as_deref(), &used_paths)
             } else {
                 base_path
             };
@@ -78,6 +215,7 @@ fn compute_relocation_plan(
             previews.push(RelocationPreview {
                 work_id: work.id,
                 title: work.title.clone(),
+                work_type: work.work_type.clone(),
                 old_path: work.path.clone(),
                 new_path: new_path_str,
             });
@@ -87,11 +225,9 @@ fn compute_relocation_plan(
     previews
 }
 
-fn make_unique_path(base: &Path, used_paths: &std::collections::HashSet<String>) -> PathBuf {
-    let base_name = base.file_name().unwrap().to_string_lossy().to_string();
+fn make_unique_path(base: &Path, extension: Option<&str>, used_paths: &HashSet<String>) -> PathBuf {
     for i in 1u32.. {
-        let dir_name = format!("{}_{:04x}", base_name, i);
-        let candidate = base.with_file_name(&dir_name);
+        let candidate = numbered_path(base, extension, i);
         let candidate_str = candidate.to_string_lossy().to_string();
         if !used_paths.contains(&candidate_str) && !candidate.exists() {
             return candidate;
@@ -100,24 +236,25 @@ fn make_unique_path(base: &Path, used_paths: &std::collections::HashSet<String>)
     unreachable!()
 }
 
+/// Lists the works that would move if the template of `work_type` became
+/// `new_template`, including works of other types that fall back to it.
 pub fn preview_relocation(
     conn: &rusqlite::Connection,
     library_root: &Path,
-    new_template: &str,
+    work_type: &str,
+    new_template: Option<&str>,
 ) -> Result<Vec<RelocationPreview>, AppError> {
-    let works = db::list_folder_works(conn)?;
-    let type_label = settings::get_type_label_folder(conn)?;
-    Ok(compute_relocation_plan(
-        &works,
-        library_root,
-        new_template,
-        &type_label,
-    ))
+    let works = db::list_all_works(conn)?;
+    let placement = Placement::with_template(conn, work_type, new_template)?;
+    Ok(compute_relocation_plan(&works, library_root, &placement))
 }
 
+/// Changes the template of `work_type` and moves every work whose path it
+/// changes, recording the run so it can be undone.
 pub fn execute_relocation(
     app_data_dir: &Path,
-    new_template: &str,
+    work_type: &str,
+    new_template: Option<&str>,
     on_progress: &Channel<RelocationProgress>,
 ) -> Result<(), AppError> {
     let conn = db::open_db(app_data_dir)?;
@@ -125,9 +262,11 @@ pub fn execute_relocation(
         .ok_or_else(|| AppError::RelocationError("ライブラリルートが設定されていません".into()))?;
     let library_root = PathBuf::from(&library_root);
 
-    let works = db::list_folder_works(&conn)?;
-    let type_label = settings::get_type_label_folder(&conn)?;
-    let plan = compute_relocation_plan(&works, &library_root, new_template, &type_label);
+    let works = db::list_all_works(&conn)?;
+    let placement = Placement::with_template(&conn, work_type, new_template)?;
+    let plan = compute_relocation_plan(&works, &library_root, &placement);
+
+    let run_id = begin_run(&conn, work_type, placement.template(work_type))?;
 
     let total = plan.len();
     let _ = on_progress.send(RelocationProgress::Started { total });
@@ -147,26 +286,36 @@ pub fn execute_relocation(
         let new_path = Path::new(&item.new_path);
 
         if !old_path.exists() {
+            let _ = on_progress.send(RelocationProgress::Error {
+                message: format!("作品が見つかりません ({}): {}", item.title, item.old_path),
+            });
             skipped += 1;
             continue;
         }
 
-        match copy_work_files(old_path, new_path) {
-            Ok(()) => {
-                if let Err(e) = db::update_work_path(&conn, item.work_id, &item.new_path) {
-                    let _ = on_progress.send(RelocationProgress::Error {
-                        message: format!("DB更新失敗 ({}): {}", item.title, e),
-                    });
-                    let _ = std::fs::remove_dir_all(new_path);
-                    failed += 1;
-                    continue;
-                }
-                remove_work_files(old_path);
-                cleanup_empty_ancestors(old_path, &library_root);
+        let result = move_work(
+            &conn,
+            item.work_id,
+            &item.work_type,
+            old_path,
+            new_path,
+            &library_root,
+            |tx| {
+                db::update_work_path(tx, item.work_id, &item.new_path)?;
+                record_run_item(tx, run_id, item)
+            },
+        );
+        match result {
+            Ok(strategy) => {
+                let _ = on_progress.send(RelocationProgress::Moved {
+                    current: i + 1,
+                    total,
+                    title: item.title.clone(),
+                    strategy,
+                });
                 relocated += 1;
             }
             Err(e) => {
-                let _ = std::fs::remove_dir_all(new_path);
                 let _ = on_progress.send(RelocationProgress::Error {
                     message: format!("移動失敗 ({}): {}", item.title, e),
                 });
@@ -175,7 +324,7 @@ pub fn execute_relocation(
         }
     }
 
-    settings::set_directory_template(&conn, new_template)?;
+    settings::set_type_template(&conn, work_type, new_template)?;
 
     let _ = on_progress.send(RelocationProgress::Completed {
         relocated,
@@ -186,31 +335,585 @@ pub fn execute_relocation(
     Ok(())
 }
 
-fn copy_work_files(old_path: &Path, new_path: &Path) -> Result<(), AppError> {
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastRelocation {
+    pub work_type: String,
+    pub old_template: Option<String>,
+    pub new_template: String,
+    pub work_count: usize,
+    pub created_at: String,
+}
+
+struct RunItem {
+    id: i64,
+    work_id: i64,
+    old_path: String,
+    new_path: String,
+}
+
+/// Starts recording a relocation run. Only the latest run is kept, so starting a
+/// new one forgets whatever could have been undone before. `new_template` is the
+/// template the type is placed by afterwards, which may be the folder template.
+fn begin_run(
+    conn: &Connection,
+    work_type: &str,
+    new_template: Option<&str>,
+) -> Result<i64, AppError> {
+    let old_template = settings::get_type_template(conn, work_type)?;
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM relocation_runs", [])?;
+    tx.execute(
+        "INSERT INTO relocation_runs (work_type, old_template, new_template) VALUES (?1, ?2, ?3)",
+        rusqlite::params![work_type, old_template, new_template.unwrap_or_default()],
+    )?;
+    let run_id = tx.last_insert_rowid();
+    tx.commit()?;
+    Ok(run_id)
+}
+
+/// Run item paths are stored like work paths, relative to the root containing
+/// them, so the run can still be undone after the library root has changed or
+/// a root has been remounted elsewhere.
+fn record_run_item(
+    conn: &Connection,
+    run_id: i64,
+    item: &RelocationPreview,
+) -> Result<(), AppError> {
+    let (old_root_id, old_location) = roots::split_location(conn, &item.old_path)?;
+    let (new_root_id, new_location) = roots::split_location(conn, &item.new_path)?;
+    conn.execute(
+        "INSERT INTO relocation_run_items (run_id, work_id, old_root_id, old_path, new_root_id, new_path) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            run_id,
+            item.work_id,
+            old_root_id,
+            old_location,
+            new_root_id,
+            new_location,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Returns the id, work type and previous template of the last run.
+fn last_run(conn: &Connection) -> Result<Option<(i64, String, Option<String>)>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, work_type, old_template FROM relocation_runs ORDER BY id DESC LIMIT 1",
+    )?;
+    Ok(stmt
+        .query_row([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .optional()?)
+}
+
+/// Describes the relocation run that `undo_last_relocation` would revert.
+pub fn last_relocation(conn: &Connection) -> Result<Option<LastRelocation>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT r.work_type, r.old_template, r.new_template, r.created_at, \
+         (SELECT COUNT(*) FROM relocation_run_items i WHERE i.run_id = r.id) \
+         FROM relocation_runs r ORDER BY r.id DESC LIMIT 1",
+    )?;
+    let run = stmt
+        .query_row([], |row| {
+            Ok(LastRelocation {
+                work_type: row.get(0)?,
+                old_template: row.get(1)?,
+                new_template: row.get(2)?,
+                created_at: row.get(3)?,
+                work_count: row.get::<_, i64>(4)? as usize,
+            })
+        })
+        .optional()?;
+    Ok(run)
+}
+
+fn list_run_items(conn: &Connection, run_id: i64) -> Result<Vec<RunItem>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT i.id, i.work_id, o.path, i.old_path, n.path, i.new_path \
+         FROM relocation_run_items i \
+         LEFT JOIN library_roots o ON o.id = i.old_root_id \
+         LEFT JOIN library_roots n ON n.id = i.new_root_id \
+         WHERE i.run_id = ?1 ORDER BY i.id DESC",
+    )?;
+    let rows = stmt.query_map([run_id], |row| {
+        let old_root: Option<String> = row.get(2)?;
+        let new_root: Option<String> = row.get(4)?;
+        Ok(RunItem {
+            id: row.get(0)?,
+            work_id: row.get(1)?,
+            old_path: roots::resolve_location(old_root.as_deref(), &row.get::<_, String>(3)?),
+            new_path: roots::resolve_location(new_root.as_deref(), &row.get::<_, String>(5)?),
+        })
+    })?;
+    let mut items = Vec::new();
+    for row in rows {
+        items.push(row?);
+    }
+    Ok(items)
+}
+
+fn forget_run_item(conn: &Connection, item_id: i64) -> Result<(), AppError> {
+    conn.execute("DELETE FROM relocation_run_items WHERE id = ?1", [item_id])?;
+    Ok(())
+}
+
+/// Moves every work of the last relocation run back to where it was and
+/// restores the template that was in effect before. Works that have been moved
+/// again since are left alone. Works that fail to move stay recorded, so the
+/// undo can be retried.
+pub fn undo_last_relocation(
+    app_data_dir: &Path,
+    on_progress: &Channel<RelocationProgress>,
+) -> Result<(), AppError> {
+    let conn = db::open_db(app_data_dir)?;
+    let library_root = settings::get_library_root(&conn)?
+        .ok_or_else(|| AppError::RelocationError("ライブラリルートが設定されていません".into()))?;
+    let library_root = PathBuf::from(&library_root);
+    let (run_id, work_type, old_template) = last_run(&conn)?
+        .ok_or_else(|| AppError::RelocationError("取り消せる再配置がありません".into()))?;
+
+    let items = list_run_items(&conn, run_id)?;
+    let total = items.len();
+    let _ = on_progress.send(RelocationProgress::Started { total });
+
+    let mut relocated = 0usize;
+    let mut skipped = 0usize;
+    let mut failed = 0usize;
+
+    for (i, item) in items.iter().enumerate() {
+        let work = db::get_work(&conn, item.work_id)?;
+        let _ = on_progress.send(RelocationProgress::Moving {
+            current: i + 1,
+            total,
+            title: work.title.clone(),
+        });
+
+        let old_path = Path::new(&item.old_path);
+        let new_path = Path::new(&item.new_path);
+
+        if work.path != item.new_path || !new_path.exists() {
+            let _ = on_progress.send(RelocationProgress::Error {
+                message: format!(
+                    "再配置後に移動されたため戻せません ({}): {}",
+                    work.title, work.path
+                ),
+            });
+            forget_run_item(&conn, item.id)?;
+            skipped += 1;
+            continue;
+        }
+        if old_path.exists() {
+            let _ = on_progress.send(RelocationProgress::Error {
+                message: format!(
+                    "元の場所が使用されています ({}): {}",
+                    work.title, item.old_path
+                ),
+            });
+            failed += 1;
+            continue;
+        }
+
+        let result = move_work(
+            &conn,
+            item.work_id,
+            &work.work_type,
+            new_path,
+            old_path,
+            &library_root,
+            |tx| {
+                db::update_work_path(tx, item.work_id, &item.old_path)?;
+                forget_run_item(tx, item.id)
+            },
+        );
+        match result {
+            Ok(strategy) => {
+                let _ = on_progress.send(RelocationProgress::Moved {
+                    current: i + 1,
+                    total,
+                    title: work.title.clone(),
+                    strategy,
+                });
+                relocated += 1;
+            }
+            Err(e) => {
+                let _ = on_progress.send(RelocationProgress::Error {
+                    message: format!("移動失敗 ({}): {}", work.title, e),
+                });
+                failed += 1;
+            }
+        }
+    }
+
+    settings::set_type_template(&conn, &work_type, old_template.as_deref())?;
+    if failed == 0 {
+        conn.execute("DELETE FROM relocation_runs WHERE id = ?1", [run_id])?;
+    }
+
+    let _ = on_progress.send(RelocationProgress::Completed {
+        relocated,
+        skipped,
+        failed,
+    });
+
+    Ok(())
+}
+
+/// Picks where a work should live under the template, or `None` if it is
+/// already there. A `_NNNN` suffix the work already carries is kept rather than
+/// bumped. `extension` is appended for single-file works.
+pub(crate) fn resolve_relocation_target(
+    base: &Path,
+    extension: Option<&str>,
+    current: &Path,
+) -> Option<PathBuf> {
+    let first = numbered_path(base, extension, 0);
+    if first == current {
+        return None;
+    }
+    if !first.exists() {
+        return Some(first);
+    }
+    for i in 1u32.. {
+        let candidate = numbered_path(base, extension, i);
+        if candidate == current {
+            return None;
+        }
+        if !candidate.exists() {
+            return Some(candidate);
+        }
+    }
+    unreachable!()
+}
+
+/// Moves a single work and runs `commit` to record the new path. On the same
+/// filesystem the folder or file is renamed; otherwise it is copied and
+/// verified, and the original removed afterwards. If the move or `commit`
+/// fails, the original is left as it was. Each step is journaled so a crash
+/// part way through can be recovered.
+pub(crate) fn move_work(
+    conn: &Connection,
+    work_id: i64,
+    work_type: &str,
+    old_path: &Path,
+    new_path: &Path,
+    library_root: &Path,
+    commit: impl FnOnce(&Connection) -> Result<(), AppError>,
+) -> Result<MoveStrategy, AppError> {
+    let is_folder = work_type == "folder";
+    let extra = settings::get_extra_files(conn)?;
+    // A rename takes the whole folder along, so it is only used when the
+    // carry-over setting would move everything anyway.
+    let renamable = (!is_folder
+        || transfer::carries_whole_folder(old_path, &importer::list_work_files(old_path, &extra)?))
+        && new_path
+            .parent()
+            .is_some_and(|parent| transfer::same_filesystem(old_path, parent));
+    let mut strategy = if renamable {
+        MoveStrategy::Rename
+    } else {
+        MoveStrategy::Copy
+    };
+
+    let operation_id = journal::begin(
+        conn,
+        &FileOperation {
+            kind: OperationKind::Relocate,
+            work_id: Some(work_id),
+            source: old_path.to_path_buf(),
+            destination: new_path.to_path_buf(),
+            work_type: work_type.to_string(),
+            delete_source: true,
+            job_item_id: None,
+            strategy,
+        },
+    )?;
+
+    let copy = || {
+        if is_folder {
+            copy_work_files(old_path, new_path, &extra)
+        } else {
+            copy_work_file(old_path, new_path)
+        }
+    };
+    let moved = match strategy {
+        MoveStrategy::Rename => match rename_work(old_path, new_path) {
+            Ok(()) => Ok(()),
+            Err(_) => {
+                strategy = MoveStrategy::Copy;
+                journal::set_strategy(conn, operation_id, strategy)?;
+                copy()
+            }
+        },
+        MoveStrategy::Copy => copy(),
+    };
+    let committed = moved.and_then(|()| {
+        let tx = conn.unchecked_transaction()?;
+        commit(&tx)?;
+        journal::mark_committed(&tx, operation_id, work_id)?;
+        tx.commit()?;
+        Ok(())
+    });
+    if let Err(e) = committed {
+        match strategy {
+            MoveStrategy::Rename => {
+                // If the work cannot be put back, leave the entry for startup recovery.
+                if std::fs::rename(new_path, old_path).is_err() {
+                    return Err(e);
+                }
+            }
+            MoveStrategy::Copy if is_folder => {
+                let _ = std::fs::remove_dir_all(new_path);
+            }
+            MoveStrategy::Copy => {
+                let _ = std::fs::remove_file(new_path);
+            }
+        }
+        cleanup_empty_ancestors(new_path, library_root);
+        journal::complete(conn, operation_id)?;
+        return Err(e);
+    }
+    if strategy == MoveStrategy::Copy {
+        journal::remove_copied_source(old_path, new_path, work_type);
+    }
+    cleanup_empty_ancestors(old_path, library_root);
+    journal::complete(conn, operation_id)?;
+    Ok(strategy)
+}
+
+fn rename_work(old_path: &Path, new_path: &Path) -> std::io::Result<()> {
+    if let Some(parent) = new_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::rename(old_path, new_path)
+}
+
+fn copy_work_files(old_path: &Path, new_path: &Path, extra: &ExtraFiles) -> Result<(), AppError> {
     std::fs::create_dir_all(new_path)?;
 
-    let images = importer::list_images_in_folder(old_path)?;
-    for image in &images {
-        let file_name = image
-            .file_name()
-            .ok_or_else(|| AppError::RelocationError("無効なファイル名".into()))?;
-        let dest = new_path.join(file_name);
-        std::fs::copy(image, &dest)?;
+    for file in importer::list_work_files(old_path, extra)? {
+        let source = old_path.join(&file);
+        let dest = new_path.join(&file);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        transfer::copy_verified(&source, &dest)?;
+    }
+
+    Ok(())
+}
+
+fn copy_work_file(old_path: &Path, new_path: &Path) -> Result<(), AppError> {
+    if let Some(parent) = new_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    transfer::copy_verified(old_path, new_path)
+}
+
+/// Makes `new_root` the library root. With `move_files` the whole library tree
+/// is moved there first and every work in it follows. Without it the tree is
+/// taken to be there already (moved by hand), so the old library root's entry
+/// is pointed at `new_root` and its works resolve against the new location.
+pub fn change_library_root(
+    conn: &Connection,
+    new_root: &Path,
+    move_files: bool,
+    on_progress: &Channel<LibraryMoveProgress>,
+) -> Result<(), AppError> {
+    if !new_root.is_absolute() {
+        return Err(AppError::RelocationError(format!(
+            "ライブラリルートには絶対パスを指定してください: {}",
+            new_root.display()
+        )));
+    }
+    let new_root_str = new_root.to_string_lossy().to_string();
+    if move_files {
+        let old_root = settings::get_library_root(conn)?.ok_or_else(|| {
+            AppError::RelocationError("ライブラリルートが設定されていません".into())
+        })?;
+        move_library_tree(conn, Path::new(&old_root), new_root, on_progress)?;
+    } else {
+        if let Some(old_root) = settings::get_library_root(conn)? {
+            if let Some(root) = roots::root_at(conn, &old_root)? {
+                roots::rebase_root(conn, root.id, &new_root_str)?;
+            }
+        }
+        settings::set_library_root(conn, &new_root_str)?;
+    }
+    roots::ensure_root(conn, &new_root_str)?;
+    roots::adopt_works(conn)?;
+    Ok(())
+}
+
+/// Moves the library tree from `old_root` to `new_root`, which must not exist
+/// yet or be empty. On the same filesystem the folder is renamed; otherwise
+/// every file is copied and verified, and the original removed once the
+/// database points at the copy. If anything fails before then, the original
+/// is left as it was. The move is journaled like `move_work`, so a crash part
+/// way through is recovered on the next start.
+fn move_library_tree(
+    conn: &Connection,
+    old_root: &Path,
+    new_root: &Path,
+    on_progress: &Channel<LibraryMoveProgress>,
+) -> Result<(), AppError> {
+    if !old_root.is_dir() {
+        return Err(AppError::RelocationError(format!(
+            "ライブラリルートが見つかりません: {}",
+            old_root.display()
+        )));
+    }
+    if old_root.starts_with(new_root) || new_root.starts_with(old_root) {
+        return Err(AppError::RelocationError(
+            "移動先が現在のライブラリルートと重なっています".into(),
+        ));
+    }
+    if new_root.exists()
+        && std::fs::read_dir(new_root).map_or(true, |mut entries| entries.next().is_some())
+    {
+        return Err(AppError::RelocationError(format!(
+            "移動先が空のフォルダではありません: {}",
+            new_root.display()
+        )));
+    }
+    let own_root = roots::root_at(conn, &old_root.to_string_lossy())?;
+    if let Some(root) = &own_root {
+        roots::check_overlap(conn, &new_root.to_string_lossy(), Some(root.id))?;
+    }
+    let works: Vec<WorkDetail> = db::list_all_works(conn)?
+        .into_iter()
+        .filter(|work| Path::new(&work.path).starts_with(old_root))
+        .collect();
+
+    if let Some(parent) = new_root.parent() {
+        std::fs::create_dir_all(parent)?;
     }
+    let renamable = new_root
+        .parent()
+        .is_some_and(|parent| transfer::same_filesystem(old_root, parent));
+    let mut strategy = if renamable {
+        MoveStrategy::Rename
+    } else {
+        MoveStrategy::Copy
+    };
+    let operation_id = journal::begin(
+        conn,
+        &FileOperation {
+            kind: OperationKind::Library,
+            work_id: None,
+            source: old_root.to_path_buf(),
+            destination: new_root.to_path_buf(),
+            work_type: "folder".to_string(),
+            delete_source: true,
+            job_item_id: None,
+            strategy,
+        },
+    )?;
 
+    let moved = match strategy {
+        MoveStrategy::Rename => match transfer::rename_onto(old_root, new_root) {
+            Ok(()) => Ok(()),
+            Err(_) => {
+                strategy = MoveStrategy::Copy;
+                journal::set_strategy(conn, operation_id, strategy)?;
+                copy_tree(old_root, new_root, on_progress)
+            }
+        },
+        MoveStrategy::Copy => copy_tree(old_root, new_root, on_progress),
+    };
+    let committed = moved.and_then(|()| {
+        let tx = conn.unchecked_transaction()?;
+        if let Some(root) = &own_root {
+            roots::rebase_root(&tx, root.id, &new_root.to_string_lossy())?;
+        }
+        for work in &works {
+            if let Some(relative) = roots::relative_location(old_root, Path::new(&work.path)) {
+                let new_path =
+                    roots::resolve_location(Some(&new_root.to_string_lossy()), &relative);
+                db::update_work_path(&tx, work.id, &new_path)?;
+            }
+        }
+        settings::set_library_root(&tx, &new_root.to_string_lossy())?;
+        journal::mark_library_committed(&tx, operation_id)?;
+        tx.commit()?;
+        Ok(())
+    });
+    if let Err(e) = committed {
+        match strategy {
+            MoveStrategy::Rename => {
+                // If the tree cannot be put back, leave the entry for startup recovery.
+                if std::fs::rename(new_root, old_root).is_err() {
+                    return Err(e);
+                }
+            }
+            MoveStrategy::Copy => {
+                let _ = std::fs::remove_dir_all(new_root);
+            }
+        }
+        journal::complete(conn, operation_id)?;
+        return Err(e);
+    }
+    if strategy == MoveStrategy::Copy {
+        journal::remove_copied_source(old_root, new_root, "folder");
+    }
+    journal::complete(conn, operation_id)?;
+    let _ = on_progress.send(LibraryMoveProgress::Completed {
+        works: works.len(),
+        strategy,
+    });
+    if old_root.exists() {
+        return Err(AppError::TransferError(format!(
+            "移動は完了しましたが元のフォルダを削除できませんでした: {}",
+            old_root.display()
+        )));
+    }
     Ok(())
 }
 
-fn remove_work_files(path: &Path) {
-    if let Ok(images) = importer::list_images_in_folder(path) {
-        for image in &images {
-            let _ = std::fs::remove_file(image);
+/// Copies everything under `source` into `dest`, verifying each file.
+fn copy_tree(
+    source: &Path,
+    dest: &Path,
+    on_progress: &Channel<LibraryMoveProgress>,
+) -> Result<(), AppError> {
+    let entries = walkdir::WalkDir::new(source)
+        .min_depth(1)
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::TransferError(e.to_string()))?;
+    let total = entries.iter().filter(|e| !e.file_type().is_dir()).count();
+    std::fs::create_dir_all(dest)?;
+    let mut current = 0;
+    for entry in entries {
+        let relative = entry.path().strip_prefix(source).unwrap_or(entry.path());
+        let target = dest.join(relative);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target)?;
+        } else {
+            transfer::copy_verified(entry.path(), &target)?;
+            current += 1;
+            let _ = on_progress.send(LibraryMoveProgress::Copied {
+                current,
+                total,
+                file: relative.to_string_lossy().to_string(),
+            });
         }
     }
-    let _ = std::fs::remove_dir(path);
+    Ok(())
+}
+
+/// The folder empty directories are pruned up to for a work stored under
+/// `root_id`: that root, or the library root for works outside every root.
+pub(crate) fn cleanup_boundary(
+    conn: &Connection,
+    root_id: Option<i64>,
+) -> Result<Option<PathBuf>, AppError> {
+    match root_id {
+        Some(root_id) => Ok(Some(PathBuf::from(roots::get_root(conn, root_id)?.path))),
+        None => Ok(settings::get_library_root(conn)?.map(PathBuf::from)),
+    }
 }
 
-fn cleanup_empty_ancestors(path: &Path, stop_at: &Path) {
+pub(crate) fn cleanup_empty_ancestors(path: &Path, stop_at: &Path) {
     let mut current = path.to_path_buf();
     while let Some(parent) = current.parent() {
         if parent == stop_at || !parent.starts_with(stop_at) {
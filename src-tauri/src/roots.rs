@@ -0,0 +1,304 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::error::AppError;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryRoot {
+    pub id: i64,
+    pub name: String,
+    pub path: String,
+    /// Whether the root's folder is currently reachable, i.e. its volume is mounted.
+    pub online: bool,
+    pub work_count: i64,
+}
+
+/// Whether a root folder stored in the database can currently be read.
+pub(crate) fn is_online(root_path: &str) -> bool {
+    Path::new(root_path).is_dir()
+}
+
+/// Turns a stored work location back into an absolute path. Works under a
+/// root store a `/`-separated path relative to it; other works store an
+/// absolute path and no root.
+pub(crate) fn resolve_location(root_path: Option<&str>, path: &str) -> String {
+    match root_path {
+        Some(root) => path
+            .split('/')
+            .fold(PathBuf::from(root), |joined, component| {
+                joined.join(component)
+            })
+            .to_string_lossy()
+            .to_string(),
+        None => path.to_string(),
+    }
+}
+
+/// `path` relative to `root` with `/` separators, or `None` unless it is
+/// strictly inside it.
+pub(crate) fn relative_location(root: &Path, path: &Path) -> Option<String> {
+    let components: Vec<String> = path
+        .strip_prefix(root)
+        .ok()?
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    (!components.is_empty()).then(|| components.join("/"))
+}
+
+/// Splits an absolute path into the root that contains it and the path
+/// relative to that root. Paths outside every root are returned as they are.
+pub(crate) fn split_location(
+    conn: &Connection,
+    path: &str,
+) -> Result<(Option<i64>, String), AppError> {
+    let mut stmt = conn.prepare_cached("SELECT id, path FROM library_roots")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+    })?;
+    let mut best: Option<(i64, usize, String)> = None;
+    for row in rows {
+        let (root_id, root_path) = row?;
+        let Some(relative) = relative_location(Path::new(&root_path), Path::new(path)) else {
+            continue;
+        };
+        let depth = Path::new(&root_path).components().count();
+        if best
+            .as_ref()
+            .is_none_or(|(_, best_depth, _)| depth > *best_depth)
+        {
+            best = Some((root_id, depth, relative));
+        }
+    }
+    Ok(match best {
+        Some((root_id, _, relative)) => (Some(root_id), relative),
+        None => (None, path.to_string()),
+    })
+}
+
+fn normalize_path(path: &str) -> Result<String, AppError> {
+    let path = path.trim();
+    if path.is_empty() || !Path::new(path).is_absolute() {
+        return Err(AppError::LibraryRootError(format!(
+            "ルートには絶対パスを指定してください: {}",
+            path
+        )));
+    }
+    // Rebuilding from components drops trailing separators and `.` parts.
+    let normalized: PathBuf = Path::new(path).components().collect();
+    Ok(normalized.to_string_lossy().to_string())
+}
+
+fn normalize_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::LibraryRootError(
+            "ルート名は空にできません".to_string(),
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Checks that `path` is a mounted folder that does not overlap any other root.
+fn check_root_path(conn: &Connection, path: &str, except: Option<i64>) -> Result<(), AppError> {
+    if !Path::new(path).is_dir() {
+        return Err(AppError::LibraryRootError(format!(
+            "フォルダが見つかりません: {}",
+            path
+        )));
+    }
+    check_overlap(conn, path, except)
+}
+
+/// Checks that `path` neither contains nor lies inside a root other than `except`.
+pub(crate) fn check_overlap(
+    conn: &Connection,
+    path: &str,
+    except: Option<i64>,
+) -> Result<(), AppError> {
+    for root in list_roots(conn)? {
+        if Some(root.id) == except {
+            continue;
+        }
+        let other = Path::new(&root.path);
+        if Path::new(path).starts_with(other) || other.starts_with(path) {
+            return Err(AppError::LibraryRootError(format!(
+                "ルート「{}」({}) と重なっています",
+                root.name, root.path
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn check_name_free(conn: &Connection, name: &str, except: Option<i64>) -> Result<(), AppError> {
+    let taken = conn
+        .prepare_cached("SELECT 1 FROM library_roots WHERE name = ?1 AND id IS NOT ?2")?
+        .exists(rusqlite::params![name, except])?;
+    if taken {
+        return Err(AppError::LibraryRootError(format!(
+            "同じ名前のルートがあります: {}",
+            name
+        )));
+    }
+    Ok(())
+}
+
+const ROOT_COLUMNS: &str =
+    "r.id, r.name, r.path, (SELECT COUNT(*) FROM works w WHERE w.root_id = r.id)";
+
+fn row_to_root(row: &rusqlite::Row) -> rusqlite::Result<LibraryRoot> {
+    let path: String = row.get(2)?;
+    Ok(LibraryRoot {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        online: is_online(&path),
+        path,
+        work_count: row.get(3)?,
+    })
+}
+
+pub fn get_root(conn: &Connection, root_id: i64) -> Result<LibraryRoot, AppError> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM library_roots r WHERE r.id = ?1",
+        ROOT_COLUMNS
+    ))?;
+    stmt.query_row([root_id], row_to_root).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => AppError::NotFound,
+        other => AppError::Database(other),
+    })
+}
+
+/// The root registered at exactly `path`, if any.
+pub(crate) fn root_at(conn: &Connection, path: &str) -> Result<Option<LibraryRoot>, AppError> {
+    let path = normalize_path(path)?;
+    Ok(list_roots(conn)?.into_iter().find(|root| root.path == path))
+}
+
+pub fn list_roots(conn: &Connection) -> Result<Vec<LibraryRoot>, AppError> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM library_roots r ORDER BY r.name, r.id",
+        ROOT_COLUMNS
+    ))?;
+    let rows = stmt.query_map([], row_to_root)?;
+    let mut roots = Vec::new();
+    for row in rows {
+        roots.push(row?);
+    }
+    Ok(roots)
+}
+
+/// Adds a root. Works already registered under it by absolute path are
+/// converted to paths relative to it, like those added afterwards.
+pub fn create_root(conn: &Connection, name: &str, path: &str) -> Result<LibraryRoot, AppError> {
+    let name = normalize_name(name)?;
+    let path = normalize_path(path)?;
+    check_name_free(conn, &name, None)?;
+    check_root_path(conn, &path, None)?;
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT INTO library_roots (name, path) VALUES (?1, ?2)",
+        rusqlite::params![name, path],
+    )?;
+    let root_id = tx.last_insert_rowid();
+    adopt_works(&tx)?;
+    tx.commit()?;
+    get_root(conn, root_id)
+}
+
+/// Converts works stored by absolute path that lie under a root to paths
+/// relative to it. Returns how many were converted.
+pub(crate) fn adopt_works(conn: &Connection) -> Result<usize, AppError> {
+    let legacy: Vec<(i64, String)> = {
+        let mut stmt = conn.prepare_cached("SELECT id, path FROM works WHERE root_id IS NULL")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    let mut adopted = 0;
+    for (work_id, path) in legacy {
+        let (root_id, location) = split_location(conn, &path)?;
+        if root_id.is_some() {
+            conn.execute(
+                "UPDATE works SET root_id = ?1, path = ?2 WHERE id = ?3",
+                rusqlite::params![root_id, location, work_id],
+            )?;
+            adopted += 1;
+        }
+    }
+    Ok(adopted)
+}
+
+pub fn rename_root(conn: &Connection, root_id: i64, name: &str) -> Result<LibraryRoot, AppError> {
+    let name = normalize_name(name)?;
+    check_name_free(conn, &name, Some(root_id))?;
+    let updated = conn.execute(
+        "UPDATE library_roots SET name = ?1 WHERE id = ?2",
+        rusqlite::params![name, root_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound);
+    }
+    get_root(conn, root_id)
+}
+
+/// Points a root at the folder its volume is now mounted on. Nothing is
+/// moved: the works under it resolve against the new path from then on.
+pub fn rebase_root(conn: &Connection, root_id: i64, path: &str) -> Result<LibraryRoot, AppError> {
+    let path = normalize_path(path)?;
+    get_root(conn, root_id)?;
+    check_root_path(conn, &path, Some(root_id))?;
+    conn.execute(
+        "UPDATE library_roots SET path = ?1 WHERE id = ?2",
+        rusqlite::params![path, root_id],
+    )?;
+    get_root(conn, root_id)
+}
+
+/// Removes a root that no work is stored under.
+pub fn delete_root(conn: &Connection, root_id: i64) -> Result<(), AppError> {
+    let root = get_root(conn, root_id)?;
+    if root.work_count > 0 {
+        return Err(AppError::LibraryRootError(format!(
+            "ルート「{}」には {} 件の作品が登録されています",
+            root.name, root.work_count
+        )));
+    }
+    conn.execute("DELETE FROM library_roots WHERE id = ?1", [root_id])?;
+    Ok(())
+}
+
+/// Makes sure works in the library root are stored relative to some root,
+/// adding one named after its folder when no existing root covers it. Folders
+/// that do not exist yet or would overlap another root are left alone.
+pub(crate) fn ensure_root(conn: &Connection, path: &str) -> Result<(), AppError> {
+    let path = normalize_path(path)?;
+    if !Path::new(&path).is_dir() {
+        return Ok(());
+    }
+    let roots = list_roots(conn)?;
+    if roots.iter().any(|root| {
+        let other = Path::new(&root.path);
+        Path::new(&path).starts_with(other) || other.starts_with(&path)
+    }) {
+        return Ok(());
+    }
+    let base = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.clone());
+    let mut name = base.clone();
+    let mut n = 2;
+    while roots.iter().any(|root| root.name == name) {
+        name = format!("{} ({})", base, n);
+        n += 1;
+    }
+    create_root(conn, &name, &path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "tests/roots.rs"]
+mod tests;
@@ -2,6 +2,10 @@ use std::path::Path;
 
 pub(crate) const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "bmp"];
 
+pub(crate) const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "cbz"];
+
+pub(crate) const PDF_EXTENSION: &str = "pdf";
+
 pub(crate) fn is_image_file(path: &Path) -> bool {
     path.extension()
         .and_then(|ext| ext.to_str())
@@ -9,6 +13,20 @@ pub(crate) fn is_image_file(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
+pub(crate) fn is_archive_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ARCHIVE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+pub(crate) fn is_pdf_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case(PDF_EXTENSION))
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 #[path = "tests/scanner.rs"]
 mod tests;
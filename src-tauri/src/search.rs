@@ -0,0 +1,299 @@
+use rusqlite::types::Value;
+use rusqlite::Connection;
+
+use crate::db::{self, WorkSummary};
+use crate::error::AppError;
+
+const FIELDS: &[&str] = &[
+    "title", "artist", "circle", "genre", "origin", "year", "tag", "type",
+];
+const TEXT_COLUMNS: &[&str] = &["title", "artist", "circle", "genre", "origin"];
+
+// The trigram tokenizer cannot match terms shorter than three characters.
+const MIN_FTS_CHARS: usize = 3;
+
+#[derive(Debug, PartialEq)]
+pub enum TermKind {
+    /// Free text matched against every indexed column.
+    Text(String),
+    /// Substring match on a single metadata column.
+    Column {
+        column: &'static str,
+        value: String,
+    },
+    Year {
+        from: Option<i32>,
+        to: Option<i32>,
+    },
+    Tag(String),
+    Type(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct QueryTerm {
+    pub negated: bool,
+    pub kind: TermKind,
+}
+
+/// A WHERE clause fragment with its positional parameters.
+#[derive(Debug, Default)]
+pub struct CompiledQuery {
+    pub conditions: Vec<String>,
+    pub params: Vec<Value>,
+}
+
+impl CompiledQuery {
+    pub fn where_clause(&self) -> String {
+        if self.conditions.is_empty() {
+            "1".to_string()
+        } else {
+            self.conditions.join(" AND ")
+        }
+    }
+}
+
+fn read_quoted(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut value = String::new();
+    for ch in chars.by_ref() {
+        if ch == '"' {
+            break;
+        }
+        value.push(ch);
+    }
+    value
+}
+
+fn read_bare(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut value = String::new();
+    while let Some(&ch) = chars.peek() {
+        if ch.is_whitespace() {
+            break;
+        }
+        value.push(ch);
+        chars.next();
+    }
+    value
+}
+
+fn parse_year(value: &str) -> Result<TermKind, AppError> {
+    let invalid = || AppError::SearchError(format!("年の指定が不正です: {}", value));
+    let parse_bound = |s: &str| -> Result<Option<i32>, AppError> {
+        if s.is_empty() {
+            Ok(None)
+        } else {
+            s.parse().map(Some).map_err(|_| invalid())
+        }
+    };
+
+    let (from, to) = match value.split_once("..") {
+        Some((from, to)) => (parse_bound(from)?, parse_bound(to)?),
+        None => {
+            let year = parse_bound(value)?;
+            (year, year)
+        }
+    };
+    if from.is_none() && to.is_none() {
+        return Err(invalid());
+    }
+    Ok(TermKind::Year { from, to })
+}
+
+fn field_term(field: &str, value: String) -> Result<TermKind, AppError> {
+    let kind = match field.to_ascii_lowercase().as_str() {
+        "title" => TermKind::Column {
+            column: "title",
+            value,
+        },
+        "artist" => TermKind::Column {
+            column: "artist",
+            value,
+        },
+        "circle" => TermKind::Column {
+            column: "circle",
+            value,
+        },
+        "genre" => TermKind::Column {
+            column: "genre",
+            value,
+        },
+        "origin" => TermKind::Column {
+            column: "origin",
+            value,
+        },
+        "year" => parse_year(&value)?,
+        "tag" => TermKind::Tag(value),
+        "type" => TermKind::Type(value),
+        _ => TermKind::Text(format!("{}:{}", field, value)),
+    };
+    Ok(kind)
+}
+
+fn is_field(name: &str) -> bool {
+    FIELDS.contains(&name.to_ascii_lowercase().as_str())
+}
+
+/// Parses a query such as `artist:foo year:2019..2021 tag:color "exact title"`.
+/// A leading `-` negates a term. Words with an unknown `prefix:` (e.g. `Re:Zero`)
+/// are treated as free text.
+pub fn parse_query(input: &str) -> Result<Vec<QueryTerm>, AppError> {
+    let mut terms = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let negated = chars.next_if_eq(&'-').is_some();
+
+        let kind = if chars.next_if_eq(&'"').is_some() {
+            TermKind::Text(read_quoted(&mut chars))
+        } else {
+            let mut word = String::new();
+            while let Some(&ch) = chars.peek() {
+                if ch.is_whitespace() || (ch == ':' && is_field(&word)) {
+                    break;
+                }
+                word.push(ch);
+                chars.next();
+            }
+            if chars.next_if_eq(&':').is_some() {
+                let value = if chars.next_if_eq(&'"').is_some() {
+                    read_quoted(&mut chars)
+                } else {
+                    read_bare(&mut chars)
+                };
+                field_term(&word, value)?
+            } else {
+                TermKind::Text(word)
+            }
+        };
+
+        let is_empty = match &kind {
+            TermKind::Text(v)
+            | TermKind::Column { value: v, .. }
+            | TermKind::Tag(v)
+            | TermKind::Type(v) => v.trim().is_empty(),
+            TermKind::Year { .. } => false,
+        };
+        if !is_empty {
+            terms.push(QueryTerm { negated, kind });
+        }
+    }
+
+    Ok(terms)
+}
+
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('%');
+    for ch in value.chars() {
+        if matches!(ch, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped.push('%');
+    escaped
+}
+
+fn fts_phrase(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+fn compile_term(kind: &TermKind, params: &mut Vec<Value>) -> String {
+    let mut bind = |value: Value| {
+        params.push(value);
+        format!("?{}", params.len())
+    };
+
+    match kind {
+        TermKind::Text(text) if text.chars().count() >= MIN_FTS_CHARS => format!(
+            "works.id IN (SELECT rowid FROM works_fts WHERE works_fts MATCH {})",
+            bind(Value::Text(fts_phrase(text)))
+        ),
+        TermKind::Text(text) => {
+            let p = bind(Value::Text(escape_like(text)));
+            let likes: Vec<String> = TEXT_COLUMNS
+                .iter()
+                .map(|col| format!("works.{} LIKE {} ESCAPE '\\'", col, p))
+                .collect();
+            format!("({})", likes.join(" OR "))
+        }
+        TermKind::Column { column, value } => format!(
+            "works.{} LIKE {} ESCAPE '\\'",
+            column,
+            bind(Value::Text(escape_like(value)))
+        ),
+        TermKind::Year { from, to } => {
+            let mut parts = Vec::new();
+            if let Some(from) = from {
+                parts.push(format!(
+                    "works.year >= {}",
+                    bind(Value::Integer(*from as i64))
+                ));
+            }
+            if let Some(to) = to {
+                parts.push(format!("works.year <= {}", bind(Value::Integer(*to as i64))));
+            }
+            format!("({})", parts.join(" AND "))
+        }
+        TermKind::Tag(name) => format!(
+            "EXISTS (SELECT 1 FROM works_tags wt JOIN tags t ON t.id = wt.tag_id WHERE wt.work_id = works.id AND t.name = {} COLLATE NOCASE)",
+            bind(Value::Text(name.clone()))
+        ),
+        TermKind::Type(work_type) => format!(
+            "works.type = {}",
+            bind(Value::Text(work_type.to_ascii_lowercase()))
+        ),
+    }
+}
+
+pub fn compile_query(terms: &[QueryTerm]) -> CompiledQuery {
+    let mut compiled = CompiledQuery::default();
+    for term in terms {
+        let condition = compile_term(&term.kind, &mut compiled.params);
+        // NULL columns must count as "not matching" rather than dropping the row.
+        compiled.conditions.push(if term.negated {
+            format!("NOT IFNULL({}, 0)", condition)
+        } else {
+            condition
+        });
+    }
+    compiled
+}
+
+pub fn search_works(
+    conn: &Connection,
+    query: &str,
+    sort_by: &str,
+    sort_order: &str,
+) -> Result<Vec<WorkSummary>, AppError> {
+    let compiled = compile_query(&parse_query(query)?);
+    let sql = format!(
+        "SELECT id, title, type, page_count, created_at, root_id FROM works WHERE {} ORDER BY {}",
+        compiled.where_clause(),
+        db::order_by_clause(sort_by, sort_order)
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(compiled.params.iter()), |row| {
+        Ok(WorkSummary {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            work_type: row.get(2)?,
+            page_count: row.get(3)?,
+            created_at: row.get(4)?,
+            root_id: row.get(5)?,
+        })
+    })?;
+    let mut works = Vec::new();
+    for row in rows {
+        works.push(row?);
+    }
+    Ok(works)
+}
+
+#[cfg(test)]
+#[path = "tests/search.rs"]
+mod tests;
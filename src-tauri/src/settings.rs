@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use rusqlite::{Connection, OptionalExtension};
 
 use crate::error::AppError;
@@ -23,10 +25,46 @@ const KEY_LIBRARY_ROOT: &str = "library_root";
 const KEY_DIRECTORY_TEMPLATE: &str = "directory_template";
 const KEY_TYPE_LABEL_IMAGE: &str = "type_label_image";
 const KEY_TYPE_LABEL_FOLDER: &str = "type_label_folder";
+const KEY_IMPORT_WORKERS: &str = "import_workers";
+const KEY_EXTRA_FILES_MODE: &str = "extra_files_mode";
+const KEY_EXTRA_FILE_EXTENSIONS: &str = "extra_file_extensions";
+
+pub const WORK_TYPES: &[&str] = &["folder", "image", "archive", "pdf"];
 
 const DEFAULT_TYPE_LABEL_IMAGE: &str = "Image";
 const DEFAULT_TYPE_LABEL_FOLDER: &str = "Folder";
 
+pub const MAX_IMPORT_WORKERS: usize = 16;
+
+const DEFAULT_EXTRA_FILES_MODE: &str = "none";
+const DEFAULT_EXTRA_FILE_EXTENSIONS: &[&str] = &["txt", "nfo", "json"];
+const EXTRA_FILES_MODES: &[&str] = &["none", "all", "listed"];
+
+/// Which files besides its top-level images travel with a folder work when it
+/// is imported or relocated.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExtraFiles {
+    /// Only the images.
+    None,
+    /// The whole folder tree.
+    All,
+    /// Files anywhere in the tree with one of these (lowercase) extensions.
+    Listed(Vec<String>),
+}
+
+impl ExtraFiles {
+    pub fn carries(&self, relative: &Path) -> bool {
+        match self {
+            ExtraFiles::None => false,
+            ExtraFiles::All => true,
+            ExtraFiles::Listed(extensions) => relative
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+                .is_some_and(|ext| extensions.contains(&ext)),
+        }
+    }
+}
+
 pub fn get_library_root(conn: &Connection) -> Result<Option<String>, AppError> {
     get_setting(conn, KEY_LIBRARY_ROOT)
 }
@@ -43,6 +81,51 @@ pub fn set_directory_template(conn: &Connection, template: &str) -> Result<(), A
     set_setting(conn, KEY_DIRECTORY_TEMPLATE, template)
 }
 
+fn type_template_key(work_type: &str) -> Result<String, AppError> {
+    match work_type {
+        "folder" => Ok(KEY_DIRECTORY_TEMPLATE.to_string()),
+        "image" | "archive" | "pdf" => Ok(format!("{}_{}", KEY_DIRECTORY_TEMPLATE, work_type)),
+        _ => Err(AppError::InvalidTemplate(format!(
+            "不明な作品種別です: {}",
+            work_type
+        ))),
+    }
+}
+
+/// The template set for this work type itself. For folder works this is the
+/// directory template; other types return `None` until they get their own.
+pub fn get_type_template(conn: &Connection, work_type: &str) -> Result<Option<String>, AppError> {
+    get_setting(conn, &type_template_key(work_type)?)
+}
+
+/// Saves or, with `None`, removes the template of a work type. A type without
+/// its own template is placed by the directory template.
+pub fn set_type_template(
+    conn: &Connection,
+    work_type: &str,
+    template: Option<&str>,
+) -> Result<(), AppError> {
+    let key = type_template_key(work_type)?;
+    match template {
+        Some(template) => set_setting(conn, &key, template),
+        None => {
+            conn.execute("DELETE FROM settings WHERE key = ?1", [key])?;
+            Ok(())
+        }
+    }
+}
+
+/// The template works of this type are placed by.
+pub fn get_effective_template(
+    conn: &Connection,
+    work_type: &str,
+) -> Result<Option<String>, AppError> {
+    match get_type_template(conn, work_type)? {
+        Some(template) => Ok(Some(template)),
+        None => get_directory_template(conn),
+    }
+}
+
 pub fn get_type_label_image(conn: &Connection) -> Result<String, AppError> {
     Ok(get_setting(conn, KEY_TYPE_LABEL_IMAGE)?.unwrap_or_else(|| DEFAULT_TYPE_LABEL_IMAGE.into()))
 }
@@ -60,6 +143,86 @@ pub fn set_type_label_folder(conn: &Connection, label: &str) -> Result<(), AppEr
     set_setting(conn, KEY_TYPE_LABEL_FOLDER, label)
 }
 
+/// Number of bulk import workers. Defaults to the available parallelism, capped
+/// at 4 since imports are usually bound by disk or network I/O.
+pub fn get_import_workers(conn: &Connection) -> Result<usize, AppError> {
+    let configured = get_setting(conn, KEY_IMPORT_WORKERS)?.and_then(|v| v.parse::<usize>().ok());
+    let workers = configured.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get().min(4))
+            .unwrap_or(1)
+    });
+    Ok(workers.clamp(1, MAX_IMPORT_WORKERS))
+}
+
+pub fn set_import_workers(conn: &Connection, workers: usize) -> Result<(), AppError> {
+    if !(1..=MAX_IMPORT_WORKERS).contains(&workers) {
+        return Err(AppError::ImportError(format!(
+            "取り込みの並列数は 1 から {} の範囲で指定してください",
+            MAX_IMPORT_WORKERS
+        )));
+    }
+    set_setting(conn, KEY_IMPORT_WORKERS, &workers.to_string())
+}
+
+pub fn get_extra_files_mode(conn: &Connection) -> Result<String, AppError> {
+    Ok(get_setting(conn, KEY_EXTRA_FILES_MODE)?
+        .filter(|mode| EXTRA_FILES_MODES.contains(&mode.as_str()))
+        .unwrap_or_else(|| DEFAULT_EXTRA_FILES_MODE.into()))
+}
+
+pub fn get_extra_file_extensions(conn: &Connection) -> Result<Vec<String>, AppError> {
+    Ok(match get_setting(conn, KEY_EXTRA_FILE_EXTENSIONS)? {
+        Some(value) => value
+            .split(',')
+            .filter(|ext| !ext.is_empty())
+            .map(str::to_string)
+            .collect(),
+        None => DEFAULT_EXTRA_FILE_EXTENSIONS
+            .iter()
+            .map(|ext| ext.to_string())
+            .collect(),
+    })
+}
+
+pub fn get_extra_files(conn: &Connection) -> Result<ExtraFiles, AppError> {
+    Ok(match get_extra_files_mode(conn)?.as_str() {
+        "all" => ExtraFiles::All,
+        "listed" => ExtraFiles::Listed(get_extra_file_extensions(conn)?),
+        _ => ExtraFiles::None,
+    })
+}
+
+/// Saves the carry-over mode and allow-list. Extensions are stored lowercase
+/// without the leading dot; the list is kept even when another mode is chosen.
+pub fn set_extra_files(
+    conn: &Connection,
+    mode: &str,
+    extensions: &[String],
+) -> Result<(), AppError> {
+    if !EXTRA_FILES_MODES.contains(&mode) {
+        return Err(AppError::ImportError(format!(
+            "不明な付属ファイルの扱いです: {}",
+            mode
+        )));
+    }
+    let mut normalized: Vec<String> = Vec::new();
+    for ext in extensions {
+        let ext = ext.trim().trim_start_matches('.').to_ascii_lowercase();
+        if ext.is_empty() {
+            continue;
+        }
+        if ext.contains(',') || ext.contains('/') || ext.contains('\\') {
+            return Err(AppError::ImportError(format!("無効な拡張子です: {}", ext)));
+        }
+        if !normalized.contains(&ext) {
+            normalized.push(ext);
+        }
+    }
+    set_setting(conn, KEY_EXTRA_FILES_MODE, mode)?;
+    set_setting(conn, KEY_EXTRA_FILE_EXTENSIONS, &normalized.join(","))
+}
+
 pub fn resolve_type_label(conn: &Connection, work_type: &str) -> Result<String, AppError> {
     match work_type {
         "image" => get_type_label_image(conn),
@@ -0,0 +1,133 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::db::{self, WorkSummary};
+use crate::error::AppError;
+use crate::thumbnail;
+
+const MAX_DISTANCE: u32 = 64;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarCluster {
+    pub works: Vec<WorkSummary>,
+    /// Largest Hamming distance between any two works in the cluster.
+    pub max_distance: u32,
+}
+
+pub fn hamming_distance(a: i64, b: i64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Fills in hashes for works imported before perceptual hashing existed, using
+/// the stored thumbnail so no files need to be read.
+fn backfill_perceptual_hashes(conn: &Connection) -> Result<(), AppError> {
+    let pending: Vec<(i64, Vec<u8>)> = {
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, thumbnail FROM works WHERE perceptual_hash IS NULL AND thumbnail IS NOT NULL",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    for (work_id, thumb) in pending {
+        if let Ok(hash) = thumbnail::perceptual_hash(&thumb) {
+            db::set_perceptual_hash(conn, work_id, Some(hash))?;
+        }
+    }
+    Ok(())
+}
+
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Groups works whose first pages are within `threshold` bits of each other.
+/// Clustering is single-link: two works share a cluster if a chain of close
+/// pairs connects them, so `max_distance` may exceed the threshold.
+pub fn find_similar_works(
+    conn: &Connection,
+    threshold: u32,
+) -> Result<Vec<SimilarCluster>, AppError> {
+    backfill_perceptual_hashes(conn)?;
+    let threshold = threshold.min(MAX_DISTANCE);
+
+    let hashed: Vec<(WorkSummary, i64)> = {
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, title, type, page_count, created_at, root_id, perceptual_hash FROM works WHERE perceptual_hash IS NOT NULL ORDER BY id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                WorkSummary {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    work_type: row.get(2)?,
+                    page_count: row.get(3)?,
+                    created_at: row.get(4)?,
+                    root_id: row.get(5)?,
+                },
+                row.get(6)?,
+            ))
+        })?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let mut parents: Vec<usize> = (0..hashed.len()).collect();
+    for i in 0..hashed.len() {
+        for j in i + 1..hashed.len() {
+            if hamming_distance(hashed[i].1, hashed[j].1) <= threshold {
+                let (a, b) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                if a != b {
+                    parents[b] = a;
+                }
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for i in 0..hashed.len() {
+        groups
+            .entry(find_root(&mut parents, i))
+            .or_default()
+            .push(i);
+    }
+
+    let mut hashed: HashMap<usize, (WorkSummary, i64)> = hashed.into_iter().enumerate().collect();
+    let mut clusters: Vec<SimilarCluster> = groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|members| {
+            let hashes: Vec<i64> = members.iter().map(|i| hashed[i].1).collect();
+            let max_distance = hashes
+                .iter()
+                .enumerate()
+                .flat_map(|(i, a)| hashes[i + 1..].iter().map(|b| hamming_distance(*a, *b)))
+                .max()
+                .unwrap_or(0);
+            let works = members
+                .iter()
+                .filter_map(|i| hashed.remove(i).map(|(work, _)| work))
+                .collect();
+            SimilarCluster {
+                works,
+                max_distance,
+            }
+        })
+        .collect();
+    clusters.sort_by(|a, b| {
+        b.works
+            .len()
+            .cmp(&a.works.len())
+            .then(a.works[0].id.cmp(&b.works[0].id))
+    });
+    Ok(clusters)
+}
+
+#[cfg(test)]
+#[path = "tests/similar.rs"]
+mod tests;
@@ -0,0 +1,225 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::AppError;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub category: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TagGroup {
+    pub category: Option<String>,
+    pub tags: Vec<Tag>,
+}
+
+fn normalize_name(name: &str) -> Result<String, AppError> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err(AppError::TagError("タグ名は空にできません".to_string()));
+    }
+    Ok(trimmed.to_string())
+}
+
+fn normalize_category(category: Option<&str>) -> Option<String> {
+    category
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .map(str::to_string)
+}
+
+fn row_to_tag(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        category: row.get(2)?,
+    })
+}
+
+// UNIQUE(name, category) treats NULL categories as distinct, so lookups use IS.
+fn find_tag_id(
+    conn: &Connection,
+    name: &str,
+    category: Option<&str>,
+) -> Result<Option<i64>, AppError> {
+    let mut stmt = conn.prepare_cached("SELECT id FROM tags WHERE name = ?1 AND category IS ?2")?;
+    Ok(stmt
+        .query_row(rusqlite::params![name, category], |row| row.get(0))
+        .optional()?)
+}
+
+pub fn get_tag(conn: &Connection, tag_id: i64) -> Result<Tag, AppError> {
+    let mut stmt = conn.prepare_cached("SELECT id, name, category FROM tags WHERE id = ?1")?;
+    stmt.query_row([tag_id], row_to_tag).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => AppError::NotFound,
+        other => AppError::Database(other),
+    })
+}
+
+pub fn list_tags(conn: &Connection) -> Result<Vec<Tag>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, name, category FROM tags ORDER BY category IS NULL, category, name",
+    )?;
+    let rows = stmt.query_map([], row_to_tag)?;
+    let mut tags = Vec::new();
+    for row in rows {
+        tags.push(row?);
+    }
+    Ok(tags)
+}
+
+pub fn create_tag(conn: &Connection, name: &str, category: Option<&str>) -> Result<Tag, AppError> {
+    let name = normalize_name(name)?;
+    let category = normalize_category(category);
+    if find_tag_id(conn, &name, category.as_deref())?.is_some() {
+        return Err(AppError::TagError(format!(
+            "タグ「{}」は既に存在します",
+            name
+        )));
+    }
+    conn.execute(
+        "INSERT INTO tags (name, category) VALUES (?1, ?2)",
+        rusqlite::params![name, category],
+    )?;
+    Ok(Tag {
+        id: conn.last_insert_rowid(),
+        name,
+        category,
+    })
+}
+
+pub fn update_tag(
+    conn: &Connection,
+    tag_id: i64,
+    name: &str,
+    category: Option<&str>,
+) -> Result<Tag, AppError> {
+    let name = normalize_name(name)?;
+    let category = normalize_category(category);
+    if let Some(existing) = find_tag_id(conn, &name, category.as_deref())? {
+        if existing != tag_id {
+            return Err(AppError::TagError(format!(
+                "タグ「{}」は既に存在します",
+                name
+            )));
+        }
+    }
+    let updated = conn.execute(
+        "UPDATE tags SET name = ?1, category = ?2 WHERE id = ?3",
+        rusqlite::params![name, category, tag_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(Tag {
+        id: tag_id,
+        name,
+        category,
+    })
+}
+
+pub fn delete_tag(conn: &Connection, tag_id: i64) -> Result<(), AppError> {
+    let deleted = conn.execute("DELETE FROM tags WHERE id = ?1", [tag_id])?;
+    if deleted == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+pub fn add_tag_to_work(conn: &Connection, work_id: i64, tag_id: i64) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT OR IGNORE INTO works_tags (work_id, tag_id) VALUES (?1, ?2)",
+        rusqlite::params![work_id, tag_id],
+    )?;
+    Ok(())
+}
+
+pub fn remove_tag_from_work(conn: &Connection, work_id: i64, tag_id: i64) -> Result<(), AppError> {
+    conn.execute(
+        "DELETE FROM works_tags WHERE work_id = ?1 AND tag_id = ?2",
+        rusqlite::params![work_id, tag_id],
+    )?;
+    Ok(())
+}
+
+pub fn add_tag_to_works(
+    conn: &mut Connection,
+    work_ids: &[i64],
+    tag_id: i64,
+) -> Result<(), AppError> {
+    let tx = conn.transaction()?;
+    for &work_id in work_ids {
+        add_tag_to_work(&tx, work_id, tag_id)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn remove_tag_from_works(
+    conn: &mut Connection,
+    work_ids: &[i64],
+    tag_id: i64,
+) -> Result<(), AppError> {
+    let tx = conn.transaction()?;
+    for &work_id in work_ids {
+        remove_tag_from_work(&tx, work_id, tag_id)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Re-points every work tagged with `source_id` to `target_id`, then deletes the source tag.
+pub fn merge_tags(conn: &mut Connection, source_id: i64, target_id: i64) -> Result<(), AppError> {
+    if source_id == target_id {
+        return Err(AppError::TagError(
+            "同じタグ同士は統合できません".to_string(),
+        ));
+    }
+    get_tag(conn, source_id)?;
+    get_tag(conn, target_id)?;
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT OR IGNORE INTO works_tags (work_id, tag_id) SELECT work_id, ?2 FROM works_tags WHERE tag_id = ?1",
+        rusqlite::params![source_id, target_id],
+    )?;
+    tx.execute("DELETE FROM tags WHERE id = ?1", [source_id])?;
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn list_work_tags(conn: &Connection, work_id: i64) -> Result<Vec<Tag>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT t.id, t.name, t.category FROM tags t JOIN works_tags wt ON wt.tag_id = t.id WHERE wt.work_id = ?1 ORDER BY t.category IS NULL, t.category, t.name",
+    )?;
+    let rows = stmt.query_map([work_id], row_to_tag)?;
+    let mut tags = Vec::new();
+    for row in rows {
+        tags.push(row?);
+    }
+    Ok(tags)
+}
+
+/// Groups tags by category, keeping the input order (categorized first, uncategorized last).
+pub fn group_by_category(tags: Vec<Tag>) -> Vec<TagGroup> {
+    let mut groups: Vec<TagGroup> = Vec::new();
+    for tag in tags {
+        match groups.last_mut() {
+            Some(group) if group.category == tag.category => group.tags.push(tag),
+            _ => groups.push(TagGroup {
+                category: tag.category.clone(),
+                tags: vec![tag],
+            }),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+#[path = "tests/tags.rs"]
+mod tests;
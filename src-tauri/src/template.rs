@@ -1,5 +1,6 @@
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
+use regex::Regex;
 use serde::Deserialize;
 
 use crate::error::AppError;
@@ -9,7 +10,7 @@ const KNOWN_PLACEHOLDERS: &[&str] = &[
 ];
 const FORBIDDEN_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
 pub struct WorkMetadata {
     pub title: String,
     pub artist: Option<String>,
@@ -21,43 +22,242 @@ pub struct WorkMetadata {
     pub work_type: Option<String>,
 }
 
-pub fn validate_template(template: &str) -> Result<(), AppError> {
-    if template.trim().is_empty() {
-        return Err(AppError::InvalidTemplate(
-            "テンプレートが空です".to_string(),
-        ));
+/// How a placeholder's value is transformed before it is written.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Format {
+    /// `:upper`
+    Upper,
+    /// `:lower`
+    Lower,
+    /// `:0N`, pads with zeros to N characters.
+    ZeroPad(usize),
+    /// `:.N`, cuts the value to at most N characters.
+    Truncate(usize),
+    /// `:initial`, puts the value in a folder named after its first letter.
+    Initial,
+}
+
+/// A `{name?|default:format...}` placeholder.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Field {
+    pub name: String,
+    /// `{name?}`: the segment is dropped when the field is missing.
+    pub optional: bool,
+    /// `{name|default}`: written when the field is missing, instead of "Unknown".
+    pub default: Option<String>,
+    pub formats: Vec<Format>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Part {
+    Literal(String),
+    Field(Field),
+}
+
+/// One `/`-separated component of a template.
+pub(crate) type Segment = Vec<Part>;
+
+/// A syntax error at a 1-based character column of the template.
+#[derive(Debug, PartialEq)]
+pub(crate) struct TemplateError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl From<TemplateError> for AppError {
+    fn from(e: TemplateError) -> Self {
+        AppError::InvalidTemplate(format!("{} 文字目: {}", e.column, e.message))
     }
+}
 
-    let mut has_title = false;
-    let mut pos = 0;
-    let bytes = template.as_bytes();
+fn column_at(template: &str, byte: usize) -> usize {
+    template[..byte].chars().count() + 1
+}
+
+fn template_error(template: &str, byte: usize, message: impl Into<String>) -> TemplateError {
+    TemplateError {
+        column: column_at(template, byte),
+        message: message.into(),
+    }
+}
+
+fn parse_format(spec: &str) -> Option<Format> {
+    match spec {
+        "upper" => Some(Format::Upper),
+        "lower" => Some(Format::Lower),
+        "initial" => Some(Format::Initial),
+        _ => {
+            if let Some(max) = spec.strip_prefix('.') {
+                return max.parse().ok().filter(|&n| n > 0).map(Format::Truncate);
+            }
+            if spec.len() >= 2 && spec.starts_with('0') {
+                return spec.parse().ok().map(Format::ZeroPad);
+            }
+            None
+        }
+    }
+}
+
+/// Parses the inside of a placeholder that starts at byte `start` (the `{`).
+fn parse_field(template: &str, start: usize, body: &str) -> Result<Field, TemplateError> {
+    let at = |offset: usize| start + 1 + offset;
+
+    let (head, specs) = match body.find(':') {
+        Some(i) => (&body[..i], Some(i)),
+        None => (body, None),
+    };
+    let (head, default) = match head.find('|') {
+        Some(i) => (
+            &head[..i],
+            Some((i, &body[i + 1..specs.unwrap_or(body.len())])),
+        ),
+        None => (head, None),
+    };
+    let (name, optional) = match head.strip_suffix('?') {
+        Some(name) => (name, true),
+        None => (head, false),
+    };
 
-    while pos < bytes.len() {
-        if bytes[pos] == b'{' {
-            let close = template[pos..].find('}').map(|i| i + pos).ok_or_else(|| {
-                AppError::InvalidTemplate("閉じられていないプレースホルダーがあります".into())
+    if name.is_empty() {
+        return Err(template_error(
+            template,
+            start,
+            "空のプレースホルダーがあります",
+        ));
+    }
+    if !KNOWN_PLACEHOLDERS.contains(&name) {
+        return Err(template_error(
+            template,
+            at(0),
+            format!("未知のプレースホルダー: {{{}}}", name),
+        ));
+    }
+    let default = match default {
+        Some((i, _)) if optional => {
+            return Err(template_error(
+                template,
+                at(i),
+                "省略可能なプレースホルダーに既定値は指定できません",
+            ));
+        }
+        Some((i, "")) => {
+            return Err(template_error(template, at(i), "既定値が空です"));
+        }
+        Some((i, value)) => {
+            if let Some(bad) = value.find(FORBIDDEN_CHARS) {
+                return Err(template_error(
+                    template,
+                    at(i + 1 + bad),
+                    "既定値に使えない文字が含まれています",
+                ));
+            }
+            Some(value.to_string())
+        }
+        None => None,
+    };
+
+    let mut formats = Vec::new();
+    if let Some(first) = specs {
+        let mut offset = first + 1;
+        for spec in body[first + 1..].split(':') {
+            let format = parse_format(spec).ok_or_else(|| {
+                template_error(template, at(offset), format!("未知の書式: {}", spec))
             })?;
-            let name = &template[pos + 1..close];
-            if name.is_empty() {
-                return Err(AppError::InvalidTemplate(
-                    "空のプレースホルダーがあります".into(),
+            formats.push(format);
+            offset += spec.len() + 1;
+        }
+    }
+
+    Ok(Field {
+        name: name.to_string(),
+        optional,
+        default,
+        formats,
+    })
+}
+
+/// Splits a template into segments of literal text and placeholders.
+pub(crate) fn parse_template(template: &str) -> Result<Vec<Segment>, TemplateError> {
+    let mut segments = Vec::new();
+    let mut segment: Segment = Vec::new();
+    let mut literal = String::new();
+    // Where the segment's `:initial` placeholder starts, if it has one.
+    let mut initial_at = None;
+
+    let finish_segment = |segment: &mut Segment,
+                          literal: &mut String,
+                          initial_at: &mut Option<usize>|
+     -> Result<Segment, TemplateError> {
+        if !literal.is_empty() {
+            segment.push(Part::Literal(std::mem::take(literal)));
+        }
+        if let Some(at) = initial_at.take() {
+            if segment.len() != 1 {
+                return Err(template_error(
+                    template,
+                    at,
+                    ":initial を使うプレースホルダーは単独のフォルダにしてください",
                 ));
             }
-            if !KNOWN_PLACEHOLDERS.contains(&name) {
-                return Err(AppError::InvalidTemplate(format!(
-                    "未知のプレースホルダー: {{{}}}",
-                    name
-                )));
+        }
+        Ok(std::mem::take(segment))
+    };
+
+    let mut pos = 0;
+    while pos < template.len() {
+        let ch = template[pos..].chars().next().unwrap();
+        match ch {
+            '{' => {
+                let close = template[pos + 1..]
+                    .find(['{', '}', '/'])
+                    .map(|i| i + pos + 1)
+                    .filter(|&i| template.as_bytes()[i] == b'}')
+                    .ok_or_else(|| {
+                        template_error(template, pos, "閉じられていないプレースホルダーがあります")
+                    })?;
+                let field = parse_field(template, pos, &template[pos + 1..close])?;
+                if field.formats.contains(&Format::Initial) {
+                    initial_at = Some(pos);
+                }
+                if !literal.is_empty() {
+                    segment.push(Part::Literal(std::mem::take(&mut literal)));
+                }
+                segment.push(Part::Field(field));
+                pos = close + 1;
             }
-            if name == "title" {
-                has_title = true;
+            '}' => {
+                return Err(template_error(
+                    template,
+                    pos,
+                    "対応する { のない } があります",
+                ));
+            }
+            '/' => {
+                segments.push(finish_segment(&mut segment, &mut literal, &mut initial_at)?);
+                pos += 1;
+            }
+            _ => {
+                literal.push(ch);
+                pos += ch.len_utf8();
             }
-            pos = close + 1;
-        } else {
-            pos += 1;
         }
     }
+    segments.push(finish_segment(&mut segment, &mut literal, &mut initial_at)?);
+    Ok(segments)
+}
 
+pub fn validate_template(template: &str) -> Result<(), AppError> {
+    if template.trim().is_empty() {
+        return Err(AppError::InvalidTemplate(
+            "テンプレートが空です".to_string(),
+        ));
+    }
+
+    let segments = parse_template(template)?;
+    let has_title = segments.iter().flatten().any(|part| match part {
+        Part::Field(field) => field.name == "title",
+        Part::Literal(_) => false,
+    });
     if !has_title {
         return Err(AppError::InvalidTemplate("{title} は必須です".to_string()));
     }
@@ -79,67 +279,275 @@ fn sanitize_segment(s: &str) -> String {
     }
 }
 
-fn resolve_placeholder(name: &str, metadata: &WorkMetadata) -> String {
-    match name {
-        "title" => metadata.title.clone(),
-        "artist" => metadata
-            .artist
-            .clone()
-            .unwrap_or_else(|| "Unknown".to_string()),
-        "year" => metadata
-            .year
-            .map(|y| y.to_string())
-            .unwrap_or_else(|| "Unknown".to_string()),
-        "genre" => metadata
-            .genre
-            .clone()
-            .unwrap_or_else(|| "Unknown".to_string()),
-        "circle" => metadata
-            .circle
-            .clone()
-            .unwrap_or_else(|| "Unknown".to_string()),
-        "origin" => metadata
-            .origin
-            .clone()
-            .unwrap_or_else(|| "Unknown".to_string()),
-        "type" => metadata
-            .work_type
-            .clone()
-            .unwrap_or_else(|| "Unknown".to_string()),
-        _ => "Unknown".to_string(),
+/// The metadata value a placeholder refers to, or `None` when it is not set.
+fn field_value(name: &str, metadata: &WorkMetadata) -> Option<String> {
+    let value = match name {
+        "title" => Some(metadata.title.clone()),
+        "artist" => metadata.artist.clone(),
+        "year" => metadata.year.map(|y| y.to_string()),
+        "genre" => metadata.genre.clone(),
+        "circle" => metadata.circle.clone(),
+        "origin" => metadata.origin.clone(),
+        "type" => metadata.work_type.clone(),
+        _ => None,
+    };
+    value.filter(|v| !v.trim().is_empty())
+}
+
+fn apply_format(value: String, format: &Format) -> String {
+    match format {
+        Format::Upper => value.to_uppercase(),
+        Format::Lower => value.to_lowercase(),
+        Format::ZeroPad(width) => {
+            let len = value.chars().count();
+            if len >= *width {
+                value
+            } else {
+                "0".repeat(width - len) + &value
+            }
+        }
+        Format::Truncate(max) => value
+            .chars()
+            .take(*max)
+            .collect::<String>()
+            .trim_end()
+            .to_string(),
+        Format::Initial => value,
     }
 }
 
-pub fn render_template(template: &str, metadata: &WorkMetadata) -> String {
-    let segments: Vec<&str> = template.split('/').collect();
-    segments
+/// What a placeholder writes when its field is missing.
+fn missing_value(field: &Field) -> String {
+    let value = field
+        .default
+        .clone()
+        .unwrap_or_else(|| "Unknown".to_string());
+    field.formats.iter().fold(value, apply_format)
+}
+
+/// The bucket folder `:initial` files a value under: an uppercase letter,
+/// `0-9` for digits, the first character for other scripts, or `#`.
+fn initial_bucket(value: &str) -> String {
+    match value.trim().chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => c.to_ascii_uppercase().to_string(),
+        Some(c) if c.is_ascii_digit() => "0-9".to_string(),
+        Some(c) if c.is_alphanumeric() => c.to_uppercase().to_string(),
+        _ => "#".to_string(),
+    }
+}
+
+/// Renders one segment into zero, one or (with `:initial`) two path components.
+fn render_segment(segment: &[Part], metadata: &WorkMetadata) -> Vec<String> {
+    let fields: Vec<&Field> = segment
         .iter()
-        .map(|segment| {
-            let mut result = String::new();
-            let mut chars = segment.char_indices().peekable();
-            while let Some((i, ch)) = chars.next() {
-                if ch == '{' {
-                    if let Some(close_offset) = segment[i..].find('}') {
-                        let name = &segment[i + 1..i + close_offset];
-                        result.push_str(&resolve_placeholder(name, metadata));
-                        while let Some(&(j, _)) = chars.peek() {
-                            if j <= i + close_offset {
-                                chars.next();
-                            } else {
-                                break;
-                            }
-                        }
-                    } else {
-                        result.push('{');
-                    }
-                } else {
-                    result.push(ch);
+        .filter_map(|part| match part {
+            Part::Field(field) => Some(field),
+            Part::Literal(_) => None,
+        })
+        .collect();
+    let missing_optional =
+        |field: &Field| field.optional && field_value(&field.name, metadata).is_none();
+    if !fields.is_empty() && fields.iter().all(|field| missing_optional(field)) {
+        return Vec::new();
+    }
+
+    let mut text = String::new();
+    let mut bucket = None;
+    for part in segment {
+        match part {
+            Part::Literal(literal) => text.push_str(literal),
+            Part::Field(field) => {
+                let value = match field_value(&field.name, metadata) {
+                    Some(value) => field.formats.iter().fold(value, apply_format),
+                    None if field.optional => continue,
+                    None => missing_value(field),
+                };
+                if field.formats.contains(&Format::Initial) {
+                    bucket = Some(initial_bucket(&value));
+                }
+                text.push_str(&value);
+            }
+        }
+    }
+    bucket
+        .into_iter()
+        .chain(std::iter::once(text))
+        .map(|component| sanitize_segment(&component))
+        .collect()
+}
+
+pub fn render_template(template: &str, metadata: &WorkMetadata) -> String {
+    match parse_template(template) {
+        Ok(segments) => segments
+            .iter()
+            .flat_map(|segment| render_segment(segment, metadata))
+            .collect::<Vec<_>>()
+            .join("/"),
+        // Saved templates are validated, so this only guards against a broken
+        // setting: the text is used as it is.
+        Err(_) => template
+            .split('/')
+            .map(sanitize_segment)
+            .collect::<Vec<_>>()
+            .join("/"),
+    }
+}
+
+/// Values read back from a path, in the order their placeholders appear.
+type Captures = Vec<(String, String)>;
+
+fn segment_fields(segment: &[Part]) -> impl Iterator<Item = &Field> {
+    segment.iter().filter_map(|part| match part {
+        Part::Field(field) => Some(field),
+        Part::Literal(_) => None,
+    })
+}
+
+/// Records what a placeholder wrote. A value equal to what a missing field
+/// renders as is not recorded; a year that is not a number fails the match.
+fn capture_value(field: &Field, raw: &str, captures: &mut Captures) -> bool {
+    // The title is never missing, so it is kept even when it reads "Unknown".
+    let missing = field.name != "title" && raw == sanitize_segment(&missing_value(field));
+    if raw.is_empty() || missing {
+        return true;
+    }
+    if field.name == "year" && raw.parse::<i32>().is_err() {
+        return false;
+    }
+    if !captures.iter().any(|(name, _)| *name == field.name) {
+        captures.push((field.name.clone(), raw.to_string()));
+    }
+    true
+}
+
+/// Matches one segment against the path components it rendered to.
+fn match_segment(segment: &[Part], components: &[String], captures: &mut Captures) -> bool {
+    if let [bucket, value] = components {
+        // Only an `:initial` placeholder renders to two components.
+        let [Part::Field(field)] = segment else {
+            return false;
+        };
+        return *bucket == sanitize_segment(&initial_bucket(value))
+            && capture_value(field, value, captures);
+    }
+    let [component] = components else {
+        return false;
+    };
+
+    let mut pattern = String::from("^");
+    let last = segment.len().saturating_sub(1);
+    for (i, part) in segment.iter().enumerate() {
+        match part {
+            Part::Literal(literal) => {
+                // Rendering drops forbidden characters and trims the component.
+                let mut literal: String = literal
+                    .chars()
+                    .filter(|c| !FORBIDDEN_CHARS.contains(c))
+                    .collect();
+                if i == 0 {
+                    literal = literal.trim_start().to_string();
+                }
+                if i == last {
+                    literal = literal.trim_end().to_string();
                 }
+                pattern.push_str(&regex::escape(&literal));
             }
-            sanitize_segment(&result)
+            Part::Field(field) if field.optional => pattern.push_str("(.*?)"),
+            Part::Field(_) => pattern.push_str("(.+?)"),
+        }
+    }
+    pattern.push('$');
+    let Some(found) = Regex::new(&pattern)
+        .ok()
+        .and_then(|regex| regex.captures(component))
+    else {
+        return false;
+    };
+    segment_fields(segment)
+        .zip(found.iter().skip(1))
+        .all(|(field, raw)| capture_value(field, raw.map_or("", |m| m.as_str()), captures))
+}
+
+/// Matches segments against path components, trying each segment as present
+/// before trying it as dropped.
+fn match_segments(segments: &[Segment], components: &[String], captures: &mut Captures) -> bool {
+    let Some((segment, rest)) = segments.split_first() else {
+        return components.is_empty();
+    };
+    let checkpoint = captures.len();
+    let width = if segment_fields(segment).any(|field| field.formats.contains(&Format::Initial)) {
+        2
+    } else {
+        1
+    };
+    if components.len() >= width
+        && match_segment(segment, &components[..width], captures)
+        && match_segments(rest, &components[width..], captures)
+    {
+        return true;
+    }
+    captures.truncate(checkpoint);
+
+    let droppable =
+        segment_fields(segment).count() > 0 && segment_fields(segment).all(|f| f.optional);
+    if droppable && match_segments(rest, components, captures) {
+        return true;
+    }
+    captures.truncate(checkpoint);
+    false
+}
+
+/// Strips the `_0001` suffix added when a templated path was already taken.
+fn strip_collision_suffix(name: &str) -> Option<&str> {
+    let (base, suffix) = name.rsplit_once('_')?;
+    (suffix.len() == 4 && suffix.chars().all(|c| c.is_ascii_hexdigit())).then_some(base)
+}
+
+/// Recovers the metadata a work path was rendered from, the inverse of
+/// [`resolve_work_path`]. Fields written as their default (or "Unknown") come
+/// back as missing, and values cut by formats come back as written. `path`
+/// must not carry the extension of a single-file work. Returns `None` when the
+/// path does not fit the template.
+pub fn parse_work_path(library_root: &Path, template: &str, path: &Path) -> Option<WorkMetadata> {
+    let segments = parse_template(template).ok()?;
+    let relative = path.strip_prefix(library_root).ok()?;
+    let components = relative
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
         })
-        .collect::<Vec<_>>()
-        .join("/")
+        .collect::<Option<Vec<_>>>()?;
+
+    // A name ending in a collision suffix is read without it first.
+    let unsuffixed = components.split_last().and_then(|(last, parents)| {
+        let base = strip_collision_suffix(last)?;
+        Some([parents, &[base.to_string()]].concat())
+    });
+    let mut captures = Captures::new();
+    let matched = unsuffixed
+        .iter()
+        .chain(std::iter::once(&components))
+        .any(|components| match_segments(&segments, components, &mut captures));
+    if !matched {
+        return None;
+    }
+
+    let value = |name: &str| {
+        captures
+            .iter()
+            .find(|(captured, _)| captured == name)
+            .map(|(_, value)| value.clone())
+    };
+    Some(WorkMetadata {
+        title: value("title")?,
+        artist: value("artist"),
+        year: value("year").and_then(|year| year.parse().ok()),
+        genre: value("genre"),
+        circle: value("circle"),
+        origin: value("origin"),
+        work_type: value("type"),
+    })
 }
 
 pub fn resolve_work_path(library_root: &Path, template: &str, metadata: &WorkMetadata) -> PathBuf {
@@ -168,21 +576,63 @@ fn normalize_path(path: &Path) -> PathBuf {
     components.iter().collect()
 }
 
-pub fn resolve_unique_work_path(
+/// Creates the directory for a new folder work and returns its path. When the
+/// templated path is taken, `_0001`, `_0002`, ... suffixes are tried. The
+/// directory is created atomically, so concurrent imports resolving to the same
+/// path each get their own.
+pub fn claim_unique_work_path(
     library_root: &Path,
     template: &str,
     metadata: &WorkMetadata,
-) -> PathBuf {
+) -> Result<PathBuf, AppError> {
     let base = resolve_work_path(library_root, template, metadata);
-    if !base.exists() {
-        return base;
+    if let Some(parent) = base.parent() {
+        std::fs::create_dir_all(parent)?;
     }
     let base_name = base.file_name().unwrap().to_string_lossy().to_string();
-    for i in 1u32.. {
-        let dir_name = format!("{}_{:04x}", base_name, i);
-        let candidate = base.with_file_name(&dir_name);
-        if !candidate.exists() {
-            return candidate;
+    for i in 0u32.. {
+        let candidate = if i == 0 {
+            base.clone()
+        } else {
+            base.with_file_name(format!("{}_{:04x}", base_name, i))
+        };
+        match std::fs::create_dir(&candidate) {
+            Ok(()) => return Ok(candidate),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    unreachable!()
+}
+
+/// Like [`claim_unique_work_path`], but for single-file works: an empty file
+/// with the given extension is created in place of the directory.
+pub fn claim_unique_file_path(
+    library_root: &Path,
+    template: &str,
+    metadata: &WorkMetadata,
+    extension: &str,
+) -> Result<PathBuf, AppError> {
+    let base = resolve_work_path(library_root, template, metadata);
+    if let Some(parent) = base.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let base_name = base.file_name().unwrap().to_string_lossy().to_string();
+    for i in 0u32.. {
+        let file_name = if i == 0 {
+            format!("{}.{}", base_name, extension)
+        } else {
+            format!("{}_{:04x}.{}", base_name, i, extension)
+        };
+        let candidate = base.with_file_name(file_name);
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&candidate)
+        {
+            Ok(_) => return Ok(candidate),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
         }
     }
     unreachable!()
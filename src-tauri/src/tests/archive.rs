@@ -0,0 +1,133 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use super::*;
+
+fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+    let file = File::create(path).unwrap();
+    let mut writer = ZipWriter::new(file);
+    for (name, data) in entries {
+        if name.ends_with('/') {
+            writer
+                .add_directory(*name, SimpleFileOptions::default())
+                .unwrap();
+        } else {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+    }
+    writer.finish().unwrap();
+}
+
+fn temp_zip(dir_name: &str, entries: &[(&str, &[u8])]) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(dir_name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let zip_path = dir.join("book.cbz");
+    write_zip(&zip_path, entries);
+    (dir, zip_path)
+}
+
+#[test]
+fn list_pages_natural_sort_order() {
+    let (dir, zip_path) = temp_zip(
+        "sharaku_test_archive_natord",
+        &[
+            ("page10.jpg", b"10"),
+            ("page2.jpg", b"2"),
+            ("page1.jpg", b"1"),
+        ],
+    );
+
+    let pages = list_archive_pages(&zip_path).unwrap();
+    assert_eq!(pages, vec!["page1.jpg", "page2.jpg", "page10.jpg"]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn list_pages_skips_non_images_and_metadata() {
+    let (dir, zip_path) = temp_zip(
+        "sharaku_test_archive_filter",
+        &[
+            ("chapter/", b""),
+            ("chapter/01.png", b"png"),
+            ("readme.txt", b"text"),
+            ("__MACOSX/chapter/._01.png", b"resource fork"),
+        ],
+    );
+
+    let pages = list_archive_pages(&zip_path).unwrap();
+    assert_eq!(pages, vec!["chapter/01.png"]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn read_page_returns_entry_data() {
+    let (dir, zip_path) = temp_zip(
+        "sharaku_test_archive_read",
+        &[("b.png", b"second"), ("a.jpg", b"first")],
+    );
+
+    let (name, data) = read_archive_page(&zip_path, 1).unwrap();
+    assert_eq!(name, "b.png");
+    assert_eq!(data, b"second");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn read_page_relists_a_replaced_archive() {
+    let (dir, zip_path) = temp_zip(
+        "sharaku_test_archive_replaced",
+        &[("a.jpg", b"first"), ("b.jpg", b"second")],
+    );
+    assert_eq!(read_archive_page(&zip_path, 0).unwrap().1, b"first");
+
+    write_zip(
+        &zip_path,
+        &[
+            ("c.jpg", b"third"),
+            ("0.jpg", b"zeroth"),
+            ("d.jpg", b"fourth"),
+        ],
+    );
+    let (name, data) = read_archive_page(&zip_path, 0).unwrap();
+    assert_eq!(name, "0.jpg");
+    assert_eq!(data, b"zeroth");
+    assert_eq!(read_archive_page(&zip_path, 2).unwrap().1, b"fourth");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn read_page_out_of_range_is_not_found() {
+    let (dir, zip_path) = temp_zip("sharaku_test_archive_range", &[("a.jpg", b"first")]);
+
+    let result = read_archive_page(&zip_path, 1);
+    assert!(matches!(result, Err(AppError::NotFound)));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn list_pages_rejects_non_zip_file() {
+    let dir = std::env::temp_dir().join("sharaku_test_archive_invalid");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("broken.zip");
+    std::fs::write(&path, b"not a zip").unwrap();
+
+    assert!(matches!(
+        list_archive_pages(&path),
+        Err(AppError::Archive(_))
+    ));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -19,6 +19,8 @@ fn sample_record<'a>(title: &'a str, path: &'a str) -> WorkRecord<'a> {
         genre: None,
         circle: None,
         origin: None,
+        content_hash: None,
+        external: false,
     }
 }
 
@@ -136,6 +138,8 @@ fn insert_work_with_metadata() {
         genre: Some("Fantasy"),
         circle: Some("Circle X"),
         origin: Some("Original"),
+        content_hash: None,
+        external: false,
     };
     insert_work(&conn, &record).unwrap();
 
@@ -147,3 +151,319 @@ fn insert_work_with_metadata() {
     assert_eq!(detail.circle.as_deref(), Some("Circle X"));
     assert_eq!(detail.origin.as_deref(), Some("Original"));
 }
+
+fn collect_pages(
+    conn: &Connection,
+    filter: &WorkFilter,
+    sort_by: &str,
+    sort_order: &str,
+) -> Vec<String> {
+    let mut titles = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = list_works_page(conn, filter, sort_by, sort_order, 2, cursor.as_ref()).unwrap();
+        assert!(page.works.len() <= 2);
+        titles.extend(page.works.into_iter().map(|w| w.title));
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    titles
+}
+
+fn insert_with_artist(conn: &Connection, title: &str, artist: Option<&str>) {
+    let path = format!("/{}.jpg", title);
+    let mut record = sample_record(title, &path);
+    record.artist = artist;
+    insert_work(conn, &record).unwrap();
+}
+
+#[test]
+fn list_works_page_walks_every_row_once() {
+    let conn = test_conn();
+    for title in ["A", "B", "C", "D", "E"] {
+        insert_work(&conn, &sample_record(title, &format!("/{}.jpg", title))).unwrap();
+    }
+
+    let filter = WorkFilter::default();
+    assert_eq!(
+        collect_pages(&conn, &filter, "title", "asc"),
+        vec!["A", "B", "C", "D", "E"]
+    );
+    assert_eq!(
+        collect_pages(&conn, &filter, "title", "desc"),
+        vec!["E", "D", "C", "B", "A"]
+    );
+}
+
+#[test]
+fn list_works_page_last_page_has_no_cursor() {
+    let conn = test_conn();
+    insert_work(&conn, &sample_record("A", "/a.jpg")).unwrap();
+    insert_work(&conn, &sample_record("B", "/b.jpg")).unwrap();
+
+    let page = list_works_page(&conn, &WorkFilter::default(), "title", "asc", 2, None).unwrap();
+    assert_eq!(page.works.len(), 2);
+    assert!(page.next_cursor.is_none());
+}
+
+#[test]
+fn list_works_page_handles_null_sort_values_and_ties() {
+    let conn = test_conn();
+    insert_with_artist(&conn, "n1", None);
+    insert_with_artist(&conn, "x1", Some("X"));
+    insert_with_artist(&conn, "n2", None);
+    insert_with_artist(&conn, "a1", Some("A"));
+    insert_with_artist(&conn, "x2", Some("X"));
+
+    let filter = WorkFilter::default();
+    assert_eq!(
+        collect_pages(&conn, &filter, "artist", "asc"),
+        vec!["n1", "n2", "a1", "x1", "x2"]
+    );
+    assert_eq!(
+        collect_pages(&conn, &filter, "artist", "desc"),
+        vec!["x2", "x1", "a1", "n2", "n1"]
+    );
+}
+
+#[test]
+fn list_works_page_applies_filters() {
+    let mut conn = test_conn();
+    let mut record = sample_record("Old", "/old.jpg");
+    record.artist = Some("Alice");
+    record.year = Some(2010);
+    insert_work(&conn, &record).unwrap();
+    let mut record = sample_record("New", "/new.jpg");
+    record.artist = Some("Alice");
+    record.year = Some(2020);
+    record.genre = Some("SF");
+    insert_work(&conn, &record).unwrap();
+    let mut record = sample_record("Other", "/other.pdf");
+    record.work_type = "pdf";
+    record.artist = Some("Bob");
+    record.year = Some(2020);
+    insert_work(&conn, &record).unwrap();
+
+    let titles = |filter: WorkFilter| collect_pages(&conn, &filter, "title", "asc");
+    assert_eq!(
+        titles(WorkFilter {
+            artist: Some("Alice".to_string()),
+            ..Default::default()
+        }),
+        vec!["New", "Old"]
+    );
+    assert_eq!(
+        titles(WorkFilter {
+            year_from: Some(2015),
+            year_to: Some(2020),
+            ..Default::default()
+        }),
+        vec!["New", "Other"]
+    );
+    assert_eq!(
+        titles(WorkFilter {
+            work_type: Some("pdf".to_string()),
+            ..Default::default()
+        }),
+        vec!["Other"]
+    );
+    assert_eq!(
+        titles(WorkFilter {
+            genre: Some("SF".to_string()),
+            artist: Some("Alice".to_string()),
+            ..Default::default()
+        }),
+        vec!["New"]
+    );
+
+    let tag = tags::create_tag(&conn, "color", None).unwrap();
+    let old_id: i64 = conn
+        .query_row("SELECT id FROM works WHERE title = 'Old'", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    tags::add_tag_to_works(&mut conn, &[old_id], tag.id).unwrap();
+    assert_eq!(
+        collect_pages(
+            &conn,
+            &WorkFilter {
+                tag_id: Some(tag.id),
+                ..Default::default()
+            },
+            "title",
+            "asc"
+        ),
+        vec!["Old"]
+    );
+}
+
+#[test]
+fn mark_work_read_orders_by_last_read() {
+    let conn = test_conn();
+    insert_work(&conn, &sample_record("Unread", "/u.jpg")).unwrap();
+    insert_work(&conn, &sample_record("First", "/f.jpg")).unwrap();
+    insert_work(&conn, &sample_record("Second", "/s.jpg")).unwrap();
+    let id_of = |title: &str| -> i64 {
+        conn.query_row("SELECT id FROM works WHERE title = ?1", [title], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    };
+
+    mark_work_read(&conn, id_of("First")).unwrap();
+    conn.execute(
+        "UPDATE works SET last_read_at = '2000-01-01T00:00:00.000Z' WHERE title = 'First'",
+        [],
+    )
+    .unwrap();
+    mark_work_read(&conn, id_of("Second")).unwrap();
+
+    assert_eq!(
+        collect_pages(&conn, &WorkFilter::default(), "last_read_at", "desc"),
+        vec!["Second", "First", "Unread"]
+    );
+    assert!(matches!(
+        mark_work_read(&conn, 9999),
+        Err(AppError::NotFound)
+    ));
+}
+
+#[test]
+fn init_db_is_idempotent_with_last_read_column() {
+    let conn = test_conn();
+    init_db(&conn).unwrap();
+    let has_index: bool = conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type='index' AND name='idx_works_last_read_at'")
+        .unwrap()
+        .exists([])
+        .unwrap();
+    assert!(has_index);
+}
+
+#[test]
+fn migration_017_converts_paths_under_the_library_root_once() {
+    let library = std::env::temp_dir().join("sharaku_test_db_migration_017");
+    let _ = std::fs::remove_dir_all(&library);
+    std::fs::create_dir_all(&library).unwrap();
+    let library_str = library.to_string_lossy().to_string();
+    let work_path = library.join("Title").to_string_lossy().to_string();
+    let moved_path = library
+        .join("Artist")
+        .join("Title")
+        .to_string_lossy()
+        .to_string();
+
+    // A library from before roots: absolute paths and no roots.
+    let conn = test_conn();
+    settings::set_library_root(&conn, &library_str).unwrap();
+    conn.execute(
+        "INSERT INTO works (title, path, type, page_count) VALUES ('Title', ?1, 'folder', 1)",
+        [&moved_path],
+    )
+    .unwrap();
+    let work_id = conn.last_insert_rowid();
+    conn.execute_batch(
+        "INSERT INTO relocation_runs (work_type, new_template) VALUES ('folder', '{artist}/{title}')",
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO relocation_run_items (run_id, work_id, old_path, new_path) VALUES (last_insert_rowid(), ?1, ?2, ?3)",
+        rusqlite::params![work_id, work_path, moved_path],
+    )
+    .unwrap();
+    conn.pragma_update(None, "user_version", 16).unwrap();
+
+    init_db(&conn).unwrap();
+    let root_id: i64 = conn
+        .query_row(
+            "SELECT id FROM library_roots WHERE path = ?1",
+            [&library_str],
+            |row| row.get(0),
+        )
+        .unwrap();
+    let stored: (Option<i64>, String) = conn
+        .query_row(
+            "SELECT root_id, path FROM works WHERE id = ?1",
+            [work_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(stored, (Some(root_id), "Artist/Title".to_string()));
+    let item: (Option<i64>, String, Option<i64>, String) = conn
+        .query_row(
+            "SELECT old_root_id, old_path, new_root_id, new_path FROM relocation_run_items",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .unwrap();
+    assert_eq!(
+        item,
+        (
+            Some(root_id),
+            "Title".to_string(),
+            Some(root_id),
+            "Artist/Title".to_string()
+        )
+    );
+    assert_eq!(get_work(&conn, work_id).unwrap().path, moved_path);
+
+    // Later opens leave absolute paths alone.
+    let outside = library.join("Later").to_string_lossy().to_string();
+    conn.execute(
+        "INSERT INTO works (title, path, type) VALUES ('Later', ?1, 'folder')",
+        [&outside],
+    )
+    .unwrap();
+    init_db(&conn).unwrap();
+    assert!(conn
+        .prepare("SELECT 1 FROM works WHERE root_id IS NULL AND path = ?1")
+        .unwrap()
+        .exists([&outside])
+        .unwrap());
+
+    std::fs::remove_dir_all(&library).unwrap();
+}
+
+#[test]
+fn migration_017_waits_for_an_unmounted_library_root() {
+    let library = std::env::temp_dir().join("sharaku_test_db_migration_017_unmounted");
+    let _ = std::fs::remove_dir_all(&library);
+    let library_str = library.to_string_lossy().to_string();
+    let work_path = library.join("Title").to_string_lossy().to_string();
+
+    let conn = test_conn();
+    settings::set_library_root(&conn, &library_str).unwrap();
+    conn.execute(
+        "INSERT INTO works (title, path, type, page_count) VALUES ('Title', ?1, 'folder', 1)",
+        [&work_path],
+    )
+    .unwrap();
+    let work_id = conn.last_insert_rowid();
+    conn.pragma_update(None, "user_version", 16).unwrap();
+    let stored = || -> (Option<i64>, String) {
+        conn.query_row(
+            "SELECT root_id, path FROM works WHERE id = ?1",
+            [work_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+    };
+    let version = || -> i64 {
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    };
+
+    init_db(&conn).unwrap();
+    assert_eq!(stored(), (None, work_path.clone()));
+    assert_eq!(version(), 16);
+
+    std::fs::create_dir_all(&library).unwrap();
+    init_db(&conn).unwrap();
+    assert_eq!(stored().1, "Title");
+    assert!(stored().0.is_some());
+    assert_eq!(version(), 17);
+
+    std::fs::remove_dir_all(&library).unwrap();
+}
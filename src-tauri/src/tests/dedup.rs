@@ -0,0 +1,96 @@
+use rusqlite::Connection;
+
+use crate::db::{self, WorkRecord};
+use crate::hashing::WorkHashes;
+
+use super::*;
+
+fn setup_test_db() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db_for_test(&conn).unwrap();
+    conn
+}
+
+fn hashes(content: &str, pages: &[&str]) -> WorkHashes {
+    WorkHashes {
+        content_hash: content.to_string(),
+        page_hashes: pages.iter().map(|p| p.to_string()).collect(),
+    }
+}
+
+fn insert_with_hashes(conn: &Connection, title: &str, work_hashes: &WorkHashes) -> i64 {
+    db::insert_work(
+        conn,
+        &WorkRecord {
+            title,
+            path: &format!("/{}", title),
+            work_type: "folder",
+            page_count: work_hashes.page_hashes.len() as i32,
+            thumbnail: b"thumb",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+    let id = conn.last_insert_rowid();
+    store_work_hashes(conn, id, work_hashes).unwrap();
+    id
+}
+
+#[test]
+fn find_duplicates_distinguishes_exact_and_partial() {
+    let conn = setup_test_db();
+    let exact = insert_with_hashes(&conn, "Exact", &hashes("w1", &["a", "b", "c"]));
+    let partial = insert_with_hashes(&conn, "Partial", &hashes("w2", &["a", "x"]));
+    insert_with_hashes(&conn, "Unrelated", &hashes("w3", &["y", "z"]));
+
+    let found = find_duplicates(&conn, &hashes("other", &["c", "b", "a"])).unwrap();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].work_id, exact);
+    assert!(found[0].exact);
+    assert_eq!(found[0].matched_pages, 3);
+    assert_eq!(found[1].work_id, partial);
+    assert!(!found[1].exact);
+    assert_eq!(found[1].matched_pages, 1);
+}
+
+#[test]
+fn find_duplicates_superset_is_partial() {
+    let conn = setup_test_db();
+    insert_with_hashes(&conn, "Short", &hashes("w1", &["a", "b"]));
+
+    let found = find_duplicates(&conn, &hashes("w2", &["a", "b", "c"])).unwrap();
+    assert_eq!(found.len(), 1);
+    assert!(!found[0].exact);
+    assert_eq!(found[0].matched_pages, 2);
+}
+
+#[test]
+fn find_duplicates_matches_content_hash_without_pages() {
+    let conn = setup_test_db();
+    let pdf = insert_with_hashes(&conn, "Pdf", &hashes("same-file", &[]));
+
+    let found = find_duplicates(&conn, &hashes("same-file", &[])).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].work_id, pdf);
+    assert!(found[0].exact);
+}
+
+#[test]
+fn store_work_hashes_replaces_previous_pages() {
+    let conn = setup_test_db();
+    let id = insert_with_hashes(&conn, "Work", &hashes("w1", &["a", "b"]));
+    store_work_hashes(&conn, id, &hashes("w2", &["c"])).unwrap();
+
+    assert!(find_duplicates(&conn, &hashes("x", &["a"]))
+        .unwrap()
+        .is_empty());
+    let found = find_duplicates(&conn, &hashes("x", &["c"])).unwrap();
+    assert!(found[0].exact);
+    assert!(has_page_hashes(&conn, id).unwrap());
+}
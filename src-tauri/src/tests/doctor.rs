@@ -0,0 +1,260 @@
+use std::path::Path;
+
+use rusqlite::Connection;
+use tauri::ipc::Channel;
+
+use crate::db::{self, WorkRecord};
+use crate::hashing;
+use crate::settings;
+
+use super::*;
+
+fn make_folder(dir: &Path, pages: &[&[u8]]) {
+    std::fs::create_dir_all(dir).unwrap();
+    for (i, data) in pages.iter().enumerate() {
+        std::fs::write(dir.join(format!("{:02}.jpg", i + 1)), data).unwrap();
+    }
+}
+
+fn register_folder(conn: &Connection, title: &str, path: &Path, page_count: i32) -> i64 {
+    let hash = hashing::hash_work_content(path, "folder").ok();
+    db::insert_work(
+        conn,
+        &WorkRecord {
+            title,
+            path: &path.to_string_lossy(),
+            work_type: "folder",
+            page_count,
+            thumbnail: b"thumb",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: hash.as_deref(),
+            external: false,
+        },
+    )
+    .unwrap();
+    conn.last_insert_rowid()
+}
+
+fn channel() -> Channel<DoctorProgress> {
+    Channel::new(|_| Ok(()))
+}
+
+#[test]
+fn run_doctor_reports_missing_orphaned_and_mismatched() {
+    let temp = std::env::temp_dir().join("sharaku_test_doctor_report");
+    let _ = std::fs::remove_dir_all(&temp);
+    let app_data_dir = temp.join("app_data");
+    let library_root = temp.join("library");
+
+    let healthy = library_root.join("Healthy");
+    let grown = library_root.join("Grown");
+    let moved_from = library_root.join("Old");
+    let moved_to = library_root.join("Renamed");
+    let stray = library_root.join("Stray");
+    make_folder(&healthy, &[b"h1"]);
+    make_folder(&grown, &[b"g1", b"g2"]);
+    make_folder(&moved_from, &[b"m1", b"m2"]);
+    make_folder(&stray, &[b"s1"]);
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    register_folder(&conn, "Healthy", &healthy, 1);
+    let grown_id = register_folder(&conn, "Grown", &grown, 1);
+    let moved_id = register_folder(&conn, "Moved", &moved_from, 2);
+    let gone_id = register_folder(&conn, "Gone", &library_root.join("Gone"), 3);
+    drop(conn);
+
+    std::fs::rename(&moved_from, &moved_to).unwrap();
+
+    let report = run_doctor(&app_data_dir, &channel()).unwrap();
+
+    assert_eq!(report.missing.len(), 2);
+    let moved = report
+        .missing
+        .iter()
+        .find(|m| m.work_id == moved_id)
+        .unwrap();
+    assert_eq!(
+        moved.relink_candidate.as_deref(),
+        Some(&*moved_to.to_string_lossy())
+    );
+    let gone = report
+        .missing
+        .iter()
+        .find(|m| m.work_id == gone_id)
+        .unwrap();
+    assert!(gone.relink_candidate.is_none());
+
+    let mut orphaned: Vec<&str> = report.orphaned.iter().map(|o| o.path.as_str()).collect();
+    orphaned.sort();
+    assert_eq!(
+        orphaned,
+        vec![&*moved_to.to_string_lossy(), &*stray.to_string_lossy()]
+    );
+
+    assert_eq!(report.page_count_mismatches.len(), 1);
+    assert_eq!(report.page_count_mismatches[0].work_id, grown_id);
+    assert_eq!(report.page_count_mismatches[0].recorded, 1);
+    assert_eq!(report.page_count_mismatches[0].actual, 2);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn run_doctor_scans_every_mounted_root_for_orphans() {
+    let temp = std::env::temp_dir().join("sharaku_test_doctor_roots");
+    let _ = std::fs::remove_dir_all(&temp);
+    let app_data_dir = temp.join("app_data");
+    let library_root = temp.join("library");
+    let volume = temp.join("volume");
+    let unmounted = temp.join("unmounted");
+    make_folder(&library_root.join("InLibrary"), &[b"l1"]);
+    make_folder(&volume.join("OnVolume"), &[b"v1"]);
+    std::fs::create_dir_all(&unmounted).unwrap();
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    roots::create_root(&conn, "Volume", &volume.to_string_lossy()).unwrap();
+    roots::create_root(&conn, "Unmounted", &unmounted.to_string_lossy()).unwrap();
+    drop(conn);
+    std::fs::remove_dir_all(&unmounted).unwrap();
+
+    let report = run_doctor(&app_data_dir, &channel()).unwrap();
+    let mut orphaned: Vec<&str> = report.orphaned.iter().map(|o| o.path.as_str()).collect();
+    orphaned.sort();
+    assert_eq!(
+        orphaned,
+        vec![
+            &*library_root.join("InLibrary").to_string_lossy(),
+            &*volume.join("OnVolume").to_string_lossy()
+        ]
+    );
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn run_doctor_does_not_report_single_image_folders_as_orphans() {
+    let temp = std::env::temp_dir().join("sharaku_test_doctor_single_image");
+    let _ = std::fs::remove_dir_all(&temp);
+    let app_data_dir = temp.join("app_data");
+    let library_root = temp.join("library");
+    let image_dir = library_root.join("Illust");
+    make_folder(&image_dir, &[b"i1"]);
+    let image = image_dir.join("01.jpg");
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    db::insert_work(
+        &conn,
+        &WorkRecord {
+            title: "Illust",
+            path: &image.to_string_lossy(),
+            work_type: "image",
+            page_count: 1,
+            thumbnail: b"thumb",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+    drop(conn);
+
+    let report = run_doctor(&app_data_dir, &channel()).unwrap();
+    assert!(report.missing.is_empty());
+    assert!(report.orphaned.is_empty());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn backfill_hashes_fills_in_missing_content_hash() {
+    let temp = std::env::temp_dir().join("sharaku_test_doctor_backfill");
+    let _ = std::fs::remove_dir_all(&temp);
+    let app_data_dir = temp.join("app_data");
+    let folder = temp.join("Work");
+    make_folder(&folder, &[b"p1"]);
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    let id = register_folder(&conn, "Work", &folder, 1);
+    conn.execute("UPDATE works SET content_hash = NULL", [])
+        .unwrap();
+    drop(conn);
+
+    // Checking reports the work but leaves the database alone.
+    let report = run_doctor(&app_data_dir, &channel()).unwrap();
+    assert_eq!(report.unhashed, vec![id]);
+    let mut conn = db::open_db(&app_data_dir).unwrap();
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT content_hash FROM works WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(stored, None);
+
+    backfill_hashes(&mut conn, &report.unhashed).unwrap();
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT content_hash FROM works WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(
+        stored,
+        Some(hashing::hash_work_content(&folder, "folder").unwrap())
+    );
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn relink_work_updates_path_and_page_count() {
+    let temp = std::env::temp_dir().join("sharaku_test_doctor_relink");
+    let _ = std::fs::remove_dir_all(&temp);
+    let old = temp.join("Old");
+    let new = temp.join("New");
+    make_folder(&new, &[b"a", b"b", b"c"]);
+
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db_for_test(&conn).unwrap();
+    let id = register_folder(&conn, "Work", &old, 2);
+
+    let work = relink_work(&conn, id, &new).unwrap();
+    assert_eq!(work.path, new.to_string_lossy());
+    assert_eq!(work.page_count, 3);
+
+    let result = relink_work(&conn, id, &temp.join("Nowhere"));
+    assert!(matches!(result, Err(AppError::WorkError(_))));
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn recount_pages_updates_stale_counts() {
+    let temp = std::env::temp_dir().join("sharaku_test_doctor_recount");
+    let _ = std::fs::remove_dir_all(&temp);
+    let folder = temp.join("Work");
+    make_folder(&folder, &[b"a", b"b"]);
+
+    let mut conn = Connection::open_in_memory().unwrap();
+    db::init_db_for_test(&conn).unwrap();
+    let id = register_folder(&conn, "Work", &folder, 5);
+
+    recount_pages(&mut conn, &[id]).unwrap();
+    assert_eq!(db::get_work(&conn, id).unwrap().page_count, 2);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
@@ -0,0 +1,68 @@
+use super::*;
+
+#[test]
+fn folder_hash_ignores_names_but_not_order() {
+    let temp = std::env::temp_dir().join("sharaku_test_hash_folder");
+    let _ = std::fs::remove_dir_all(&temp);
+    let a = temp.join("a");
+    let b = temp.join("b");
+    let c = temp.join("c");
+    for dir in [&a, &b, &c] {
+        std::fs::create_dir_all(dir).unwrap();
+    }
+    std::fs::write(a.join("01.jpg"), b"one").unwrap();
+    std::fs::write(a.join("02.jpg"), b"two").unwrap();
+    std::fs::write(b.join("p1.jpg"), b"one").unwrap();
+    std::fs::write(b.join("p2.jpg"), b"two").unwrap();
+    std::fs::write(c.join("01.jpg"), b"two").unwrap();
+    std::fs::write(c.join("02.jpg"), b"one").unwrap();
+
+    let hash_a = hash_work_content(&a, "folder").unwrap();
+    assert_eq!(hash_a.len(), 64);
+    assert_eq!(hash_a, hash_work_content(&b, "folder").unwrap());
+    assert_ne!(hash_a, hash_work_content(&c, "folder").unwrap());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn folder_hash_separates_page_boundaries() {
+    let temp = std::env::temp_dir().join("sharaku_test_hash_boundaries");
+    let _ = std::fs::remove_dir_all(&temp);
+    let a = temp.join("a");
+    let b = temp.join("b");
+    std::fs::create_dir_all(&a).unwrap();
+    std::fs::create_dir_all(&b).unwrap();
+    std::fs::write(a.join("01.jpg"), b"ab").unwrap();
+    std::fs::write(a.join("02.jpg"), b"c").unwrap();
+    std::fs::write(b.join("01.jpg"), b"a").unwrap();
+    std::fs::write(b.join("02.jpg"), b"bc").unwrap();
+
+    assert_ne!(
+        hash_work_content(&a, "folder").unwrap(),
+        hash_work_content(&b, "folder").unwrap()
+    );
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn hash_work_returns_one_hash_per_page() {
+    let temp = std::env::temp_dir().join("sharaku_test_hash_pages");
+    let _ = std::fs::remove_dir_all(&temp);
+    std::fs::create_dir_all(&temp).unwrap();
+    std::fs::write(temp.join("01.jpg"), b"same").unwrap();
+    std::fs::write(temp.join("02.jpg"), b"same").unwrap();
+    std::fs::write(temp.join("03.jpg"), b"other").unwrap();
+
+    let hashes = hash_work(&temp, "folder").unwrap();
+    assert_eq!(hashes.page_hashes.len(), 3);
+    assert_eq!(hashes.page_hashes[0], hashes.page_hashes[1]);
+    assert_ne!(hashes.page_hashes[0], hashes.page_hashes[2]);
+    assert_eq!(
+        hashes.content_hash,
+        hash_work_content(&temp, "folder").unwrap()
+    );
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
@@ -0,0 +1,107 @@
+use rusqlite::Connection;
+
+use crate::db;
+use crate::importer::ImportMode;
+
+use super::*;
+
+fn setup_test_db() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db_for_test(&conn).unwrap();
+    conn
+}
+
+fn request(title: &str) -> ImportRequest {
+    ImportRequest {
+        source_path: format!("/src/{}", title),
+        title: title.to_string(),
+        artist: Some("Artist".to_string()),
+        year: Some(2020),
+        genre: None,
+        circle: None,
+        origin: None,
+        mode: ImportMode::Move,
+        allow_duplicates: true,
+    }
+}
+
+#[test]
+fn create_job_persists_requests_in_order() {
+    let mut conn = setup_test_db();
+    let job_id = create_job(&mut conn, &[request("A"), request("B")]).unwrap();
+
+    let items = pending_items(&conn, job_id).unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].request.title, "A");
+    assert_eq!(items[0].request.year, Some(2020));
+    assert!(items[0].request.mode == ImportMode::Move);
+    assert!(items[0].request.allow_duplicates);
+    assert_eq!(items[1].request.title, "B");
+
+    let job = get_job(&conn, job_id).unwrap();
+    assert_eq!(job.status, "running");
+    assert_eq!(job.total, 2);
+    assert!(!job.active);
+}
+
+#[test]
+fn finished_items_are_no_longer_pending() {
+    let mut conn = setup_test_db();
+    let job_id = create_job(&mut conn, &[request("A"), request("B"), request("C")]).unwrap();
+    let items = pending_items(&conn, job_id).unwrap();
+    finish_item(&conn, items[0].id, None).unwrap();
+    finish_item(&conn, items[1].id, Some("boom")).unwrap();
+
+    let pending = pending_items(&conn, job_id).unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].request.title, "C");
+
+    let job = get_job(&conn, job_id).unwrap();
+    assert_eq!(job.succeeded, 1);
+    assert_eq!(job.failed, 1);
+}
+
+#[test]
+fn list_unfinished_jobs_skips_completed() {
+    let mut conn = setup_test_db();
+    let done = create_job(&mut conn, &[request("A")]).unwrap();
+    let cancelled = create_job(&mut conn, &[request("B")]).unwrap();
+    let interrupted = create_job(&mut conn, &[request("C")]).unwrap();
+    set_job_status(&conn, done, "completed").unwrap();
+    set_job_status(&conn, cancelled, "cancelled").unwrap();
+
+    let ids: Vec<i64> = list_unfinished_jobs(&conn)
+        .unwrap()
+        .iter()
+        .map(|job| job.id)
+        .collect();
+    assert_eq!(ids, vec![cancelled, interrupted]);
+}
+
+#[test]
+fn delete_job_removes_items() {
+    let mut conn = setup_test_db();
+    let job_id = create_job(&mut conn, &[request("A")]).unwrap();
+    delete_job(&conn, job_id).unwrap();
+
+    assert!(matches!(get_job(&conn, job_id), Err(AppError::NotFound)));
+    let count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM import_job_items", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn activate_rejects_running_job_and_cancel_sets_flag() {
+    let job_id = -13;
+    let flag = activate(job_id).unwrap();
+    assert!(matches!(activate(job_id), Err(AppError::ImportJobError(_))));
+
+    request_cancel(job_id).unwrap();
+    assert!(flag.load(Ordering::SeqCst));
+
+    deactivate(job_id);
+    assert!(matches!(request_cancel(job_id), Err(AppError::NotFound)));
+}
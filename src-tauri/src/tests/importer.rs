@@ -126,8 +126,45 @@ fn preview_path_with_template() {
         origin: None,
         work_type: None,
     };
-    let result = preview_import_path(Path::new("/library"), "{artist}/{title}", &metadata);
+    let result = preview_import_path(Path::new("/library"), "{artist}/{title}", &metadata, "");
     assert_eq!(result, "/library/Artist/My Work");
+    let result = preview_import_path(Path::new("/library"), "{title}", &metadata, "cbz");
+    assert_eq!(result, "/library/My Work.cbz");
+}
+
+#[test]
+fn preview_source_destination_uses_the_type_template_and_extension() {
+    let dir = std::env::temp_dir().join("sharaku_test_preview_source");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("Folder")).unwrap();
+    std::fs::write(dir.join("Book.CBZ"), b"zip").unwrap();
+
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    crate::db::init_db_for_test(&conn).unwrap();
+    settings::set_library_root(&conn, "/library").unwrap();
+    settings::set_directory_template(&conn, "{type}/{title}").unwrap();
+    settings::set_type_template(&conn, "archive", Some("books/{artist}/{title}")).unwrap();
+    let metadata = WorkMetadata {
+        title: "My Work".to_string(),
+        artist: Some("Artist".to_string()),
+        year: None,
+        genre: None,
+        circle: None,
+        origin: None,
+        work_type: None,
+    };
+
+    assert_eq!(
+        preview_source_destination(&conn, &dir.join("Book.CBZ"), &metadata).unwrap(),
+        "/library/books/Artist/My Work.cbz"
+    );
+    let folder_label = settings::get_type_label_folder(&conn).unwrap();
+    assert_eq!(
+        preview_source_destination(&conn, &dir.join("Folder"), &metadata).unwrap(),
+        format!("/library/{}/My Work", folder_label)
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
 }
 
 // paths_overlap tests
@@ -216,3 +253,469 @@ fn count_direct_images_empty_dir() {
 
     std::fs::remove_dir_all(&dir).unwrap();
 }
+
+// import_work archive tests
+
+fn png_bytes() -> Vec<u8> {
+    let img = image::RgbImage::from_pixel(4, 4, image::Rgb([200, 100, 50]));
+    let mut buf = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut buf, image::ImageFormat::Png).unwrap();
+    buf.into_inner()
+}
+
+fn write_zip(path: &Path, names: &[&str]) {
+    use std::io::Write;
+
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    for name in names {
+        writer
+            .start_file(*name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(&png_bytes()).unwrap();
+    }
+    writer.finish().unwrap();
+}
+
+#[test]
+fn import_archive_copies_file_and_registers_work() {
+    let temp = std::env::temp_dir().join("sharaku_test_import_archive");
+    let _ = std::fs::remove_dir_all(&temp);
+    let library_root = temp.join("library");
+    let app_data_dir = temp.join("app_data");
+    std::fs::create_dir_all(&library_root).unwrap();
+
+    let source = temp.join("Book.CBZ");
+    write_zip(&source, &["02.png", "01.png"]);
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    settings::set_directory_template(&conn, "{artist}/{title}").unwrap();
+    drop(conn);
+
+    let request = ImportRequest {
+        source_path: source.to_string_lossy().to_string(),
+        title: "Book".to_string(),
+        artist: Some("Artist".to_string()),
+        year: None,
+        genre: None,
+        circle: None,
+        origin: None,
+        mode: ImportMode::Move,
+        allow_duplicates: false,
+    };
+    let result = import_work(&request, &app_data_dir).unwrap();
+
+    let expected = library_root.join("Artist").join("Book.cbz");
+    assert_eq!(result.destination_path, expected.to_string_lossy());
+    assert_eq!(result.page_count, 2);
+    assert!(expected.is_file());
+    assert!(!source.exists());
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    let works = db::list_works(&conn, "title", "asc").unwrap();
+    assert_eq!(works.len(), 1);
+    assert_eq!(works[0].work_type, "archive");
+    assert_eq!(works[0].page_count, 2);
+    assert!(!db::get_thumbnail(&conn, works[0].id).unwrap().is_empty());
+    let journaled: i64 = conn
+        .query_row("SELECT COUNT(*) FROM file_operations", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(journaled, 0);
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn import_refuses_exact_duplicate_unless_allowed() {
+    let temp = std::env::temp_dir().join("sharaku_test_import_duplicate");
+    let _ = std::fs::remove_dir_all(&temp);
+    let library_root = temp.join("library");
+    let app_data_dir = temp.join("app_data");
+    let source = temp.join("Source");
+    std::fs::create_dir_all(&library_root).unwrap();
+    std::fs::create_dir_all(&source).unwrap();
+    std::fs::write(source.join("01.png"), png_bytes()).unwrap();
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    settings::set_directory_template(&conn, "{title}").unwrap();
+    drop(conn);
+
+    let mut request = ImportRequest {
+        source_path: source.to_string_lossy().to_string(),
+        title: "First".to_string(),
+        artist: None,
+        year: None,
+        genre: None,
+        circle: None,
+        origin: None,
+        mode: ImportMode::Copy,
+        allow_duplicates: false,
+    };
+    let first = import_work(&request, &app_data_dir).unwrap();
+    assert!(first.duplicates.is_empty());
+
+    request.title = "Second".to_string();
+    let refused = import_work(&request, &app_data_dir);
+    assert!(matches!(refused, Err(AppError::ImportError(_))));
+    assert!(!library_root.join("Second").exists());
+
+    request.allow_duplicates = true;
+    let second = import_work(&request, &app_data_dir).unwrap();
+    assert_eq!(second.duplicates.len(), 1);
+    assert!(second.duplicates[0].exact);
+    assert_eq!(second.duplicates[0].title, "First");
+
+    let folders = discover_image_folders(
+        &source,
+        &app_data_dir,
+        &tauri::ipc::Channel::new(|_| Ok(())),
+    )
+    .unwrap();
+    assert_eq!(folders.len(), 1);
+    assert!(!folders[0].already_registered);
+    assert_eq!(folders[0].duplicates.len(), 2);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn discover_reports_archives_alongside_image_folders() {
+    let temp = std::env::temp_dir().join("sharaku_test_discover_archives");
+    let _ = std::fs::remove_dir_all(&temp);
+    let source = temp.join("source");
+    std::fs::create_dir_all(source.join("Folder")).unwrap();
+    std::fs::write(source.join("Folder").join("01.png"), png_bytes()).unwrap();
+    write_zip(&source.join("Book.cbz"), &["01.png", "02.png"]);
+    std::fs::write(source.join("notes.txt"), b"text").unwrap();
+
+    let mut found: Vec<(String, String, usize)> = discover_image_folders(
+        &source,
+        &temp.join("app_data"),
+        &tauri::ipc::Channel::new(|_| Ok(())),
+    )
+    .unwrap()
+    .into_iter()
+    .map(|f| (f.folder_name, f.work_type, f.image_count))
+    .collect();
+    found.sort();
+    assert_eq!(
+        found,
+        vec![
+            ("Book.cbz".to_string(), "archive".to_string(), 2),
+            ("Folder".to_string(), "folder".to_string(), 1),
+        ]
+    );
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+// import job tests
+
+#[test]
+fn cancelled_job_resumes_remaining_items() {
+    let temp = std::env::temp_dir().join("sharaku_test_import_job_resume");
+    let _ = std::fs::remove_dir_all(&temp);
+    let library_root = temp.join("library");
+    let app_data_dir = temp.join("app_data");
+    std::fs::create_dir_all(&library_root).unwrap();
+
+    let requests: Vec<ImportRequest> = ["One", "Two"]
+        .iter()
+        .map(|title| {
+            let source = temp.join(title);
+            std::fs::create_dir_all(&source).unwrap();
+            std::fs::write(source.join("01.png"), png_bytes()).unwrap();
+            std::fs::write(source.join(format!("{}.png", title)), title).unwrap();
+            ImportRequest {
+                source_path: source.to_string_lossy().to_string(),
+                title: title.to_string(),
+                artist: None,
+                year: None,
+                genre: None,
+                circle: None,
+                origin: None,
+                mode: ImportMode::Copy,
+                allow_duplicates: false,
+            }
+        })
+        .collect();
+
+    let mut conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    settings::set_directory_template(&conn, "{title}").unwrap();
+    let job_id = import_jobs::create_job(&mut conn, &requests).unwrap();
+    drop(conn);
+
+    let channel = tauri::ipc::Channel::new(|_| Ok(()));
+    let cancelled =
+        run_active_job(job_id, &app_data_dir, &channel, &AtomicBool::new(true)).unwrap();
+    assert!(cancelled.cancelled);
+    assert_eq!(cancelled.succeeded + cancelled.failed, 0);
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    let job = import_jobs::get_job(&conn, job_id).unwrap();
+    assert_eq!(job.status, "cancelled");
+    let item = import_jobs::pending_items(&conn, job_id).unwrap().remove(0);
+    import_work(&item.request, &app_data_dir).unwrap();
+    import_jobs::finish_item(&conn, item.id, None).unwrap();
+    drop(conn);
+
+    // The first item was finished by an earlier run and must not be imported again.
+    let summary = run_import_job(job_id, &app_data_dir, &channel).unwrap();
+    assert!(!summary.cancelled);
+    assert_eq!(summary.succeeded, 2);
+    assert_eq!(summary.failed, 0);
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    assert_eq!(db::list_works(&conn, "title", "asc").unwrap().len(), 2);
+    assert_eq!(
+        import_jobs::get_job(&conn, job_id).unwrap().status,
+        "completed"
+    );
+    assert!(matches!(
+        run_import_job(job_id, &app_data_dir, &channel),
+        Err(AppError::ImportJobError(_))
+    ));
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn parallel_bulk_import_reports_in_order_and_keeps_paths_unique() {
+    use std::sync::{Arc, Mutex};
+
+    let temp = std::env::temp_dir().join("sharaku_test_import_parallel");
+    let _ = std::fs::remove_dir_all(&temp);
+    let library_root = temp.join("library");
+    let app_data_dir = temp.join("app_data");
+    std::fs::create_dir_all(&library_root).unwrap();
+
+    // Every request resolves to the same template path.
+    let requests: Vec<ImportRequest> = (0..6)
+        .map(|i| {
+            let source = temp.join(format!("source{}", i));
+            std::fs::create_dir_all(&source).unwrap();
+            std::fs::write(source.join("01.png"), png_bytes()).unwrap();
+            std::fs::write(source.join("02.png"), format!("page {}", i)).unwrap();
+            ImportRequest {
+                source_path: source.to_string_lossy().to_string(),
+                title: "Same".to_string(),
+                artist: None,
+                year: None,
+                genre: None,
+                circle: None,
+                origin: None,
+                mode: ImportMode::Move,
+                allow_duplicates: false,
+            }
+        })
+        .collect();
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    settings::set_directory_template(&conn, "{title}").unwrap();
+    settings::set_import_workers(&conn, 3).unwrap();
+    drop(conn);
+
+    let currents = Arc::new(Mutex::new(Vec::new()));
+    let sink = currents.clone();
+    let channel = tauri::ipc::Channel::new(move |body| {
+        if let tauri::ipc::InvokeResponseBody::Json(json) = body {
+            let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+            if value["type"] == "importing" {
+                sink.lock()
+                    .unwrap()
+                    .push(value["current"].as_u64().unwrap());
+            }
+        }
+        Ok(())
+    });
+    let summary = bulk_import(&requests, &app_data_dir, &channel).unwrap();
+    assert_eq!(summary.succeeded, 6);
+    assert_eq!(summary.failed, 0);
+    assert_eq!(*currents.lock().unwrap(), vec![1, 2, 3, 4, 5, 6]);
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    let mut paths: Vec<String> = db::list_works(&conn, "title", "asc")
+        .unwrap()
+        .into_iter()
+        .map(|w| db::get_work(&conn, w.id).unwrap().path)
+        .collect();
+    paths.sort();
+    paths.dedup();
+    assert_eq!(paths.len(), 6);
+    for path in &paths {
+        assert_eq!(std::fs::read_dir(path).unwrap().count(), 2);
+    }
+    assert!(!temp.join("source0").exists());
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+// extra file tests
+
+fn folder_with_extras(source: &Path) {
+    std::fs::create_dir_all(source.join("extras")).unwrap();
+    std::fs::write(source.join("01.png"), png_bytes()).unwrap();
+    std::fs::write(source.join("credits.txt"), b"credits").unwrap();
+    std::fs::write(source.join("info.nfo"), b"info").unwrap();
+    std::fs::write(source.join("extras").join("bonus.jpg"), b"bonus").unwrap();
+}
+
+fn move_request(source: &Path, title: &str) -> ImportRequest {
+    ImportRequest {
+        source_path: source.to_string_lossy().to_string(),
+        title: title.to_string(),
+        artist: None,
+        year: None,
+        genre: None,
+        circle: None,
+        origin: None,
+        mode: ImportMode::Move,
+        allow_duplicates: true,
+    }
+}
+
+#[test]
+fn move_import_carries_extra_files_per_setting() {
+    let temp = std::env::temp_dir().join("sharaku_test_import_extras");
+    let _ = std::fs::remove_dir_all(&temp);
+    let library_root = temp.join("library");
+    let app_data_dir = temp.join("app_data");
+    std::fs::create_dir_all(&library_root).unwrap();
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    settings::set_directory_template(&conn, "{title}").unwrap();
+
+    // Images only: everything else stays behind in the source.
+    let source = temp.join("none");
+    folder_with_extras(&source);
+    import_work(&move_request(&source, "None"), &app_data_dir).unwrap();
+    assert!(library_root.join("None").join("01.png").is_file());
+    assert!(!library_root.join("None").join("credits.txt").exists());
+    assert!(!source.join("01.png").exists());
+    assert!(source.join("credits.txt").is_file());
+
+    // Allow-list: listed extensions travel, the rest stays.
+    settings::set_extra_files(&conn, "listed", &["txt".to_string()]).unwrap();
+    let source = temp.join("listed");
+    folder_with_extras(&source);
+    import_work(&move_request(&source, "Listed"), &app_data_dir).unwrap();
+    assert!(library_root.join("Listed").join("credits.txt").is_file());
+    assert!(!library_root.join("Listed").join("info.nfo").exists());
+    assert!(!source.join("credits.txt").exists());
+    assert!(source.join("info.nfo").is_file());
+    assert!(source.join("extras").join("bonus.jpg").is_file());
+
+    // Whole tree: the source disappears entirely.
+    settings::set_extra_files(&conn, "all", &[]).unwrap();
+    let source = temp.join("all");
+    folder_with_extras(&source);
+    let result = import_work(&move_request(&source, "All"), &app_data_dir).unwrap();
+    let dest = library_root.join("All");
+    assert!(dest.join("info.nfo").is_file());
+    assert!(dest.join("extras").join("bonus.jpg").is_file());
+    assert!(!source.exists());
+    // Only top-level images are pages.
+    assert_eq!(result.page_count, 1);
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn adopt_registers_work_in_place_with_path_metadata() {
+    let temp = std::env::temp_dir().join("sharaku_test_import_adopt");
+    let _ = std::fs::remove_dir_all(&temp);
+    let library_root = temp.join("library");
+    let app_data_dir = temp.join("app_data");
+    let source = library_root.join("Artist").join("2020").join("Title");
+    let outside = temp.join("Outside");
+    std::fs::create_dir_all(&source).unwrap();
+    std::fs::create_dir_all(&outside).unwrap();
+    std::fs::write(source.join("01.png"), png_bytes()).unwrap();
+    std::fs::write(outside.join("01.png"), png_bytes()).unwrap();
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    settings::set_directory_template(&conn, "{artist}/{year}/{title}").unwrap();
+    let metadata = read_source_metadata(&conn, &FolderNameParser::builtin(), &source).unwrap();
+    assert_eq!(metadata.title, "Title");
+    assert_eq!(metadata.artist.as_deref(), Some("Artist"));
+    assert_eq!(metadata.year, Some(2020));
+    drop(conn);
+
+    let mut request = ImportRequest {
+        source_path: source.to_string_lossy().to_string(),
+        title: metadata.title,
+        artist: metadata.artist,
+        year: metadata.year,
+        genre: None,
+        circle: None,
+        origin: None,
+        mode: ImportMode::Adopt,
+        allow_duplicates: false,
+    };
+    let result = import_work(&request, &app_data_dir).unwrap();
+    assert_eq!(result.destination_path, source.to_string_lossy());
+    assert!(source.join("01.png").is_file());
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    let works = db::list_works(&conn, "title", "asc").unwrap();
+    assert_eq!(works.len(), 1);
+    assert_eq!(db::get_work(&conn, works[0].id).unwrap().year, Some(2020));
+    drop(conn);
+
+    request.allow_duplicates = true;
+    assert!(matches!(
+        import_work(&request, &app_data_dir),
+        Err(AppError::ImportError(_))
+    ));
+    request.source_path = outside.to_string_lossy().to_string();
+    assert!(matches!(
+        import_work(&request, &app_data_dir),
+        Err(AppError::ImportError(_))
+    ));
+    assert!(outside.join("01.png").is_file());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn reference_registers_external_work_without_template() {
+    let temp = std::env::temp_dir().join("sharaku_test_import_reference");
+    let _ = std::fs::remove_dir_all(&temp);
+    let app_data_dir = temp.join("app_data");
+    let source = temp.join("drive").join("Work");
+    std::fs::create_dir_all(&source).unwrap();
+    std::fs::write(source.join("01.png"), png_bytes()).unwrap();
+
+    // No library root or template is needed to reference a work.
+    let request = ImportRequest {
+        source_path: source.to_string_lossy().to_string(),
+        title: "Work".to_string(),
+        artist: None,
+        year: None,
+        genre: None,
+        circle: None,
+        origin: None,
+        mode: ImportMode::Reference,
+        allow_duplicates: false,
+    };
+    let result = import_work(&request, &app_data_dir).unwrap();
+    assert_eq!(result.destination_path, source.to_string_lossy());
+    assert!(source.join("01.png").is_file());
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    let works = db::list_works(&conn, "title", "asc").unwrap();
+    assert_eq!(works.len(), 1);
+    assert!(db::get_work(&conn, works[0].id).unwrap().external);
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
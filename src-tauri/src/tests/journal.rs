@@ -0,0 +1,308 @@
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use crate::db::{self, WorkRecord};
+use crate::importer::{ImportMode, ImportRequest};
+use crate::roots;
+use crate::settings;
+
+use super::*;
+
+fn setup_test_db() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db_for_test(&conn).unwrap();
+    conn
+}
+
+fn make_folder(path: &Path, pages: &[(&str, &[u8])]) {
+    std::fs::create_dir_all(path).unwrap();
+    for (name, bytes) in pages {
+        std::fs::write(path.join(name), bytes).unwrap();
+    }
+}
+
+fn folder_operation(kind: OperationKind, source: &Path, destination: &Path) -> FileOperation {
+    FileOperation {
+        kind,
+        work_id: None,
+        source: source.to_path_buf(),
+        destination: destination.to_path_buf(),
+        work_type: "folder".to_string(),
+        delete_source: true,
+        job_item_id: None,
+        strategy: MoveStrategy::Copy,
+    }
+}
+
+fn insert_work(conn: &Connection, path: &Path) -> i64 {
+    db::insert_work(
+        conn,
+        &WorkRecord {
+            title: "Work",
+            path: &path.to_string_lossy(),
+            work_type: "folder",
+            page_count: 1,
+            thumbnail: b"thumb",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+    conn.last_insert_rowid()
+}
+
+fn journal_len(conn: &Connection) -> i64 {
+    conn.query_row("SELECT COUNT(*) FROM file_operations", [], |row| row.get(0))
+        .unwrap()
+}
+
+#[test]
+fn recover_rolls_back_uncommitted_copy() {
+    let temp = std::env::temp_dir().join("sharaku_test_journal_rollback");
+    let _ = std::fs::remove_dir_all(&temp);
+    let source = temp.join("source");
+    let root = temp.join("library");
+    let destination = root.join("Artist").join("Work");
+    make_folder(&source, &[("01.jpg", b"page")]);
+    make_folder(&destination, &[("01.jpg", b"pa")]);
+
+    let conn = setup_test_db();
+    settings::set_library_root(&conn, &root.to_string_lossy()).unwrap();
+    begin(
+        &conn,
+        &folder_operation(OperationKind::Import, &source, &destination),
+    )
+    .unwrap();
+
+    let summary = recover(&conn).unwrap();
+    assert_eq!(summary.rolled_back, 1);
+    assert!(!destination.exists());
+    assert!(!root.join("Artist").exists());
+    assert!(source.join("01.jpg").is_file());
+    assert_eq!(journal_len(&conn), 0);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn recover_rolls_forward_committed_relocation() {
+    let temp = std::env::temp_dir().join("sharaku_test_journal_rollforward");
+    let _ = std::fs::remove_dir_all(&temp);
+    let root = temp.join("library");
+    let source = root.join("Old").join("Work");
+    let destination = root.join("New").join("Work");
+    make_folder(&source, &[("01.jpg", b"page"), ("02.jpg", b"page two")]);
+    // The crash hit before 02.jpg was deleted; 01.jpg is already gone.
+    make_folder(
+        &destination,
+        &[("01.jpg", b"page"), ("02.jpg", b"page two")],
+    );
+    std::fs::remove_file(source.join("01.jpg")).unwrap();
+
+    let conn = setup_test_db();
+    settings::set_library_root(&conn, &root.to_string_lossy()).unwrap();
+    let work_id = insert_work(&conn, &destination);
+    let operation_id = begin(
+        &conn,
+        &folder_operation(OperationKind::Relocate, &source, &destination),
+    )
+    .unwrap();
+    mark_committed(&conn, operation_id, work_id).unwrap();
+
+    let summary = recover(&conn).unwrap();
+    assert_eq!(summary.rolled_forward, 1);
+    assert!(!source.exists());
+    assert!(!root.join("Old").exists());
+    assert!(destination.join("01.jpg").is_file());
+    assert!(destination.join("02.jpg").is_file());
+    assert_eq!(journal_len(&conn), 0);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn recover_prunes_empty_folders_up_to_the_work_root() {
+    let temp = std::env::temp_dir().join("sharaku_test_journal_other_root");
+    let _ = std::fs::remove_dir_all(&temp);
+    let library_root = temp.join("library");
+    let volume = temp.join("volume");
+    let source = volume.join("Old").join("Work");
+    let destination = volume.join("New").join("Work");
+    make_folder(&destination, &[("01.jpg", b"page")]);
+    std::fs::create_dir_all(&source).unwrap();
+    std::fs::create_dir_all(&library_root).unwrap();
+
+    let conn = setup_test_db();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    roots::create_root(&conn, "Volume", &volume.to_string_lossy()).unwrap();
+    let work_id = insert_work(&conn, &destination);
+    let operation_id = begin(
+        &conn,
+        &folder_operation(OperationKind::Relocate, &source, &destination),
+    )
+    .unwrap();
+    mark_committed(&conn, operation_id, work_id).unwrap();
+
+    recover(&conn).unwrap();
+    assert!(!volume.join("Old").exists());
+    assert!(volume.is_dir());
+    assert!(destination.join("01.jpg").is_file());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn recover_keeps_source_pages_that_were_not_fully_copied() {
+    let temp = std::env::temp_dir().join("sharaku_test_journal_partial");
+    let _ = std::fs::remove_dir_all(&temp);
+    let source = temp.join("source");
+    let destination = temp.join("destination");
+    make_folder(&source, &[("01.jpg", b"page"), ("02.jpg", b"page two")]);
+    make_folder(&destination, &[("01.jpg", b"page"), ("02.jpg", b"pa")]);
+
+    remove_copied_source(&source, &destination, "folder");
+    assert!(!source.join("01.jpg").exists());
+    assert!(source.join("02.jpg").is_file());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn recover_marks_committed_job_item_succeeded() {
+    let mut conn = setup_test_db();
+    let request = ImportRequest {
+        source_path: "/nonexistent/source".to_string(),
+        title: "Work".to_string(),
+        artist: None,
+        year: None,
+        genre: None,
+        circle: None,
+        origin: None,
+        mode: ImportMode::Copy,
+        allow_duplicates: false,
+    };
+    let job_id = import_jobs::create_job(&mut conn, &[request]).unwrap();
+    let item_id = import_jobs::pending_items(&conn, job_id).unwrap()[0].id;
+
+    let destination = Path::new("/nonexistent/library/Work");
+    let work_id = insert_work(&conn, destination);
+    let operation_id = begin(
+        &conn,
+        &FileOperation {
+            job_item_id: Some(item_id),
+            delete_source: false,
+            ..folder_operation(
+                OperationKind::Import,
+                Path::new("/nonexistent/source"),
+                destination,
+            )
+        },
+    )
+    .unwrap();
+    mark_committed(&conn, operation_id, work_id).unwrap();
+
+    recover(&conn).unwrap();
+    assert!(import_jobs::pending_items(&conn, job_id)
+        .unwrap()
+        .is_empty());
+    assert_eq!(import_jobs::get_job(&conn, job_id).unwrap().succeeded, 1);
+}
+
+#[test]
+fn recover_never_removes_a_registered_destination() {
+    let temp = std::env::temp_dir().join("sharaku_test_journal_registered");
+    let _ = std::fs::remove_dir_all(&temp);
+    let source = temp.join("source");
+    let destination = temp.join("destination");
+    make_folder(&source, &[("01.jpg", b"page")]);
+    make_folder(&destination, &[("01.jpg", b"page")]);
+
+    let conn = setup_test_db();
+    insert_work(&conn, &destination);
+    begin(
+        &conn,
+        &folder_operation(OperationKind::Import, &source, &destination),
+    )
+    .unwrap();
+
+    let summary = recover(&conn).unwrap();
+    assert_eq!(summary.rolled_back, 1);
+    assert!(destination.join("01.jpg").is_file());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn recover_renames_uncommitted_rename_back() {
+    let temp = std::env::temp_dir().join("sharaku_test_journal_rename_back");
+    let _ = std::fs::remove_dir_all(&temp);
+    let root = temp.join("library");
+    let source = root.join("Old");
+    let destination = root.join("New").join("Work");
+    // The rename happened, but the crash came before the database was updated.
+    make_folder(&destination, &[("01.jpg", b"page")]);
+
+    let conn = setup_test_db();
+    settings::set_library_root(&conn, &root.to_string_lossy()).unwrap();
+    begin(
+        &conn,
+        &FileOperation {
+            strategy: MoveStrategy::Rename,
+            ..folder_operation(OperationKind::Relocate, &source, &destination)
+        },
+    )
+    .unwrap();
+
+    let summary = recover(&conn).unwrap();
+    assert_eq!(summary.rolled_back, 1);
+    assert!(source.join("01.jpg").is_file());
+    assert!(!root.join("New").exists());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn recover_finishes_or_reverts_library_moves() {
+    let temp = std::env::temp_dir().join("sharaku_test_journal_library_move");
+    let _ = std::fs::remove_dir_all(&temp);
+    let old_root = temp.join("old");
+    let new_root = temp.join("new");
+    let conn = setup_test_db();
+
+    // Renamed, but the database was never switched over.
+    make_folder(&new_root.join("Work"), &[("01.jpg", b"page")]);
+    begin(
+        &conn,
+        &FileOperation {
+            strategy: MoveStrategy::Rename,
+            ..folder_operation(OperationKind::Library, &old_root, &new_root)
+        },
+    )
+    .unwrap();
+    let summary = recover(&conn).unwrap();
+    assert_eq!(summary.rolled_back, 1);
+    assert!(old_root.join("Work").join("01.jpg").is_file());
+    assert!(!new_root.exists());
+
+    // Copied and switched over, but the original was not removed yet.
+    make_folder(&new_root.join("Work"), &[("01.jpg", b"page")]);
+    let operation_id = begin(
+        &conn,
+        &folder_operation(OperationKind::Library, &old_root, &new_root),
+    )
+    .unwrap();
+    mark_library_committed(&conn, operation_id).unwrap();
+    let summary = recover(&conn).unwrap();
+    assert_eq!(summary.rolled_forward, 1);
+    assert!(!old_root.exists());
+    assert!(new_root.join("Work").join("01.jpg").is_file());
+    assert_eq!(journal_len(&conn), 0);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
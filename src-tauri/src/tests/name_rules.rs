@@ -0,0 +1,167 @@
+use rusqlite::Connection;
+
+use crate::db;
+
+use super::*;
+
+fn test_conn() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db_for_test(&conn).unwrap();
+    conn
+}
+
+fn rule(name: &str, pattern: &str, priority: i64) -> NameRuleInput {
+    NameRuleInput {
+        name: name.to_string(),
+        pattern: pattern.to_string(),
+        priority,
+        enabled: true,
+    }
+}
+
+#[test]
+fn builtin_reads_doujin_convention() {
+    let parsed =
+        FolderNameParser::builtin().parse("(C97) [Circle (Artist)] Title (Origin) [English]");
+    assert_eq!(parsed.rule.as_deref(), Some(BUILTIN_RULES[0].0));
+    assert!(parsed.builtin);
+    assert_eq!(
+        parsed.metadata,
+        ParsedMetadata {
+            title: "Title".to_string(),
+            artist: Some("Artist".to_string()),
+            circle: Some("Circle".to_string()),
+            year: None,
+            genre: None,
+            origin: Some("Origin".to_string()),
+            event: Some("C97".to_string()),
+            language: Some("English".to_string()),
+        }
+    );
+}
+
+#[test]
+fn builtin_doujin_optional_parts() {
+    let parsed = FolderNameParser::builtin().parse("[サークル (作者)] 作品タイトル");
+    assert_eq!(parsed.metadata.title, "作品タイトル");
+    assert_eq!(parsed.metadata.circle.as_deref(), Some("サークル"));
+    assert_eq!(parsed.metadata.artist.as_deref(), Some("作者"));
+    assert_eq!(parsed.metadata.event, None);
+    assert_eq!(parsed.metadata.origin, None);
+    assert_eq!(parsed.metadata.language, None);
+}
+
+#[test]
+fn unmatched_name_becomes_title() {
+    let parsed = FolderNameParser::builtin().parse("Just A Title");
+    assert_eq!(parsed.rule, None);
+    assert!(!parsed.builtin);
+    assert_eq!(parsed.metadata.title, "Just A Title");
+    assert_eq!(parsed.metadata.artist, None);
+}
+
+#[test]
+fn user_rules_run_in_priority_order_before_builtins() {
+    let conn = test_conn();
+    create_rule(
+        &conn,
+        &rule("year last", r"^(?P<title>.+) \((?P<year>\d{4})\)$", 10),
+    )
+    .unwrap();
+    create_rule(
+        &conn,
+        &rule("artist first", r"^(?P<artist>[^_]+)_(?P<title>.+)$", 5),
+    )
+    .unwrap();
+
+    let parser = FolderNameParser::load(&conn).unwrap();
+    let parsed = parser.parse("Artist_Title (2020)");
+    assert_eq!(parsed.rule.as_deref(), Some("artist first"));
+    assert!(!parsed.builtin);
+    assert_eq!(parsed.metadata.artist.as_deref(), Some("Artist"));
+    assert_eq!(parsed.metadata.title, "Title (2020)");
+
+    let parsed = parser.parse("Title (2020)");
+    assert_eq!(parsed.rule.as_deref(), Some("year last"));
+    assert_eq!(parsed.metadata.title, "Title");
+    assert_eq!(parsed.metadata.year, Some(2020));
+
+    // Names no user rule reads still get the built-in ones.
+    let parsed = parser.parse("[Artist] Title");
+    assert!(parsed.builtin);
+    assert_eq!(parsed.metadata.artist.as_deref(), Some("Artist"));
+}
+
+#[test]
+fn disabled_rules_are_skipped() {
+    let conn = test_conn();
+    let created = create_rule(&conn, &rule("all", r"^(?P<title>.+)$", 0)).unwrap();
+    let mut input = rule("all", r"^(?P<title>.+)$", 0);
+    input.enabled = false;
+    update_rule(&conn, created.id, &input).unwrap();
+
+    let parsed = FolderNameParser::load(&conn)
+        .unwrap()
+        .parse("[Artist] Title");
+    assert!(parsed.builtin);
+    assert_eq!(parsed.metadata.title, "Title");
+}
+
+#[test]
+fn rule_with_empty_title_does_not_match() {
+    let conn = test_conn();
+    create_rule(
+        &conn,
+        &rule("prefix", r"^(?P<artist>\w+):(?P<title>.*)$", 0),
+    )
+    .unwrap();
+
+    let parser = FolderNameParser::load(&conn).unwrap();
+    assert_eq!(parser.parse("Artist:").rule, None);
+    assert_eq!(
+        parser.parse("Artist: Title").rule.as_deref(),
+        Some("prefix")
+    );
+}
+
+#[test]
+fn invalid_patterns_are_rejected() {
+    let conn = test_conn();
+    for pattern in [
+        r"^(?P<title>.+",
+        r"^(?P<author>.+) (?P<title>.+)$",
+        r"^(?P<artist>.+)$",
+    ] {
+        assert!(matches!(
+            create_rule(&conn, &rule("bad", pattern, 0)),
+            Err(AppError::NameRuleError(_))
+        ));
+    }
+    assert!(matches!(
+        create_rule(&conn, &rule("  ", r"^(?P<title>.+)$", 0)),
+        Err(AppError::NameRuleError(_))
+    ));
+    assert!(list_rules(&conn).unwrap().is_empty());
+}
+
+#[test]
+fn update_and_delete_rules() {
+    let conn = test_conn();
+    let created = create_rule(&conn, &rule("first", r"^(?P<title>.+)$", 0)).unwrap();
+    let updated =
+        update_rule(&conn, created.id, &rule(" renamed ", r"^(?P<title>.+)$", 3)).unwrap();
+    assert_eq!(updated.name, "renamed");
+    assert_eq!(updated.priority, 3);
+    assert_eq!(list_rules(&conn).unwrap(), vec![updated]);
+
+    delete_rule(&conn, created.id).unwrap();
+    assert!(list_rules(&conn).unwrap().is_empty());
+    assert!(matches!(
+        delete_rule(&conn, created.id),
+        Err(AppError::NotFound)
+    ));
+    assert!(matches!(
+        update_rule(&conn, created.id, &rule("gone", r"^(?P<title>.+)$", 0)),
+        Err(AppError::NotFound)
+    ));
+}
@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+
+use crate::db;
+use crate::importer::{import_work, ImportMode, ImportRequest};
+use crate::settings;
+
+use super::*;
+
+/// Points the tests at the library `npm run fetch-pdfium` puts next to the
+/// manifest. Without it there is nothing to render with, so the tests that
+/// need PDFium say so and pass.
+fn pdfium_available() -> bool {
+    set_library_dir(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("pdfium"));
+    match pdfium() {
+        Ok(_) => true,
+        Err(e) => {
+            eprintln!("skipping: {}", e);
+            false
+        }
+    }
+}
+
+/// A PDF of blank 200x300 pages.
+fn sample_pdf(pages: usize) -> Vec<u8> {
+    let kids: Vec<String> = (0..pages).map(|i| format!("{} 0 R", i + 3)).collect();
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages
+        ),
+    ];
+    for _ in 0..pages {
+        objects.push("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 200 300] >>".to_string());
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).bytes());
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .bytes(),
+    );
+    pdf
+}
+
+fn temp_pdf(dir_name: &str, pages: usize) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(dir_name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let pdf_path = dir.join("book.pdf");
+    std::fs::write(&pdf_path, sample_pdf(pages)).unwrap();
+    (dir, pdf_path)
+}
+
+#[test]
+fn page_count_reads_every_page() {
+    if !pdfium_available() {
+        return;
+    }
+    let (dir, pdf_path) = temp_pdf("sharaku_test_pdf_page_count", 3);
+    assert_eq!(page_count(&pdf_path).unwrap(), 3);
+    assert!(matches!(
+        page_count(&dir.join("missing.pdf")),
+        Err(AppError::Pdf(_))
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn render_page_as_jpeg_encodes_requested_page() {
+    if !pdfium_available() {
+        return;
+    }
+    let (dir, pdf_path) = temp_pdf("sharaku_test_pdf_render", 2);
+
+    let data = render_page_as_jpeg(&pdf_path, 1).unwrap();
+    assert_eq!(&data[..2], &[0xFF, 0xD8]);
+    let img = image::load_from_memory(&data).unwrap();
+    // Within the viewing size, keeping the page's 2:3 shape.
+    assert!(img.height() <= VIEW_MAX_HEIGHT as u32);
+    assert!((img.width() as i64 * 3 - img.height() as i64 * 2).abs() <= 3);
+
+    assert!(matches!(
+        render_page_as_jpeg(&pdf_path, 2),
+        Err(AppError::NotFound)
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn import_registers_pdf_with_page_count_and_thumbnail() {
+    if !pdfium_available() {
+        return;
+    }
+    let (dir, pdf_path) = temp_pdf("sharaku_test_pdf_import", 4);
+    let library_root = dir.join("library");
+    std::fs::create_dir_all(&library_root).unwrap();
+    let app_data_dir = dir.join("app_data");
+    let conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    drop(conn);
+
+    let request = ImportRequest {
+        source_path: pdf_path.to_string_lossy().to_string(),
+        title: "Book".to_string(),
+        artist: None,
+        year: None,
+        genre: None,
+        circle: None,
+        origin: None,
+        mode: ImportMode::Copy,
+        allow_duplicates: false,
+    };
+    let result = import_work(&request, &app_data_dir).unwrap();
+    assert_eq!(result.page_count, 4);
+    assert!(result.destination_path.ends_with(".pdf"));
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    let works = db::list_works(&conn, "title", "asc").unwrap();
+    assert_eq!(works.len(), 1);
+    assert_eq!(works[0].work_type, "pdf");
+    assert_eq!(works[0].page_count, 4);
+    assert!(!db::get_thumbnail(&conn, works[0].id).unwrap().is_empty());
+    drop(conn);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,180 @@
+use rusqlite::Connection;
+
+use crate::db::{self, WorkRecord};
+
+use super::*;
+
+fn test_conn() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db_for_test(&conn).unwrap();
+    conn
+}
+
+fn insert_work(conn: &Connection, title: &str) -> i64 {
+    let path = format!("/{}.jpg", title);
+    db::insert_work(
+        conn,
+        &WorkRecord {
+            title,
+            path: &path,
+            work_type: "image",
+            page_count: 1,
+            thumbnail: b"thumb",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+    conn.last_insert_rowid()
+}
+
+fn titles(conn: &Connection, playlist_id: i64) -> Vec<String> {
+    list_playlist_works(conn, playlist_id)
+        .unwrap()
+        .into_iter()
+        .map(|w| w.title)
+        .collect()
+}
+
+fn positions(conn: &Connection, playlist_id: i64) -> Vec<i64> {
+    let mut stmt = conn
+        .prepare("SELECT position FROM playlist_items WHERE playlist_id = ?1 ORDER BY position")
+        .unwrap();
+    stmt.query_map([playlist_id], |row| row.get(0))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect()
+}
+
+#[test]
+fn create_rename_and_delete_playlist() {
+    let conn = test_conn();
+    let playlist = create_playlist(&conn, " Favorites ").unwrap();
+    assert_eq!(playlist.name, "Favorites");
+    assert_eq!(playlist.item_count, 0);
+
+    rename_playlist(&conn, playlist.id, "Best").unwrap();
+    assert_eq!(get_playlist(&conn, playlist.id).unwrap().name, "Best");
+
+    delete_playlist(&conn, playlist.id).unwrap();
+    assert!(list_playlists(&conn).unwrap().is_empty());
+    assert!(matches!(
+        delete_playlist(&conn, playlist.id),
+        Err(AppError::NotFound)
+    ));
+}
+
+#[test]
+fn create_playlist_rejects_empty_name() {
+    let conn = test_conn();
+    assert!(matches!(
+        create_playlist(&conn, "  "),
+        Err(AppError::PlaylistError(_))
+    ));
+}
+
+#[test]
+fn add_works_appends_in_order_and_skips_duplicates() {
+    let mut conn = test_conn();
+    let a = insert_work(&conn, "A");
+    let b = insert_work(&conn, "B");
+    let c = insert_work(&conn, "C");
+    let playlist = create_playlist(&conn, "List").unwrap();
+
+    add_works_to_playlist(&mut conn, playlist.id, &[b, a]).unwrap();
+    add_works_to_playlist(&mut conn, playlist.id, &[a, c]).unwrap();
+
+    assert_eq!(titles(&conn, playlist.id), vec!["B", "A", "C"]);
+    assert_eq!(positions(&conn, playlist.id), vec![0, 1, 2]);
+    assert_eq!(get_playlist(&conn, playlist.id).unwrap().item_count, 3);
+}
+
+#[test]
+fn add_works_to_missing_playlist_fails() {
+    let mut conn = test_conn();
+    let a = insert_work(&conn, "A");
+    assert!(matches!(
+        add_works_to_playlist(&mut conn, 9999, &[a]),
+        Err(AppError::NotFound)
+    ));
+}
+
+#[test]
+fn remove_works_keeps_positions_dense() {
+    let mut conn = test_conn();
+    let a = insert_work(&conn, "A");
+    let b = insert_work(&conn, "B");
+    let c = insert_work(&conn, "C");
+    let d = insert_work(&conn, "D");
+    let playlist = create_playlist(&conn, "List").unwrap();
+    add_works_to_playlist(&mut conn, playlist.id, &[a, b, c, d]).unwrap();
+
+    remove_works_from_playlist(&mut conn, playlist.id, &[a, c]).unwrap();
+
+    assert_eq!(titles(&conn, playlist.id), vec!["B", "D"]);
+    assert_eq!(positions(&conn, playlist.id), vec![0, 1]);
+}
+
+#[test]
+fn reorder_rewrites_positions() {
+    let mut conn = test_conn();
+    let a = insert_work(&conn, "A");
+    let b = insert_work(&conn, "B");
+    let c = insert_work(&conn, "C");
+    let playlist = create_playlist(&conn, "List").unwrap();
+    add_works_to_playlist(&mut conn, playlist.id, &[a, b, c]).unwrap();
+
+    reorder_playlist(&mut conn, playlist.id, &[c, a, b]).unwrap();
+
+    assert_eq!(titles(&conn, playlist.id), vec!["C", "A", "B"]);
+    assert_eq!(positions(&conn, playlist.id), vec![0, 1, 2]);
+}
+
+#[test]
+fn reorder_rejects_mismatched_items() {
+    let mut conn = test_conn();
+    let a = insert_work(&conn, "A");
+    let b = insert_work(&conn, "B");
+    let playlist = create_playlist(&conn, "List").unwrap();
+    add_works_to_playlist(&mut conn, playlist.id, &[a, b]).unwrap();
+
+    assert!(matches!(
+        reorder_playlist(&mut conn, playlist.id, &[a]),
+        Err(AppError::PlaylistError(_))
+    ));
+    assert!(matches!(
+        reorder_playlist(&mut conn, playlist.id, &[a, a]),
+        Err(AppError::PlaylistError(_))
+    ));
+    assert_eq!(titles(&conn, playlist.id), vec!["A", "B"]);
+}
+
+#[test]
+fn next_work_follows_playlist_order() {
+    let mut conn = test_conn();
+    let a = insert_work(&conn, "A");
+    let b = insert_work(&conn, "B");
+    let c = insert_work(&conn, "C");
+    let playlist = create_playlist(&conn, "List").unwrap();
+    add_works_to_playlist(&mut conn, playlist.id, &[c, a, b]).unwrap();
+
+    assert_eq!(next_work_id(&conn, playlist.id, c).unwrap(), Some(a));
+    assert_eq!(next_work_id(&conn, playlist.id, a).unwrap(), Some(b));
+    assert_eq!(next_work_id(&conn, playlist.id, b).unwrap(), None);
+}
+
+#[test]
+fn next_work_outside_playlist_is_none() {
+    let mut conn = test_conn();
+    let a = insert_work(&conn, "A");
+    let b = insert_work(&conn, "B");
+    let playlist = create_playlist(&conn, "List").unwrap();
+    add_works_to_playlist(&mut conn, playlist.id, &[a]).unwrap();
+
+    assert_eq!(next_work_id(&conn, playlist.id, b).unwrap(), None);
+}
@@ -27,6 +27,8 @@ fn insert_folder_work(conn: &Connection, title: &str, path: &str, artist: Option
             genre: None,
             circle: None,
             origin: None,
+            content_hash: None,
+            external: false,
         },
     )
     .unwrap();
@@ -35,7 +37,8 @@ fn insert_folder_work(conn: &Connection, title: &str, path: &str, artist: Option
 #[test]
 fn preview_empty_when_no_folder_works() {
     let conn = setup_test_db();
-    let previews = preview_relocation(&conn, Path::new("/library"), "{title}").unwrap();
+    let previews =
+        preview_relocation(&conn, Path::new("/library"), "folder", Some("{title}")).unwrap();
     assert!(previews.is_empty());
 }
 
@@ -43,7 +46,8 @@ fn preview_empty_when_no_folder_works() {
 fn preview_empty_when_path_unchanged() {
     let conn = setup_test_db();
     insert_folder_work(&conn, "MyWork", "/library/MyWork", None);
-    let previews = preview_relocation(&conn, Path::new("/library"), "{title}").unwrap();
+    let previews =
+        preview_relocation(&conn, Path::new("/library"), "folder", Some("{title}")).unwrap();
     assert!(previews.is_empty());
 }
 
@@ -51,7 +55,13 @@ fn preview_empty_when_path_unchanged() {
 fn preview_shows_changed_paths() {
     let conn = setup_test_db();
     insert_folder_work(&conn, "MyWork", "/library/old_location", Some("Artist"));
-    let previews = preview_relocation(&conn, Path::new("/library"), "{artist}/{title}").unwrap();
+    let previews = preview_relocation(
+        &conn,
+        Path::new("/library"),
+        "folder",
+        Some("{artist}/{title}"),
+    )
+    .unwrap();
     assert_eq!(previews.len(), 1);
     assert_eq!(previews[0].old_path, "/library/old_location");
     assert_eq!(previews[0].new_path, "/library/Artist/MyWork");
@@ -59,7 +69,7 @@ fn preview_shows_changed_paths() {
 }
 
 #[test]
-fn preview_skips_image_type_works() {
+fn preview_moves_image_works_keeping_extension() {
     let conn = setup_test_db();
     db::insert_work(
         &conn,
@@ -74,11 +84,97 @@ fn preview_skips_image_type_works() {
             genre: None,
             circle: None,
             origin: None,
+            content_hash: None,
+            external: false,
         },
     )
     .unwrap();
-    let previews = preview_relocation(&conn, Path::new("/library"), "{title}").unwrap();
-    assert!(previews.is_empty());
+    let previews = preview_relocation(
+        &conn,
+        Path::new("/library"),
+        "folder",
+        Some("{type}/{title}"),
+    )
+    .unwrap();
+    assert_eq!(previews.len(), 1);
+    assert_eq!(previews[0].work_type, "image");
+    assert_eq!(previews[0].new_path, "/library/Image/ImageWork.jpg");
+}
+
+#[test]
+fn preview_uses_type_template_over_folder_template() {
+    let conn = setup_test_db();
+    insert_folder_work(&conn, "Folder", "/library/Folder", None);
+    db::insert_work(
+        &conn,
+        &WorkRecord {
+            title: "Book",
+            path: "/library/Book.cbz",
+            work_type: "archive",
+            page_count: 1,
+            thumbnail: b"thumb",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+    settings::set_directory_template(&conn, "{title}").unwrap();
+
+    let previews = preview_relocation(
+        &conn,
+        Path::new("/library"),
+        "archive",
+        Some("Archives/{title}"),
+    )
+    .unwrap();
+    assert_eq!(previews.len(), 1);
+    assert_eq!(previews[0].new_path, "/library/Archives/Book.cbz");
+
+    // Without its own template the archive follows the folder template.
+    settings::set_type_template(&conn, "archive", Some("Archives/{title}")).unwrap();
+    let archive_id = previews[0].work_id;
+    db::update_work_path(&conn, archive_id, "/library/Archives/Book.cbz").unwrap();
+    let previews = preview_relocation(&conn, Path::new("/library"), "archive", None).unwrap();
+    assert_eq!(previews.len(), 1);
+    assert_eq!(previews[0].new_path, "/library/Book.cbz");
+}
+
+#[test]
+fn preview_skips_external_works() {
+    let conn = setup_test_db();
+    insert_folder_work(&conn, "Kept", "/archive/Kept", None);
+    insert_folder_work(&conn, "Moved", "/library/old_location", None);
+    conn.execute("UPDATE works SET external = 1 WHERE title = 'Kept'", [])
+        .unwrap();
+
+    let previews =
+        preview_relocation(&conn, Path::new("/library"), "folder", Some("{title}")).unwrap();
+    assert_eq!(previews.len(), 1);
+    assert_eq!(previews[0].title, "Moved");
+}
+
+#[test]
+fn preview_leaves_works_on_other_roots_in_place() {
+    let temp = std::env::temp_dir().join("sharaku_test_relocate_other_root");
+    let _ = std::fs::remove_dir_all(&temp);
+    let volume = temp.join("volume");
+    std::fs::create_dir_all(&volume).unwrap();
+    let conn = setup_test_db();
+    crate::roots::create_root(&conn, "Volume", &volume.to_string_lossy()).unwrap();
+    insert_folder_work(&conn, "Kept", &volume.join("Kept").to_string_lossy(), None);
+    insert_folder_work(&conn, "Moved", "/library/old_location", None);
+
+    let previews =
+        preview_relocation(&conn, Path::new("/library"), "folder", Some("{title}")).unwrap();
+    assert_eq!(previews.len(), 1);
+    assert_eq!(previews[0].title, "Moved");
+
+    std::fs::remove_dir_all(&temp).unwrap();
 }
 
 #[test]
@@ -86,7 +182,13 @@ fn preview_multiple_works_different_paths() {
     let conn = setup_test_db();
     insert_folder_work(&conn, "Work1", "/library/old1", Some("A"));
     insert_folder_work(&conn, "Work2", "/library/old2", Some("B"));
-    let previews = preview_relocation(&conn, Path::new("/library"), "{artist}/{title}").unwrap();
+    let previews = preview_relocation(
+        &conn,
+        Path::new("/library"),
+        "folder",
+        Some("{artist}/{title}"),
+    )
+    .unwrap();
     assert_eq!(previews.len(), 2);
 }
 
@@ -111,8 +213,9 @@ fn execute_moves_files_and_updates_db() {
     drop(conn);
 
     let conn = db::open_db(&app_data_dir).unwrap();
-    let works = db::list_folder_works(&conn).unwrap();
-    let plan = compute_relocation_plan(&works, &library_root, "{artist}/{title}", "Folder");
+    let works = db::list_all_works(&conn).unwrap();
+    let placement = Placement::with_template(&conn, "folder", Some("{artist}/{title}")).unwrap();
+    let plan = compute_relocation_plan(&works, &library_root, &placement);
     assert_eq!(plan.len(), 1);
     assert!(plan[0].new_path.contains("Artist"));
 
@@ -133,14 +236,256 @@ fn execute_moves_files_and_updates_db() {
     std::fs::remove_dir_all(&temp).unwrap();
 }
 
+fn relocation_strategies(app_data_dir: &Path, template: &str) -> Vec<String> {
+    use std::sync::{Arc, Mutex};
+
+    let strategies = Arc::new(Mutex::new(Vec::new()));
+    let sink = strategies.clone();
+    let channel = tauri::ipc::Channel::new(move |body| {
+        if let tauri::ipc::InvokeResponseBody::Json(json) = body {
+            let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+            if value["type"] == "moved" {
+                let strategy = value["strategy"].as_str().unwrap().to_string();
+                sink.lock().unwrap().push(strategy);
+            }
+        }
+        Ok(())
+    });
+    execute_relocation(app_data_dir, "folder", Some(template), &channel).unwrap();
+    let strategies = strategies.lock().unwrap().clone();
+    strategies
+}
+
+#[test]
+fn execute_renames_whole_folders_and_copies_partial_ones() {
+    let temp = std::env::temp_dir().join("sharaku_test_relocate_strategy");
+    let _ = std::fs::remove_dir_all(&temp);
+
+    let library_root = temp.join("library");
+    let images_only = library_root.join("images_only");
+    let with_notes = library_root.join("with_notes");
+    std::fs::create_dir_all(&images_only).unwrap();
+    std::fs::create_dir_all(&with_notes).unwrap();
+    std::fs::write(images_only.join("01.jpg"), b"image_data").unwrap();
+    std::fs::write(with_notes.join("01.jpg"), b"image_data").unwrap();
+    std::fs::write(with_notes.join("notes.txt"), b"notes").unwrap();
+
+    let app_data_dir = temp.join("app_data");
+    let conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    settings::set_directory_template(&conn, "{title}").unwrap();
+    insert_folder_work(&conn, "A", &images_only.to_string_lossy(), Some("Artist"));
+    insert_folder_work(&conn, "B", &with_notes.to_string_lossy(), Some("Artist"));
+    drop(conn);
+
+    let strategies = relocation_strategies(&app_data_dir, "{artist}/{title}");
+    assert_eq!(strategies, ["rename", "copy"]);
+
+    let moved = library_root.join("Artist");
+    assert!(moved.join("A").join("01.jpg").is_file());
+    assert!(moved.join("B").join("01.jpg").is_file());
+    assert!(!moved.join("B").join("notes.txt").exists());
+    assert!(!images_only.exists());
+    // The copy leaves behind what the carry-over setting does not take along.
+    assert!(with_notes.join("notes.txt").is_file());
+    assert!(!with_notes.join("01.jpg").exists());
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    let paths: Vec<String> = db::list_all_works(&conn)
+        .unwrap()
+        .into_iter()
+        .map(|w| w.path)
+        .collect();
+    assert!(paths.contains(&moved.join("A").to_string_lossy().to_string()));
+    assert!(paths.contains(&moved.join("B").to_string_lossy().to_string()));
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn execute_moves_single_file_works_by_type_template() {
+    let temp = std::env::temp_dir().join("sharaku_test_relocate_file");
+    let _ = std::fs::remove_dir_all(&temp);
+
+    let library_root = temp.join("library");
+    std::fs::create_dir_all(&library_root).unwrap();
+    let archive = library_root.join("Book.CBZ");
+    std::fs::write(&archive, b"archive_data").unwrap();
+
+    let app_data_dir = temp.join("app_data");
+    let conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    settings::set_directory_template(&conn, "{title}").unwrap();
+    db::insert_work(
+        &conn,
+        &WorkRecord {
+            title: "Book",
+            path: &archive.to_string_lossy(),
+            work_type: "archive",
+            page_count: 1,
+            thumbnail: b"thumb",
+            artist: Some("Artist"),
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+    drop(conn);
+
+    let channel = tauri::ipc::Channel::new(|_| Ok(()));
+    execute_relocation(&app_data_dir, "archive", Some("{artist}/{title}"), &channel).unwrap();
+
+    let moved = library_root.join("Artist").join("Book.CBZ");
+    assert_eq!(std::fs::read(&moved).unwrap(), b"archive_data");
+    assert!(!archive.exists());
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    assert_eq!(
+        db::list_all_works(&conn).unwrap()[0].path,
+        moved.to_string_lossy()
+    );
+    assert_eq!(
+        settings::get_type_template(&conn, "archive")
+            .unwrap()
+            .as_deref(),
+        Some("{artist}/{title}")
+    );
+    assert_eq!(
+        settings::get_directory_template(&conn).unwrap().as_deref(),
+        Some("{title}")
+    );
+    drop(conn);
+
+    undo_last_relocation(&app_data_dir, &channel).unwrap();
+    assert!(archive.is_file());
+    assert!(!library_root.join("Artist").exists());
+    let conn = db::open_db(&app_data_dir).unwrap();
+    assert_eq!(settings::get_type_template(&conn, "archive").unwrap(), None);
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn undo_moves_works_back_and_restores_template() {
+    let temp = std::env::temp_dir().join("sharaku_test_relocate_undo");
+    let _ = std::fs::remove_dir_all(&temp);
+
+    let library_root = temp.join("library");
+    let first = library_root.join("A");
+    let second = library_root.join("B");
+    std::fs::create_dir_all(&first).unwrap();
+    std::fs::create_dir_all(&second).unwrap();
+    std::fs::write(first.join("01.jpg"), b"image_data").unwrap();
+    std::fs::write(second.join("01.jpg"), b"image_data").unwrap();
+
+    let app_data_dir = temp.join("app_data");
+    let conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    settings::set_directory_template(&conn, "{title}").unwrap();
+    insert_folder_work(&conn, "A", &first.to_string_lossy(), Some("Artist"));
+    insert_folder_work(&conn, "B", &second.to_string_lossy(), Some("Artist"));
+    drop(conn);
+
+    relocation_strategies(&app_data_dir, "{artist}/{title}");
+    let conn = db::open_db(&app_data_dir).unwrap();
+    let last = last_relocation(&conn).unwrap().unwrap();
+    assert_eq!(last.old_template.as_deref(), Some("{title}"));
+    assert_eq!(last.new_template, "{artist}/{title}");
+    assert_eq!(last.work_count, 2);
+    drop(conn);
+
+    let channel = tauri::ipc::Channel::new(|_| Ok(()));
+    undo_last_relocation(&app_data_dir, &channel).unwrap();
+
+    assert!(first.join("01.jpg").is_file());
+    assert!(second.join("01.jpg").is_file());
+    assert!(!library_root.join("Artist").exists());
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    let mut paths: Vec<String> = db::list_all_works(&conn)
+        .unwrap()
+        .into_iter()
+        .map(|w| w.path)
+        .collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            first.to_string_lossy().to_string(),
+            second.to_string_lossy().to_string()
+        ]
+    );
+    assert_eq!(
+        settings::get_directory_template(&conn).unwrap().as_deref(),
+        Some("{title}")
+    );
+    assert!(last_relocation(&conn).unwrap().is_none());
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn undo_leaves_works_moved_since_alone() {
+    let temp = std::env::temp_dir().join("sharaku_test_relocate_undo_moved");
+    let _ = std::fs::remove_dir_all(&temp);
+
+    let library_root = temp.join("library");
+    let original = library_root.join("A");
+    std::fs::create_dir_all(&original).unwrap();
+    std::fs::write(original.join("01.jpg"), b"image_data").unwrap();
+
+    let app_data_dir = temp.join("app_data");
+    let conn = db::open_db(&app_data_dir).unwrap();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    insert_folder_work(&conn, "A", &original.to_string_lossy(), Some("Artist"));
+    drop(conn);
+
+    relocation_strategies(&app_data_dir, "{artist}/{title}");
+
+    // The work is edited into another place after the run.
+    let relocated = library_root.join("Artist").join("A");
+    let elsewhere = library_root.join("Elsewhere");
+    std::fs::rename(&relocated, &elsewhere).unwrap();
+    let conn = db::open_db(&app_data_dir).unwrap();
+    let work_id = db::list_all_works(&conn).unwrap()[0].id;
+    db::update_work_path(&conn, work_id, &elsewhere.to_string_lossy()).unwrap();
+    drop(conn);
+
+    let channel = tauri::ipc::Channel::new(|_| Ok(()));
+    undo_last_relocation(&app_data_dir, &channel).unwrap();
+
+    assert!(elsewhere.join("01.jpg").is_file());
+    assert!(!original.exists());
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    assert_eq!(
+        db::get_work(&conn, work_id).unwrap().path,
+        elsewhere.to_string_lossy()
+    );
+    // There was no template before the run, so none is left afterwards.
+    assert!(settings::get_directory_template(&conn).unwrap().is_none());
+    assert!(last_relocation(&conn).unwrap().is_none());
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
 #[test]
 fn compute_plan_handles_path_collision() {
     let conn = setup_test_db();
     insert_folder_work(&conn, "SameTitle", "/library/folder_a", Some("Artist"));
     insert_folder_work(&conn, "SameTitle", "/library/folder_b", Some("Artist"));
 
-    let works = db::list_folder_works(&conn).unwrap();
-    let plan = compute_relocation_plan(&works, Path::new("/library"), "{artist}/{title}", "Folder");
+    let works = db::list_all_works(&conn).unwrap();
+    let placement = Placement::with_template(&conn, "folder", Some("{artist}/{title}")).unwrap();
+    let plan = compute_relocation_plan(&works, Path::new("/library"), &placement);
 
     assert_eq!(plan.len(), 2);
     assert_ne!(plan[0].new_path, plan[1].new_path);
@@ -157,7 +502,7 @@ fn copy_work_files_preserves_source() {
     std::fs::write(src.join("01.jpg"), b"image_data").unwrap();
     std::fs::write(src.join("02.png"), b"image_data2").unwrap();
 
-    copy_work_files(&src, &dst).unwrap();
+    copy_work_files(&src, &dst, &ExtraFiles::None).unwrap();
 
     assert!(dst.join("01.jpg").exists());
     assert!(dst.join("02.png").exists());
@@ -167,6 +512,27 @@ fn copy_work_files_preserves_source() {
     std::fs::remove_dir_all(&temp).unwrap();
 }
 
+#[test]
+fn copy_work_files_carries_listed_extras() {
+    let temp = std::env::temp_dir().join("sharaku_test_copy_extras");
+    let _ = std::fs::remove_dir_all(&temp);
+
+    let src = temp.join("src_folder");
+    let dst = temp.join("dst_folder");
+    std::fs::create_dir_all(src.join("notes")).unwrap();
+    std::fs::write(src.join("01.jpg"), b"image_data").unwrap();
+    std::fs::write(src.join("notes").join("credits.txt"), b"credits").unwrap();
+    std::fs::write(src.join("cover.psd"), b"layers").unwrap();
+
+    copy_work_files(&src, &dst, &ExtraFiles::Listed(vec!["txt".to_string()])).unwrap();
+
+    assert!(dst.join("01.jpg").exists());
+    assert!(dst.join("notes").join("credits.txt").exists());
+    assert!(!dst.join("cover.psd").exists());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
 #[test]
 fn cleanup_empty_ancestors_removes_empty_dirs() {
     let temp = std::env::temp_dir().join("sharaku_test_cleanup_ancestors");
@@ -207,3 +573,175 @@ fn cleanup_empty_ancestors_stops_at_non_empty() {
 
     std::fs::remove_dir_all(&temp).unwrap();
 }
+
+fn no_progress() -> tauri::ipc::Channel<LibraryMoveProgress> {
+    tauri::ipc::Channel::new(|_| Ok(()))
+}
+
+fn library_with_work(temp: &Path) -> (std::path::PathBuf, Connection) {
+    let _ = std::fs::remove_dir_all(temp);
+    let library_root = temp.join("library");
+    let work_dir = library_root.join("Artist").join("Title");
+    std::fs::create_dir_all(&work_dir).unwrap();
+    std::fs::write(work_dir.join("01.jpg"), b"image_data").unwrap();
+
+    let conn = db::open_db(&temp.join("app_data")).unwrap();
+    change_library_root(&conn, &library_root, false, &no_progress()).unwrap();
+    insert_folder_work(&conn, "Title", &work_dir.to_string_lossy(), None);
+    (library_root, conn)
+}
+
+#[test]
+fn change_library_root_moves_tree_and_works() {
+    let temp = std::env::temp_dir().join("sharaku_test_relocate_change_root");
+    let (old_root, conn) = library_with_work(&temp);
+    let root = roots::root_at(&conn, &old_root.to_string_lossy())
+        .unwrap()
+        .unwrap();
+    let new_root = temp.join("moved").join("library");
+
+    change_library_root(&conn, &new_root, true, &no_progress()).unwrap();
+
+    assert!(!old_root.exists());
+    let work = &db::list_all_works(&conn).unwrap()[0];
+    let moved = new_root.join("Artist").join("Title");
+    assert!(moved.join("01.jpg").is_file());
+    assert_eq!(work.path, moved.to_string_lossy());
+    assert_eq!(work.root_id, Some(root.id));
+    assert_eq!(
+        roots::get_root(&conn, root.id).unwrap().path,
+        new_root.to_string_lossy()
+    );
+    assert_eq!(roots::list_roots(&conn).unwrap().len(), 1);
+    assert_eq!(
+        settings::get_library_root(&conn).unwrap().as_deref(),
+        Some(new_root.to_string_lossy().as_ref())
+    );
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn change_library_root_without_moving_follows_a_tree_moved_by_hand() {
+    let temp = std::env::temp_dir().join("sharaku_test_relocate_change_root_stay");
+    let (old_root, conn) = library_with_work(&temp);
+    let root = roots::root_at(&conn, &old_root.to_string_lossy())
+        .unwrap()
+        .unwrap();
+    let new_root = temp.join("fresh");
+    std::fs::rename(&old_root, &new_root).unwrap();
+
+    change_library_root(&conn, &new_root, false, &no_progress()).unwrap();
+
+    let work = &db::list_all_works(&conn).unwrap()[0];
+    assert_eq!(
+        work.path,
+        new_root.join("Artist").join("Title").to_string_lossy()
+    );
+    assert!(!work.offline);
+    assert_eq!(work.root_id, Some(root.id));
+    assert_eq!(roots::list_roots(&conn).unwrap().len(), 1);
+    assert_eq!(
+        settings::get_library_root(&conn).unwrap().as_deref(),
+        Some(new_root.to_string_lossy().as_ref())
+    );
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn change_library_root_refuses_occupied_or_nested_targets() {
+    let temp = std::env::temp_dir().join("sharaku_test_relocate_change_root_refused");
+    let (old_root, conn) = library_with_work(&temp);
+    let occupied = temp.join("occupied");
+    std::fs::create_dir_all(&occupied).unwrap();
+    std::fs::write(occupied.join("file.txt"), b"x").unwrap();
+
+    for target in [occupied.clone(), old_root.join("nested")] {
+        assert!(matches!(
+            change_library_root(&conn, &target, true, &no_progress()),
+            Err(AppError::RelocationError(_))
+        ));
+    }
+    assert!(matches!(
+        change_library_root(&conn, Path::new("relative"), true, &no_progress()),
+        Err(AppError::RelocationError(_))
+    ));
+    assert!(old_root
+        .join("Artist")
+        .join("Title")
+        .join("01.jpg")
+        .is_file());
+    assert_eq!(
+        settings::get_library_root(&conn).unwrap().as_deref(),
+        Some(old_root.to_string_lossy().as_ref())
+    );
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn undo_follows_a_moved_library_root() {
+    let temp = std::env::temp_dir().join("sharaku_test_relocate_undo_after_move");
+    let (old_root, conn) = library_with_work(&temp);
+    settings::set_directory_template(&conn, "{artist}/{title}").unwrap();
+    drop(conn);
+    let app_data_dir = temp.join("app_data");
+
+    relocation_strategies(&app_data_dir, "{title}");
+    assert!(old_root.join("Title").join("01.jpg").is_file());
+
+    let new_root = temp.join("moved");
+    let conn = db::open_db(&app_data_dir).unwrap();
+    change_library_root(&conn, &new_root, true, &no_progress()).unwrap();
+    drop(conn);
+
+    let channel = tauri::ipc::Channel::new(|_| Ok(()));
+    undo_last_relocation(&app_data_dir, &channel).unwrap();
+
+    let restored = new_root.join("Artist").join("Title");
+    assert!(restored.join("01.jpg").is_file());
+    let conn = db::open_db(&app_data_dir).unwrap();
+    assert_eq!(
+        db::list_all_works(&conn).unwrap()[0].path,
+        restored.to_string_lossy()
+    );
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn undo_still_works_after_switching_library_root() {
+    let temp = std::env::temp_dir().join("sharaku_test_relocate_undo_after_switch");
+    let (old_root, conn) = library_with_work(&temp);
+    drop(conn);
+    let app_data_dir = temp.join("app_data");
+
+    relocation_strategies(&app_data_dir, "{title}");
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    let other = temp.join("other");
+    std::fs::rename(&old_root, &other).unwrap();
+    change_library_root(&conn, &other, false, &no_progress()).unwrap();
+    drop(conn);
+
+    let channel = tauri::ipc::Channel::new(|_| Ok(()));
+    undo_last_relocation(&app_data_dir, &channel).unwrap();
+
+    let restored = other.join("Artist").join("Title");
+    assert!(restored.join("01.jpg").is_file());
+    assert!(!other.join("Title").exists());
+    let conn = db::open_db(&app_data_dir).unwrap();
+    assert_eq!(
+        db::list_all_works(&conn).unwrap()[0].path,
+        restored.to_string_lossy()
+    );
+    assert!(last_relocation(&conn).unwrap().is_none());
+    drop(conn);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
@@ -0,0 +1,245 @@
+use std::path::PathBuf;
+
+use rusqlite::Connection;
+
+use crate::db::{self, WorkRecord};
+
+use super::*;
+
+fn test_conn() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db_for_test(&conn).unwrap();
+    conn
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+fn insert(conn: &Connection, title: &str, path: &Path) -> i64 {
+    db::insert_work(
+        conn,
+        &WorkRecord {
+            title,
+            path: &path_str(path),
+            work_type: "folder",
+            page_count: 1,
+            thumbnail: b"thumb",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+    conn.last_insert_rowid()
+}
+
+fn stored_location(conn: &Connection, work_id: i64) -> (Option<i64>, String) {
+    conn.query_row(
+        "SELECT root_id, path FROM works WHERE id = ?1",
+        [work_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .unwrap()
+}
+
+#[test]
+fn works_under_a_root_are_stored_relative_to_it() {
+    let dir = temp_dir("sharaku_test_roots_relative");
+    let conn = test_conn();
+    let root = create_root(&conn, "Disk A", &path_str(&dir)).unwrap();
+    let inside = dir.join("Artist").join("Title");
+    let outside = std::env::temp_dir().join("sharaku_test_roots_elsewhere");
+
+    let inside_id = insert(&conn, "Inside", &inside);
+    let outside_id = insert(&conn, "Outside", &outside);
+
+    assert_eq!(
+        stored_location(&conn, inside_id),
+        (Some(root.id), "Artist/Title".to_string())
+    );
+    assert_eq!(
+        stored_location(&conn, outside_id),
+        (None, path_str(&outside))
+    );
+
+    let work = db::get_work(&conn, inside_id).unwrap();
+    assert_eq!(work.path, path_str(&inside));
+    assert_eq!(work.root_id, Some(root.id));
+    assert!(!work.offline);
+    assert!(db::path_exists(&conn, &path_str(&inside)).unwrap());
+    assert!(db::path_exists(&conn, &path_str(&outside)).unwrap());
+    assert_eq!(get_root(&conn, root.id).unwrap().work_count, 1);
+}
+
+#[test]
+fn same_relative_path_may_exist_under_two_roots() {
+    let a = temp_dir("sharaku_test_roots_same_a");
+    let b = temp_dir("sharaku_test_roots_same_b");
+    let conn = test_conn();
+    create_root(&conn, "A", &path_str(&a)).unwrap();
+    create_root(&conn, "B", &path_str(&b)).unwrap();
+
+    insert(&conn, "On A", &a.join("Title"));
+    insert(&conn, "On B", &b.join("Title"));
+    assert_eq!(db::list_all_works(&conn).unwrap().len(), 2);
+    assert!(db::insert_work(
+        &conn,
+        &WorkRecord {
+            title: "Again",
+            path: &path_str(&a.join("Title")),
+            work_type: "folder",
+            page_count: 1,
+            thumbnail: b"thumb",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .is_err());
+}
+
+#[test]
+fn rebasing_a_root_moves_nothing() {
+    let old = temp_dir("sharaku_test_roots_rebase_old");
+    let new = temp_dir("sharaku_test_roots_rebase_new");
+    let conn = test_conn();
+    let root = create_root(&conn, "Removable", &path_str(&old)).unwrap();
+    let work_id = insert(&conn, "Work", &old.join("Artist").join("Title"));
+
+    let rebased = rebase_root(&conn, root.id, &path_str(&new)).unwrap();
+    assert_eq!(rebased.path, path_str(&new));
+    assert_eq!(
+        stored_location(&conn, work_id),
+        (Some(root.id), "Artist/Title".to_string())
+    );
+    assert_eq!(
+        db::get_work(&conn, work_id).unwrap().path,
+        path_str(&new.join("Artist").join("Title"))
+    );
+
+    let missing = std::env::temp_dir().join("sharaku_test_roots_rebase_missing");
+    let _ = std::fs::remove_dir_all(&missing);
+    assert!(matches!(
+        rebase_root(&conn, root.id, &path_str(&missing)),
+        Err(AppError::LibraryRootError(_))
+    ));
+}
+
+#[test]
+fn works_on_an_unmounted_root_are_offline() {
+    let dir = temp_dir("sharaku_test_roots_offline");
+    let conn = test_conn();
+    let root = create_root(&conn, "Removable", &path_str(&dir)).unwrap();
+    let work_id = insert(&conn, "Work", &dir.join("Title"));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!get_root(&conn, root.id).unwrap().online);
+    let work = db::get_work(&conn, work_id).unwrap();
+    assert!(work.offline);
+    assert_eq!(work.path, path_str(&dir.join("Title")));
+}
+
+#[test]
+fn overlapping_and_invalid_roots_are_rejected() {
+    let dir = temp_dir("sharaku_test_roots_overlap");
+    std::fs::create_dir_all(dir.join("inner")).unwrap();
+    let conn = test_conn();
+    create_root(&conn, "Outer", &path_str(&dir)).unwrap();
+
+    for (name, path) in [
+        ("Inner", path_str(&dir.join("inner"))),
+        ("Parent", path_str(dir.parent().unwrap())),
+        ("Relative", "relative/path".to_string()),
+        ("  ", path_str(&std::env::temp_dir())),
+        (
+            "Outer",
+            path_str(&temp_dir("sharaku_test_roots_overlap_other")),
+        ),
+    ] {
+        assert!(
+            matches!(
+                create_root(&conn, name, &path),
+                Err(AppError::LibraryRootError(_))
+            ),
+            "{} at {} should be rejected",
+            name,
+            path
+        );
+    }
+    assert_eq!(list_roots(&conn).unwrap().len(), 1);
+}
+
+#[test]
+fn rename_and_delete_roots() {
+    let dir = temp_dir("sharaku_test_roots_rename");
+    let conn = test_conn();
+    let root = create_root(&conn, "Disk", &path_str(&dir)).unwrap();
+    let renamed = rename_root(&conn, root.id, " Archive ").unwrap();
+    assert_eq!(renamed.name, "Archive");
+
+    let work_id = insert(&conn, "Work", &dir.join("Title"));
+    assert!(matches!(
+        delete_root(&conn, root.id),
+        Err(AppError::LibraryRootError(_))
+    ));
+    db::delete_work(&conn, work_id).unwrap();
+    delete_root(&conn, root.id).unwrap();
+    assert!(list_roots(&conn).unwrap().is_empty());
+    assert!(matches!(
+        rename_root(&conn, root.id, "Gone"),
+        Err(AppError::NotFound)
+    ));
+}
+
+#[test]
+fn ensure_root_adds_the_library_root_once() {
+    let dir = temp_dir("sharaku_test_roots_ensure");
+    std::fs::create_dir_all(dir.join("nested")).unwrap();
+    let conn = test_conn();
+
+    ensure_root(&conn, &path_str(&dir)).unwrap();
+    ensure_root(&conn, &path_str(&dir.join("nested"))).unwrap();
+    let roots = list_roots(&conn).unwrap();
+    assert_eq!(roots.len(), 1);
+    assert_eq!(roots[0].name, "sharaku_test_roots_ensure");
+    assert_eq!(roots[0].path, path_str(&dir));
+
+    let missing = std::env::temp_dir().join("sharaku_test_roots_ensure_missing");
+    let _ = std::fs::remove_dir_all(&missing);
+    ensure_root(&conn, &path_str(&missing)).unwrap();
+    assert_eq!(list_roots(&conn).unwrap().len(), 1);
+}
+
+#[test]
+fn creating_a_root_adopts_works_registered_by_absolute_path() {
+    let dir = temp_dir("sharaku_test_roots_adopt");
+    let conn = test_conn();
+    let work_id = insert(&conn, "Work", &dir.join("Artist").join("Title"));
+    assert_eq!(
+        stored_location(&conn, work_id),
+        (None, path_str(&dir.join("Artist").join("Title")))
+    );
+
+    let root = create_root(&conn, "Library", &path_str(&dir)).unwrap();
+    assert_eq!(
+        stored_location(&conn, work_id),
+        (Some(root.id), "Artist/Title".to_string())
+    );
+    assert_eq!(adopt_works(&conn).unwrap(), 0);
+}
@@ -41,3 +41,25 @@ fn rejects_hidden_files_without_image_ext() {
 fn accepts_hidden_files_with_image_ext() {
     assert!(is_image_file(Path::new(".photo.jpg")));
 }
+
+#[test]
+fn recognizes_archive_extensions() {
+    assert!(is_archive_file(Path::new("book.zip")));
+    assert!(is_archive_file(Path::new("book.cbz")));
+    assert!(is_archive_file(Path::new("book.CBZ")));
+}
+
+#[test]
+fn rejects_non_archive_extensions() {
+    assert!(!is_archive_file(Path::new("photo.jpg")));
+    assert!(!is_archive_file(Path::new("book.rar")));
+    assert!(!is_archive_file(Path::new("README")));
+}
+
+#[test]
+fn recognizes_pdf_extension() {
+    assert!(is_pdf_file(Path::new("book.pdf")));
+    assert!(is_pdf_file(Path::new("book.PDF")));
+    assert!(!is_pdf_file(Path::new("book.zip")));
+    assert!(!is_pdf_file(Path::new("pdf")));
+}
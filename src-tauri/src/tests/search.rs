@@ -0,0 +1,282 @@
+use rusqlite::Connection;
+
+use crate::db::WorkRecord;
+use crate::tags;
+
+use super::*;
+
+fn test_conn() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db_for_test(&conn).unwrap();
+    conn
+}
+
+fn insert(
+    conn: &Connection,
+    title: &str,
+    artist: Option<&str>,
+    year: Option<i32>,
+    work_type: &str,
+) -> i64 {
+    let path = format!("/{}", title);
+    db::insert_work(
+        conn,
+        &WorkRecord {
+            title,
+            path: &path,
+            work_type,
+            page_count: 1,
+            thumbnail: b"thumb",
+            artist,
+            year,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+    conn.last_insert_rowid()
+}
+
+fn search_titles(conn: &Connection, query: &str) -> Vec<String> {
+    search_works(conn, query, "title", "asc")
+        .unwrap()
+        .into_iter()
+        .map(|w| w.title)
+        .collect()
+}
+
+// parse_query tests
+
+#[test]
+fn parse_mixed_query() {
+    let terms = parse_query(r#"artist:foo year:2019..2021 tag:color "exact title" word"#).unwrap();
+    assert_eq!(
+        terms,
+        vec![
+            QueryTerm {
+                negated: false,
+                kind: TermKind::Column {
+                    column: "artist",
+                    value: "foo".into()
+                }
+            },
+            QueryTerm {
+                negated: false,
+                kind: TermKind::Year {
+                    from: Some(2019),
+                    to: Some(2021)
+                }
+            },
+            QueryTerm {
+                negated: false,
+                kind: TermKind::Tag("color".into())
+            },
+            QueryTerm {
+                negated: false,
+                kind: TermKind::Text("exact title".into())
+            },
+            QueryTerm {
+                negated: false,
+                kind: TermKind::Text("word".into())
+            },
+        ]
+    );
+}
+
+#[test]
+fn parse_quoted_field_value_and_negation() {
+    let terms = parse_query(r#"-artist:"Some One" -tag:mono"#).unwrap();
+    assert_eq!(
+        terms,
+        vec![
+            QueryTerm {
+                negated: true,
+                kind: TermKind::Column {
+                    column: "artist",
+                    value: "Some One".into()
+                }
+            },
+            QueryTerm {
+                negated: true,
+                kind: TermKind::Tag("mono".into())
+            },
+        ]
+    );
+}
+
+#[test]
+fn parse_unknown_prefix_is_text() {
+    let terms = parse_query("Re:Zero").unwrap();
+    assert_eq!(terms[0].kind, TermKind::Text("Re:Zero".into()));
+}
+
+#[test]
+fn parse_open_year_ranges() {
+    let terms = parse_query("year:2019.. year:..2021 year:2020").unwrap();
+    let kinds: Vec<&TermKind> = terms.iter().map(|t| &t.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            &TermKind::Year {
+                from: Some(2019),
+                to: None
+            },
+            &TermKind::Year {
+                from: None,
+                to: Some(2021)
+            },
+            &TermKind::Year {
+                from: Some(2020),
+                to: Some(2020)
+            },
+        ]
+    );
+}
+
+#[test]
+fn parse_invalid_year_fails() {
+    assert!(matches!(
+        parse_query("year:abc"),
+        Err(AppError::SearchError(_))
+    ));
+    assert!(matches!(
+        parse_query("year:.."),
+        Err(AppError::SearchError(_))
+    ));
+}
+
+#[test]
+fn parse_empty_query() {
+    assert!(parse_query("   ").unwrap().is_empty());
+    assert!(parse_query(r#"artist: "" -"#).unwrap().is_empty());
+}
+
+#[test]
+fn compile_uses_parameters() {
+    let compiled = compile_query(&parse_query("artist:x'; DROP TABLE works; --").unwrap());
+    assert!(!compiled.where_clause().contains("DROP"));
+    assert_eq!(compiled.params[0], Value::Text("%x';%".into()));
+}
+
+// search_works tests
+
+#[test]
+fn search_free_text_uses_fts() {
+    let conn = test_conn();
+    insert(&conn, "魔法少女の物語", None, None, "folder");
+    insert(&conn, "Space Opera", Some("Someone"), None, "folder");
+
+    assert_eq!(search_titles(&conn, "少女の"), vec!["魔法少女の物語"]);
+    assert_eq!(search_titles(&conn, "opera"), vec!["Space Opera"]);
+    assert_eq!(search_titles(&conn, "someone"), vec!["Space Opera"]);
+}
+
+#[test]
+fn search_short_text_falls_back_to_like() {
+    let conn = test_conn();
+    insert(&conn, "魔法少女", None, None, "folder");
+    insert(&conn, "Other", None, None, "folder");
+
+    assert_eq!(search_titles(&conn, "魔法"), vec!["魔法少女"]);
+}
+
+#[test]
+fn search_exact_phrase() {
+    let conn = test_conn();
+    insert(&conn, "Blue Sky Days", None, None, "folder");
+    insert(&conn, "Sky Blue", None, None, "folder");
+
+    assert_eq!(search_titles(&conn, r#""blue sky""#), vec!["Blue Sky Days"]);
+}
+
+#[test]
+fn search_fields_year_and_type() {
+    let conn = test_conn();
+    insert(&conn, "A", Some("Foo"), Some(2018), "folder");
+    insert(&conn, "B", Some("Foo"), Some(2020), "pdf");
+    insert(&conn, "C", Some("Bar"), Some(2020), "folder");
+
+    assert_eq!(search_titles(&conn, "artist:foo"), vec!["A", "B"]);
+    assert_eq!(
+        search_titles(&conn, "artist:foo year:2019..2021"),
+        vec!["B"]
+    );
+    assert_eq!(search_titles(&conn, "type:folder year:2020"), vec!["C"]);
+    assert_eq!(search_titles(&conn, "-artist:foo"), vec!["C"]);
+}
+
+#[test]
+fn search_negation_keeps_null_columns() {
+    let conn = test_conn();
+    insert(&conn, "A", Some("Foo"), None, "folder");
+    insert(&conn, "B", None, None, "folder");
+
+    assert_eq!(search_titles(&conn, "-artist:foo"), vec!["B"]);
+}
+
+#[test]
+fn search_like_wildcards_are_literal() {
+    let conn = test_conn();
+    insert(&conn, "100% Orange", None, None, "folder");
+    insert(&conn, "1000 Orange", None, None, "folder");
+
+    assert_eq!(search_titles(&conn, "title:0%"), vec!["100% Orange"]);
+}
+
+#[test]
+fn search_by_tag() {
+    let conn = test_conn();
+    let a = insert(&conn, "A", None, None, "folder");
+    insert(&conn, "B", None, None, "folder");
+    let tag = tags::create_tag(&conn, "Color", Some("style")).unwrap();
+    tags::add_tag_to_work(&conn, a, tag.id).unwrap();
+
+    assert_eq!(search_titles(&conn, "tag:color"), vec!["A"]);
+    assert_eq!(search_titles(&conn, "-tag:color"), vec!["B"]);
+}
+
+#[test]
+fn search_empty_query_returns_everything() {
+    let conn = test_conn();
+    insert(&conn, "A", None, None, "folder");
+    insert(&conn, "B", None, None, "folder");
+
+    assert_eq!(search_titles(&conn, ""), vec!["A", "B"]);
+}
+
+#[test]
+fn fts_index_follows_update_and_delete() {
+    let conn = test_conn();
+    let id = insert(&conn, "Old Title", None, None, "folder");
+    assert_eq!(search_titles(&conn, "old title"), vec!["Old Title"]);
+
+    conn.execute("UPDATE works SET title = 'New Name' WHERE id = ?1", [id])
+        .unwrap();
+    assert!(search_titles(&conn, "old title").is_empty());
+    assert_eq!(search_titles(&conn, "new name"), vec!["New Name"]);
+
+    conn.execute("DELETE FROM works WHERE id = ?1", [id])
+        .unwrap();
+    assert!(search_titles(&conn, "new name").is_empty());
+}
+
+#[test]
+fn fts_index_is_rebuilt_for_existing_rows() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(include_str!(
+        "../../migrations/001_create_initial_tables.sql"
+    ))
+    .unwrap();
+    conn.execute(
+        "INSERT INTO works (title, path, type, page_count) VALUES ('Legacy Work', '/legacy', 'image', 1)",
+        [],
+    )
+    .unwrap();
+
+    db::init_db_for_test(&conn).unwrap();
+
+    assert_eq!(search_titles(&conn, "legacy"), vec!["Legacy Work"]);
+}
@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use rusqlite::Connection;
 
 use crate::db;
@@ -50,6 +52,35 @@ fn directory_template_helpers() {
     );
 }
 
+#[test]
+fn type_templates_fall_back_to_directory_template() {
+    let conn = test_conn();
+    set_directory_template(&conn, "{artist}/{title}").unwrap();
+    assert_eq!(get_type_template(&conn, "archive").unwrap(), None);
+    assert_eq!(
+        get_effective_template(&conn, "archive").unwrap(),
+        Some("{artist}/{title}".into())
+    );
+
+    set_type_template(&conn, "archive", Some("Archives/{title}")).unwrap();
+    assert_eq!(
+        get_effective_template(&conn, "archive").unwrap(),
+        Some("Archives/{title}".into())
+    );
+    assert_eq!(
+        get_effective_template(&conn, "pdf").unwrap(),
+        Some("{artist}/{title}".into())
+    );
+    assert_eq!(
+        get_type_template(&conn, "folder").unwrap(),
+        Some("{artist}/{title}".into())
+    );
+
+    set_type_template(&conn, "archive", None).unwrap();
+    assert_eq!(get_type_template(&conn, "archive").unwrap(), None);
+    assert!(set_type_template(&conn, "video", Some("{title}")).is_err());
+}
+
 #[test]
 fn type_label_defaults() {
     let conn = test_conn();
@@ -74,3 +105,59 @@ fn resolve_type_label_uses_settings() {
     assert_eq!(resolve_type_label(&conn, "image").unwrap(), "Image");
     assert_eq!(resolve_type_label(&conn, "other").unwrap(), "other");
 }
+
+#[test]
+fn import_workers_default_and_bounds() {
+    let conn = test_conn();
+    let default = get_import_workers(&conn).unwrap();
+    assert!((1..=4).contains(&default));
+
+    set_import_workers(&conn, 8).unwrap();
+    assert_eq!(get_import_workers(&conn).unwrap(), 8);
+
+    assert!(set_import_workers(&conn, 0).is_err());
+    assert!(set_import_workers(&conn, MAX_IMPORT_WORKERS + 1).is_err());
+    assert_eq!(get_import_workers(&conn).unwrap(), 8);
+}
+
+#[test]
+fn extra_files_default_and_normalization() {
+    let conn = test_conn();
+    assert_eq!(get_extra_files(&conn).unwrap(), ExtraFiles::None);
+    assert_eq!(
+        get_extra_file_extensions(&conn).unwrap(),
+        ["txt", "nfo", "json"]
+    );
+
+    set_extra_files(
+        &conn,
+        "listed",
+        &[
+            ".TXT".to_string(),
+            " nfo ".to_string(),
+            "txt".to_string(),
+            String::new(),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        get_extra_files(&conn).unwrap(),
+        ExtraFiles::Listed(vec!["txt".to_string(), "nfo".to_string()])
+    );
+
+    set_extra_files(&conn, "all", &["txt".to_string()]).unwrap();
+    assert_eq!(get_extra_files(&conn).unwrap(), ExtraFiles::All);
+    assert_eq!(get_extra_file_extensions(&conn).unwrap(), ["txt"]);
+
+    assert!(set_extra_files(&conn, "some", &[]).is_err());
+}
+
+#[test]
+fn extra_files_carries() {
+    let listed = ExtraFiles::Listed(vec!["txt".to_string()]);
+    assert!(listed.carries(Path::new("credits.TXT")));
+    assert!(listed.carries(Path::new("extras/notes.txt")));
+    assert!(!listed.carries(Path::new("extras/cover.jpg")));
+    assert!(ExtraFiles::All.carries(Path::new("extras/cover.jpg")));
+    assert!(!ExtraFiles::None.carries(Path::new("credits.txt")));
+}
@@ -0,0 +1,86 @@
+use rusqlite::Connection;
+
+use crate::db::{self, WorkRecord};
+
+use super::*;
+
+fn setup_test_db() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db_for_test(&conn).unwrap();
+    conn
+}
+
+fn insert_with_hash(conn: &Connection, title: &str, hash: Option<i64>) -> i64 {
+    db::insert_work(
+        conn,
+        &WorkRecord {
+            title,
+            path: &format!("/{}", title),
+            work_type: "folder",
+            page_count: 1,
+            thumbnail: b"not an image",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+    let id = conn.last_insert_rowid();
+    db::set_perceptual_hash(conn, id, hash).unwrap();
+    id
+}
+
+fn cluster_ids(clusters: &[SimilarCluster]) -> Vec<Vec<i64>> {
+    clusters
+        .iter()
+        .map(|c| c.works.iter().map(|w| w.id).collect())
+        .collect()
+}
+
+#[test]
+fn hamming_distance_counts_differing_bits() {
+    assert_eq!(hamming_distance(0, 0), 0);
+    assert_eq!(hamming_distance(0b1011, 0b0001), 2);
+    assert_eq!(hamming_distance(-1, 0), 64);
+}
+
+#[test]
+fn find_similar_works_groups_within_threshold() {
+    let conn = setup_test_db();
+    let a = insert_with_hash(&conn, "A", Some(0b0000));
+    let b = insert_with_hash(&conn, "B", Some(0b0011));
+    let c = insert_with_hash(&conn, "C", Some(-1));
+    let d = insert_with_hash(&conn, "D", Some(-2));
+    insert_with_hash(&conn, "Lonely", Some(0x00FF_00FF));
+
+    let clusters = find_similar_works(&conn, 2).unwrap();
+    assert_eq!(cluster_ids(&clusters), vec![vec![a, b], vec![c, d]]);
+    assert_eq!(clusters[0].max_distance, 2);
+    assert_eq!(clusters[1].max_distance, 1);
+
+    assert!(find_similar_works(&conn, 0).unwrap().is_empty());
+}
+
+#[test]
+fn find_similar_works_links_chains() {
+    let conn = setup_test_db();
+    let a = insert_with_hash(&conn, "A", Some(0b000));
+    let b = insert_with_hash(&conn, "B", Some(0b001));
+    let c = insert_with_hash(&conn, "C", Some(0b011));
+
+    let clusters = find_similar_works(&conn, 1).unwrap();
+    assert_eq!(cluster_ids(&clusters), vec![vec![a, b, c]]);
+    assert_eq!(clusters[0].max_distance, 2);
+}
+
+#[test]
+fn find_similar_works_skips_undecodable_thumbnails() {
+    let conn = setup_test_db();
+    insert_with_hash(&conn, "A", None);
+    insert_with_hash(&conn, "B", None);
+    assert!(find_similar_works(&conn, 64).unwrap().is_empty());
+}
@@ -0,0 +1,206 @@
+use rusqlite::Connection;
+
+use crate::db::{self, WorkRecord};
+
+use super::*;
+
+fn test_conn() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db_for_test(&conn).unwrap();
+    conn
+}
+
+fn insert_work(conn: &Connection, path: &str) -> i64 {
+    db::insert_work(
+        conn,
+        &WorkRecord {
+            title: "Work",
+            path,
+            work_type: "image",
+            page_count: 1,
+            thumbnail: b"thumb",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+    conn.last_insert_rowid()
+}
+
+#[test]
+fn create_and_list_tags() {
+    let conn = test_conn();
+    create_tag(&conn, "color", None).unwrap();
+    create_tag(&conn, "fantasy", Some("genre")).unwrap();
+
+    let tags = list_tags(&conn).unwrap();
+    assert_eq!(tags.len(), 2);
+    assert_eq!(tags[0].name, "fantasy");
+    assert_eq!(tags[0].category.as_deref(), Some("genre"));
+    assert_eq!(tags[1].name, "color");
+    assert_eq!(tags[1].category, None);
+}
+
+#[test]
+fn create_tag_trims_and_rejects_empty() {
+    let conn = test_conn();
+    let tag = create_tag(&conn, "  color ", Some("  ")).unwrap();
+    assert_eq!(tag.name, "color");
+    assert_eq!(tag.category, None);
+
+    assert!(matches!(
+        create_tag(&conn, "   ", None),
+        Err(AppError::TagError(_))
+    ));
+}
+
+#[test]
+fn create_duplicate_tag_without_category_fails() {
+    let conn = test_conn();
+    create_tag(&conn, "color", None).unwrap();
+    assert!(matches!(
+        create_tag(&conn, "color", None),
+        Err(AppError::TagError(_))
+    ));
+    assert!(create_tag(&conn, "color", Some("style")).is_ok());
+}
+
+#[test]
+fn update_tag_renames_and_detects_conflicts() {
+    let conn = test_conn();
+    let a = create_tag(&conn, "colour", None).unwrap();
+    create_tag(&conn, "mono", None).unwrap();
+
+    let updated = update_tag(&conn, a.id, "color", Some("style")).unwrap();
+    assert_eq!(get_tag(&conn, a.id).unwrap(), updated);
+
+    assert!(matches!(
+        update_tag(&conn, a.id, "mono", None),
+        Err(AppError::TagError(_))
+    ));
+    assert!(matches!(
+        update_tag(&conn, 9999, "x", None),
+        Err(AppError::NotFound)
+    ));
+}
+
+#[test]
+fn delete_tag_removes_work_links() {
+    let conn = test_conn();
+    let work_id = insert_work(&conn, "/a.jpg");
+    let tag = create_tag(&conn, "color", None).unwrap();
+    add_tag_to_work(&conn, work_id, tag.id).unwrap();
+
+    delete_tag(&conn, tag.id).unwrap();
+
+    assert!(list_work_tags(&conn, work_id).unwrap().is_empty());
+    assert!(matches!(delete_tag(&conn, tag.id), Err(AppError::NotFound)));
+}
+
+#[test]
+fn add_and_remove_tag_on_work() {
+    let conn = test_conn();
+    let work_id = insert_work(&conn, "/a.jpg");
+    let tag = create_tag(&conn, "color", None).unwrap();
+
+    add_tag_to_work(&conn, work_id, tag.id).unwrap();
+    add_tag_to_work(&conn, work_id, tag.id).unwrap();
+    assert_eq!(list_work_tags(&conn, work_id).unwrap(), vec![tag.clone()]);
+
+    remove_tag_from_work(&conn, work_id, tag.id).unwrap();
+    assert!(list_work_tags(&conn, work_id).unwrap().is_empty());
+}
+
+#[test]
+fn bulk_add_and_remove_tag() {
+    let mut conn = test_conn();
+    let a = insert_work(&conn, "/a.jpg");
+    let b = insert_work(&conn, "/b.jpg");
+    let tag = create_tag(&conn, "color", None).unwrap();
+
+    add_tag_to_works(&mut conn, &[a, b], tag.id).unwrap();
+    assert_eq!(list_work_tags(&conn, a).unwrap().len(), 1);
+    assert_eq!(list_work_tags(&conn, b).unwrap().len(), 1);
+
+    remove_tag_from_works(&mut conn, &[a, b], tag.id).unwrap();
+    assert!(list_work_tags(&conn, a).unwrap().is_empty());
+    assert!(list_work_tags(&conn, b).unwrap().is_empty());
+}
+
+#[test]
+fn bulk_add_rolls_back_on_unknown_work() {
+    let mut conn = test_conn();
+    let a = insert_work(&conn, "/a.jpg");
+    let tag = create_tag(&conn, "color", None).unwrap();
+
+    assert!(add_tag_to_works(&mut conn, &[a, 9999], tag.id).is_err());
+    assert!(list_work_tags(&conn, a).unwrap().is_empty());
+}
+
+#[test]
+fn merge_tags_moves_links_and_deletes_source() {
+    let mut conn = test_conn();
+    let a = insert_work(&conn, "/a.jpg");
+    let b = insert_work(&conn, "/b.jpg");
+    let source = create_tag(&conn, "colour", None).unwrap();
+    let target = create_tag(&conn, "color", None).unwrap();
+    add_tag_to_work(&conn, a, source.id).unwrap();
+    add_tag_to_work(&conn, b, source.id).unwrap();
+    add_tag_to_work(&conn, b, target.id).unwrap();
+
+    merge_tags(&mut conn, source.id, target.id).unwrap();
+
+    assert!(matches!(get_tag(&conn, source.id), Err(AppError::NotFound)));
+    assert_eq!(list_work_tags(&conn, a).unwrap(), vec![target.clone()]);
+    assert_eq!(list_work_tags(&conn, b).unwrap(), vec![target]);
+}
+
+#[test]
+fn merge_tag_into_itself_fails() {
+    let mut conn = test_conn();
+    let tag = create_tag(&conn, "color", None).unwrap();
+    assert!(matches!(
+        merge_tags(&mut conn, tag.id, tag.id),
+        Err(AppError::TagError(_))
+    ));
+}
+
+#[test]
+fn work_detail_groups_tags_by_category() {
+    let conn = test_conn();
+    let work_id = insert_work(&conn, "/a.jpg");
+    for (name, category) in [
+        ("color", None),
+        ("fantasy", Some("genre")),
+        ("alice", Some("character")),
+        ("action", Some("genre")),
+    ] {
+        let tag = create_tag(&conn, name, category).unwrap();
+        add_tag_to_work(&conn, work_id, tag.id).unwrap();
+    }
+
+    let detail = db::get_work(&conn, work_id).unwrap();
+    let summary: Vec<(Option<&str>, Vec<&str>)> = detail
+        .tags
+        .iter()
+        .map(|g| {
+            (
+                g.category.as_deref(),
+                g.tags.iter().map(|t| t.name.as_str()).collect(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (Some("character"), vec!["alice"]),
+            (Some("genre"), vec!["action", "fantasy"]),
+            (None, vec!["color"]),
+        ]
+    );
+}
@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use super::*;
 
@@ -203,25 +203,28 @@ fn resolve_path_nested() {
     assert_eq!(path, Path::new("/library/Artist A/2024/My Title"));
 }
 
-// resolve_unique_work_path tests
+// claim_unique_work_path tests
 
 #[test]
-fn resolve_unique_nonexistent_returns_base() {
+fn claim_unique_nonexistent_returns_base() {
     let dir = std::env::temp_dir().join("sharaku_test_unique_nonexist");
     let _ = std::fs::remove_dir_all(&dir);
 
-    let path = resolve_unique_work_path(&dir, "{title}", &full_metadata());
+    let path = claim_unique_work_path(&dir, "{title}", &full_metadata()).unwrap();
     assert_eq!(path, dir.join("My Title"));
+    assert!(path.is_dir());
+
+    std::fs::remove_dir_all(&dir).unwrap();
 }
 
 #[test]
-fn resolve_unique_existing_gets_suffix() {
+fn claim_unique_existing_gets_suffix() {
     let dir = std::env::temp_dir().join("sharaku_test_unique_exist");
     let _ = std::fs::remove_dir_all(&dir);
     let target = dir.join("My Title");
     std::fs::create_dir_all(&target).unwrap();
 
-    let path = resolve_unique_work_path(&dir, "{title}", &full_metadata());
+    let path = claim_unique_work_path(&dir, "{title}", &full_metadata()).unwrap();
     assert_ne!(path, target);
     assert!(path
         .file_name()
@@ -233,7 +236,7 @@ fn resolve_unique_existing_gets_suffix() {
 }
 
 #[test]
-fn resolve_unique_skips_existing_suffixes() {
+fn claim_unique_skips_existing_suffixes() {
     let dir = std::env::temp_dir().join("sharaku_test_unique_skip");
     let _ = std::fs::remove_dir_all(&dir);
 
@@ -242,12 +245,32 @@ fn resolve_unique_skips_existing_suffixes() {
     std::fs::create_dir_all(&base).unwrap();
     std::fs::create_dir_all(&first_suffix).unwrap();
 
-    let path = resolve_unique_work_path(&dir, "{title}", &full_metadata());
+    let path = claim_unique_work_path(&dir, "{title}", &full_metadata()).unwrap();
     assert_eq!(path.file_name().unwrap().to_string_lossy(), "My Title_0002");
 
     std::fs::remove_dir_all(&dir).unwrap();
 }
 
+#[test]
+fn claim_unique_concurrent_claims_get_distinct_paths() {
+    let dir = std::env::temp_dir().join("sharaku_test_unique_concurrent");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut paths: Vec<PathBuf> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                scope.spawn(|| claim_unique_work_path(&dir, "{title}", &full_metadata()).unwrap())
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    paths.sort();
+    paths.dedup();
+    assert_eq!(paths.len(), 8);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
 #[test]
 fn render_type_placeholder_with_value() {
     let mut meta = full_metadata();
@@ -267,3 +290,190 @@ fn validate_type_placeholder() {
     assert!(validate_template("{type}/{title}").is_ok());
     assert!(validate_template("{artist}/{type}/{title}").is_ok());
 }
+
+// placeholder option tests
+
+#[test]
+fn render_default_value() {
+    let result = render_template("{artist|Anonymous}/{title}", &partial_metadata());
+    assert_eq!(result, "Anonymous/My Title");
+    let result = render_template("{artist|Anonymous}/{title}", &full_metadata());
+    assert_eq!(result, "Artist A/My Title");
+}
+
+#[test]
+fn render_drops_segment_of_missing_optional_field() {
+    let template = "{artist}/[{circle?}]/{title} {origin?}";
+    assert_eq!(
+        render_template(template, &partial_metadata()),
+        "Unknown/My Title"
+    );
+    assert_eq!(
+        render_template(template, &full_metadata()),
+        "Artist A/[Circle X]/My Title Original"
+    );
+}
+
+#[test]
+fn render_case_width_and_truncation() {
+    let mut meta = full_metadata();
+    meta.year = Some(99);
+    assert_eq!(
+        render_template("{year:04}/{title:upper}", &meta),
+        "0099/MY TITLE"
+    );
+    assert_eq!(
+        render_template("{artist:lower}/{title:.4}", &meta),
+        "artist a/My T"
+    );
+    // Trailing whitespace left by truncation is trimmed.
+    assert_eq!(render_template("{title:.3}", &meta), "My");
+    assert_eq!(render_template("{title:upper:.5}", &meta), "MY TI");
+}
+
+#[test]
+fn render_initial_bucket() {
+    let mut meta = full_metadata();
+    assert_eq!(
+        render_template("{artist:initial}/{title}", &meta),
+        "A/Artist A/My Title"
+    );
+    meta.artist = Some("9lives".to_string());
+    assert_eq!(
+        render_template("{artist:initial}/{title}", &meta),
+        "0-9/9lives/My Title"
+    );
+    meta.artist = Some("あーてぃすと".to_string());
+    assert_eq!(
+        render_template("{artist:initial}/{title}", &meta),
+        "あ/あーてぃすと/My Title"
+    );
+    meta.artist = None;
+    assert_eq!(
+        render_template("{artist:initial}/{title}", &meta),
+        "U/Unknown/My Title"
+    );
+}
+
+#[test]
+fn validate_placeholder_options() {
+    assert!(validate_template("{artist|Anonymous}/{circle?}/{year:04}/{title:upper:.40}").is_ok());
+    assert!(validate_template("{artist:initial}/{title}").is_ok());
+}
+
+#[test]
+fn validate_reports_error_columns() {
+    let message = |template: &str| validate_template(template).unwrap_err().to_string();
+
+    assert!(message("{artist}/{titel}").contains("11 文字目"));
+    assert!(message("{artist}/{titel}").contains("未知のプレースホルダー"));
+    assert!(message("{title:shout}").contains("8 文字目"));
+    assert!(message("{title:shout}").contains("未知の書式: shout"));
+    assert!(message("{title:4}").contains("未知の書式"));
+    assert!(message("{circle?|x}/{title}").contains("9 文字目"));
+    assert!(message("{artist|}/{title}").contains("既定値が空です"));
+    assert!(message("{artist|a/b}/{title}").contains("閉じられていない"));
+    assert!(message("作品/{title").contains("4 文字目"));
+    assert!(message("{title}}").contains("8 文字目"));
+    assert!(message("x{artist:initial}/{title}").contains("2 文字目"));
+}
+
+// claim_unique_file_path tests
+
+#[test]
+fn claim_unique_file_appends_extension() {
+    let dir = std::env::temp_dir().join("sharaku_test_unique_file_nonexist");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let path = claim_unique_file_path(&dir, "{artist}/{title}", &full_metadata(), "cbz").unwrap();
+    assert_eq!(path, dir.join("Artist A").join("My Title.cbz"));
+    assert!(path.is_file());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn claim_unique_file_existing_gets_suffix() {
+    let dir = std::env::temp_dir().join("sharaku_test_unique_file_exist");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("My Title.zip"), b"zip").unwrap();
+
+    let path = claim_unique_file_path(&dir, "{title}", &full_metadata(), "zip").unwrap();
+    assert_eq!(
+        path.file_name().unwrap().to_string_lossy(),
+        "My Title_0001.zip"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// parse_work_path tests
+
+#[test]
+fn parse_work_path_inverts_render() {
+    let root = Path::new("/library");
+    let template = "{artist}/{year}/[{circle}] {title} ({origin})";
+    let mut meta = full_metadata();
+    meta.genre = None;
+    let path = resolve_work_path(root, template, &meta);
+
+    assert_eq!(parse_work_path(root, template, &path), Some(meta));
+}
+
+#[test]
+fn parse_work_path_reads_defaults_as_missing() {
+    let root = Path::new("/library");
+    let path = root.join("Unknown").join("Anonymous").join("My Title");
+
+    let parsed = parse_work_path(root, "{year}/{artist|Anonymous}/{title}", &path).unwrap();
+    assert_eq!(parsed, partial_metadata());
+}
+
+#[test]
+fn parse_work_path_skips_dropped_optional_segments() {
+    let root = Path::new("/library");
+    let template = "{artist}/{circle?}/{title}";
+
+    let parsed = parse_work_path(root, template, &root.join("Artist A/My Title")).unwrap();
+    assert_eq!(parsed.artist.as_deref(), Some("Artist A"));
+    assert_eq!(parsed.circle, None);
+    assert_eq!(parsed.title, "My Title");
+
+    let parsed = parse_work_path(root, template, &root.join("Artist A/Circle X/My Title")).unwrap();
+    assert_eq!(parsed.circle.as_deref(), Some("Circle X"));
+}
+
+#[test]
+fn parse_work_path_initial_bucket_and_suffix() {
+    let root = Path::new("/library");
+    let template = "{artist:initial}/{title}";
+
+    let parsed = parse_work_path(root, template, &root.join("A/Artist A/My Title_0002")).unwrap();
+    assert_eq!(parsed.artist.as_deref(), Some("Artist A"));
+    assert_eq!(parsed.title, "My Title");
+
+    // The bucket has to be the one the value would be filed under.
+    assert_eq!(
+        parse_work_path(root, template, &root.join("B/Artist A/My Title")),
+        None
+    );
+}
+
+#[test]
+fn parse_work_path_rejects_paths_that_do_not_fit() {
+    let root = Path::new("/library");
+
+    assert_eq!(
+        parse_work_path(root, "{artist}/{title}", &root.join("My Title")),
+        None
+    );
+    assert_eq!(
+        parse_work_path(root, "{year}/{title}", &root.join("Recent/My Title")),
+        None
+    );
+    assert_eq!(
+        parse_work_path(root, "{title}", Path::new("/elsewhere/My Title")),
+        None
+    );
+}
@@ -0,0 +1,32 @@
+use image::{DynamicImage, GrayImage, Luma};
+
+use super::*;
+
+fn gradient(width: u32, height: u32, reversed: bool) -> DynamicImage {
+    DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, _| {
+        let v = (x * 255 / (width - 1)) as u8;
+        Luma([if reversed { 255 - v } else { v }])
+    }))
+}
+
+#[test]
+fn dhash_is_stable_across_resolutions() {
+    let small = dhash(&gradient(90, 120, false));
+    let large = dhash(&gradient(900, 1200, false));
+    assert_eq!(small, large);
+}
+
+#[test]
+fn dhash_differs_for_different_images() {
+    let left_to_right = dhash(&gradient(90, 120, false));
+    let right_to_left = dhash(&gradient(90, 120, true));
+    assert!((left_to_right ^ right_to_left).count_ones() > 32);
+}
+
+#[test]
+fn perceptual_hash_reads_generated_thumbnail() {
+    let thumb = generate_thumbnail_from_image(&gradient(400, 560, true)).unwrap();
+    let hash = perceptual_hash(&thumb).unwrap();
+    let expected = dhash(&gradient(400, 560, true)) as i64;
+    assert!((hash ^ expected).count_ones() <= 4);
+}
@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use super::*;
+
+#[test]
+fn same_filesystem_within_temp_dir() {
+    let temp = std::env::temp_dir().join("sharaku_test_transfer_same_fs");
+    let _ = std::fs::remove_dir_all(&temp);
+    std::fs::create_dir_all(temp.join("a")).unwrap();
+
+    // The second path does not exist yet; its nearest existing ancestor counts.
+    assert!(same_filesystem(&temp.join("a"), &temp.join("b").join("c")));
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn carries_whole_folder_counts_every_file() {
+    let temp = std::env::temp_dir().join("sharaku_test_transfer_whole");
+    let _ = std::fs::remove_dir_all(&temp);
+    std::fs::create_dir_all(temp.join("sub")).unwrap();
+    std::fs::write(temp.join("01.jpg"), b"page").unwrap();
+    std::fs::write(temp.join("sub").join("notes.txt"), b"notes").unwrap();
+
+    let images_only = vec![PathBuf::from("01.jpg")];
+    assert!(!carries_whole_folder(&temp, &images_only));
+    let everything = vec![PathBuf::from("01.jpg"), PathBuf::from("sub/notes.txt")];
+    assert!(carries_whole_folder(&temp, &everything));
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn rename_onto_replaces_empty_placeholder_dir() {
+    let temp = std::env::temp_dir().join("sharaku_test_transfer_rename");
+    let _ = std::fs::remove_dir_all(&temp);
+    let source = temp.join("source");
+    let target = temp.join("target");
+    std::fs::create_dir_all(&source).unwrap();
+    std::fs::create_dir_all(&target).unwrap();
+    std::fs::write(source.join("01.jpg"), b"page").unwrap();
+
+    rename_onto(&source, &target).unwrap();
+    assert!(!source.exists());
+    assert!(target.join("01.jpg").is_file());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn copy_verified_copies_identical_content() {
+    let temp = std::env::temp_dir().join("sharaku_test_transfer_copy");
+    let _ = std::fs::remove_dir_all(&temp);
+    std::fs::create_dir_all(&temp).unwrap();
+    std::fs::write(temp.join("a.jpg"), b"page data").unwrap();
+
+    copy_verified(&temp.join("a.jpg"), &temp.join("b.jpg")).unwrap();
+    assert_eq!(std::fs::read(temp.join("b.jpg")).unwrap(), b"page data");
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
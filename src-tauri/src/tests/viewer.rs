@@ -69,3 +69,43 @@ fn content_type_unknown() {
         "application/octet-stream"
     );
 }
+
+#[test]
+fn load_image_reports_works_on_unmounted_roots_as_unavailable() {
+    let temp = std::env::temp_dir().join("sharaku_test_viewer_offline");
+    let _ = std::fs::remove_dir_all(&temp);
+    let app_data_dir = temp.join("app_data");
+    let volume = temp.join("volume");
+    let work_dir = volume.join("Work");
+    std::fs::create_dir_all(&work_dir).unwrap();
+    std::fs::write(work_dir.join("01.jpg"), b"page").unwrap();
+
+    let conn = db::open_db(&app_data_dir).unwrap();
+    crate::roots::create_root(&conn, "Volume", &volume.to_string_lossy()).unwrap();
+    db::insert_work(
+        &conn,
+        &db::WorkRecord {
+            title: "Work",
+            path: &work_dir.to_string_lossy(),
+            work_type: "folder",
+            page_count: 1,
+            thumbnail: b"thumb",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+    let work_id = conn.last_insert_rowid();
+    drop(conn);
+
+    assert_eq!(load_image(&app_data_dir, work_id, 0).unwrap().0, b"page");
+    std::fs::remove_dir_all(&volume).unwrap();
+    assert_eq!(load_image(&app_data_dir, work_id, 0).unwrap_err(), 503);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
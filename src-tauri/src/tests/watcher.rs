@@ -0,0 +1,315 @@
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use crate::db::{self, WorkRecord};
+
+use super::*;
+
+fn png_bytes(shade: u8) -> Vec<u8> {
+    let img = image::RgbImage::from_pixel(4, 4, image::Rgb([shade, 100, 50]));
+    let mut buf = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut buf, image::ImageFormat::Png).unwrap();
+    buf.into_inner()
+}
+
+fn setup(temp: &Path, pages: &[&str]) -> (Connection, PathBuf, i64) {
+    let _ = std::fs::remove_dir_all(temp);
+    let folder = temp.join("Work");
+    std::fs::create_dir_all(&folder).unwrap();
+    for name in pages {
+        std::fs::write(folder.join(name), png_bytes(10)).unwrap();
+    }
+
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db_for_test(&conn).unwrap();
+    db::insert_work(
+        &conn,
+        &WorkRecord {
+            title: "Work",
+            path: &folder.to_string_lossy(),
+            work_type: "folder",
+            page_count: pages.len() as i32,
+            thumbnail: b"original",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+    let id = conn.last_insert_rowid();
+    (conn, folder, id)
+}
+
+#[test]
+fn added_last_page_updates_count_but_keeps_thumbnail() {
+    let temp = std::env::temp_dir().join("sharaku_test_watcher_append");
+    let (conn, folder, id) = setup(&temp, &["01.png", "02.png"]);
+
+    let added = folder.join("03.png");
+    std::fs::write(&added, png_bytes(20)).unwrap();
+    let changes = apply_changes(&conn, &[added], &mut HashMap::new()).unwrap();
+
+    assert_eq!(
+        changes,
+        vec![WorkChange::Updated {
+            work_id: id,
+            page_count: 3,
+            thumbnail_updated: false,
+        }]
+    );
+    assert_eq!(db::get_work(&conn, id).unwrap().page_count, 3);
+    assert_eq!(db::get_thumbnail(&conn, id).unwrap(), b"original");
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn removed_first_page_regenerates_thumbnail() {
+    let temp = std::env::temp_dir().join("sharaku_test_watcher_first_page");
+    let (conn, folder, id) = setup(&temp, &["01.png", "02.png"]);
+
+    let removed = folder.join("01.png");
+    std::fs::remove_file(&removed).unwrap();
+    let changes = apply_changes(&conn, &[removed], &mut HashMap::new()).unwrap();
+
+    assert_eq!(
+        changes,
+        vec![WorkChange::Updated {
+            work_id: id,
+            page_count: 1,
+            thumbnail_updated: true,
+        }]
+    );
+    assert_ne!(db::get_thumbnail(&conn, id).unwrap(), b"original");
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn deleted_folder_is_reported_missing() {
+    let temp = std::env::temp_dir().join("sharaku_test_watcher_missing");
+    let (conn, folder, id) = setup(&temp, &["01.png"]);
+
+    std::fs::remove_dir_all(&folder).unwrap();
+    let changes = apply_changes(&conn, std::slice::from_ref(&folder), &mut HashMap::new()).unwrap();
+    assert_eq!(changes, vec![WorkChange::Missing { work_id: id }]);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn unrelated_paths_are_ignored() {
+    let temp = std::env::temp_dir().join("sharaku_test_watcher_unrelated");
+    let (conn, folder, _) = setup(&temp, &["01.png"]);
+
+    let nested = folder.join("sub").join("01.png");
+    let elsewhere = temp.join("other.png");
+    let changes = apply_changes(&conn, &[nested, elsewhere], &mut HashMap::new()).unwrap();
+    assert!(changes.is_empty());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn pages_are_rehashed_only_when_they_change() {
+    let temp = std::env::temp_dir().join("sharaku_test_watcher_rehash");
+    let (conn, folder, id) = setup(&temp, &["01.png", "02.png"]);
+    let page = folder.join("02.png");
+    let content_hash = || -> Option<String> {
+        conn.query_row(
+            "SELECT content_hash FROM works WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    };
+    let mut seen_mtimes = HashMap::new();
+
+    apply_changes(&conn, std::slice::from_ref(&page), &mut seen_mtimes).unwrap();
+    assert!(content_hash().is_some());
+
+    conn.execute("UPDATE works SET content_hash = 'stale'", [])
+        .unwrap();
+    apply_changes(&conn, std::slice::from_ref(&page), &mut seen_mtimes).unwrap();
+    assert_eq!(content_hash().as_deref(), Some("stale"));
+
+    std::fs::write(&page, png_bytes(30)).unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(&page)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(60))
+        .unwrap();
+    let changes = apply_changes(&conn, std::slice::from_ref(&page), &mut seen_mtimes).unwrap();
+    assert!(changes.is_empty());
+    assert_ne!(content_hash().as_deref(), Some("stale"));
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn first_page_changed_compares_natural_order() {
+    let first = Path::new("/w/2.png");
+    assert!(first_page_changed(first, &[Path::new("/w/2.png")]));
+    assert!(first_page_changed(first, &[Path::new("/w/1.png")]));
+    assert!(!first_page_changed(first, &[Path::new("/w/10.png")]));
+    assert!(!first_page_changed(first, &[Path::new("/w/1.txt")]));
+}
+
+#[test]
+fn watch_targets_cover_roots_recursively_and_outside_works_alone() {
+    let temp = std::env::temp_dir().join("sharaku_test_watcher_targets");
+    let (conn, outside, _) = setup(&temp, &["01.png"]);
+    let mounted = temp.join("mounted");
+    let unmounted = temp.join("unmounted");
+    std::fs::create_dir_all(&mounted).unwrap();
+    std::fs::create_dir_all(&unmounted).unwrap();
+    roots::create_root(&conn, "Mounted", &mounted.to_string_lossy()).unwrap();
+    roots::create_root(&conn, "Unmounted", &unmounted.to_string_lossy()).unwrap();
+    std::fs::remove_dir_all(&unmounted).unwrap();
+    let inside = mounted.join("Inside");
+    std::fs::create_dir_all(&inside).unwrap();
+    db::insert_work(
+        &conn,
+        &WorkRecord {
+            title: "Inside",
+            path: &inside.to_string_lossy(),
+            work_type: "folder",
+            page_count: 0,
+            thumbnail: b"thumb",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+
+    let targets = watch_targets(&conn).unwrap();
+    assert_eq!(
+        targets.into_iter().collect::<Vec<_>>(),
+        vec![
+            (outside, RecursiveMode::NonRecursive),
+            (mounted, RecursiveMode::Recursive),
+            (unmounted, RecursiveMode::Recursive),
+        ]
+    );
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+fn insert_folder_work(conn: &Connection, path: &Path) {
+    std::fs::create_dir_all(path).unwrap();
+    db::insert_work(
+        conn,
+        &WorkRecord {
+            title: "Added",
+            path: &path.to_string_lossy(),
+            work_type: "folder",
+            page_count: 0,
+            thumbnail: b"thumb",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn only_works_outside_the_watched_targets_make_them_stale() {
+    let temp = std::env::temp_dir().join("sharaku_test_watcher_stale");
+    let (conn, _, _) = setup(&temp, &["01.png"]);
+    let mounted = temp.join("mounted");
+    std::fs::create_dir_all(&mounted).unwrap();
+    roots::create_root(&conn, "Mounted", &mounted.to_string_lossy()).unwrap();
+    let watched = watch_targets(&conn).unwrap();
+
+    insert_folder_work(&conn, &mounted.join("Inside"));
+    assert!(!has_new_targets(&watched, &watch_targets(&conn).unwrap()));
+
+    insert_folder_work(&conn, &temp.join("Loose"));
+    assert!(has_new_targets(&watched, &watch_targets(&conn).unwrap()));
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[derive(Default)]
+struct RecordingWatcher {
+    watched: Vec<PathBuf>,
+    refuse: Option<PathBuf>,
+}
+
+impl Watcher for RecordingWatcher {
+    fn new<F: notify_debouncer_mini::notify::EventHandler>(
+        _: F,
+        _: notify_debouncer_mini::notify::Config,
+    ) -> notify_debouncer_mini::notify::Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn watch(
+        &mut self,
+        path: &Path,
+        _: RecursiveMode,
+    ) -> notify_debouncer_mini::notify::Result<()> {
+        if self.refuse.as_deref() == Some(path) {
+            return Err(notify_debouncer_mini::notify::Error::generic("refused"));
+        }
+        self.watched.push(path.to_path_buf());
+        Ok(())
+    }
+
+    fn unwatch(&mut self, path: &Path) -> notify_debouncer_mini::notify::Result<()> {
+        self.watched.retain(|watched| watched != path);
+        Ok(())
+    }
+
+    fn kind() -> notify_debouncer_mini::notify::WatcherKind {
+        notify_debouncer_mini::notify::WatcherKind::NullWatcher
+    }
+}
+
+#[test]
+fn arm_skips_failures_and_rewatches_returning_roots() {
+    let temp = std::env::temp_dir().join("sharaku_test_watcher_arm");
+    let _ = std::fs::remove_dir_all(&temp);
+    let [volume, refused, later] = ["volume", "refused", "later"].map(|name| temp.join(name));
+    std::fs::create_dir_all(&volume).unwrap();
+    std::fs::create_dir_all(&refused).unwrap();
+    let targets: BTreeMap<PathBuf, RecursiveMode> = [&volume, &refused, &later]
+        .into_iter()
+        .map(|path| (path.clone(), RecursiveMode::Recursive))
+        .collect();
+    let mut watcher = RecordingWatcher {
+        refuse: Some(refused.clone()),
+        ..Default::default()
+    };
+    let mut armed = HashSet::new();
+
+    arm(&mut watcher, &targets, &mut armed);
+    assert_eq!(watcher.watched, vec![volume.clone()]);
+
+    // Unmounted, then mounted again; a new folder turns up meanwhile.
+    std::fs::remove_dir_all(&volume).unwrap();
+    arm(&mut watcher, &targets, &mut armed);
+    assert!(watcher.watched.is_empty());
+    std::fs::create_dir_all(&volume).unwrap();
+    std::fs::create_dir_all(&later).unwrap();
+    arm(&mut watcher, &targets, &mut armed);
+    assert_eq!(watcher.watched, vec![later.clone(), volume.clone()]);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
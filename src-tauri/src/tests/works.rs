@@ -0,0 +1,268 @@
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use crate::db::{self, WorkEdit, WorkRecord};
+use crate::settings;
+
+use super::*;
+
+fn setup_test_db() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    db::init_db_for_test(&conn).unwrap();
+    conn
+}
+
+fn insert_work(conn: &Connection, title: &str, path: &str, work_type: &str) -> i64 {
+    db::insert_work(
+        conn,
+        &WorkRecord {
+            title,
+            path,
+            work_type,
+            page_count: 2,
+            thumbnail: b"thumb",
+            artist: None,
+            year: None,
+            genre: None,
+            circle: None,
+            origin: None,
+            content_hash: None,
+            external: false,
+        },
+    )
+    .unwrap();
+    conn.last_insert_rowid()
+}
+
+fn edit(title: &str, artist: Option<&str>) -> WorkEdit {
+    WorkEdit {
+        title: title.to_string(),
+        artist: artist.map(str::to_string),
+        year: Some(2021),
+        genre: Some("  ".to_string()),
+        circle: None,
+        origin: None,
+    }
+}
+
+fn make_folder(dir: &Path) {
+    std::fs::create_dir_all(dir).unwrap();
+    std::fs::write(dir.join("01.jpg"), b"page one").unwrap();
+    std::fs::write(dir.join("02.jpg"), b"page two").unwrap();
+}
+
+#[test]
+fn update_work_saves_metadata_and_bumps_updated_at() {
+    let mut conn = setup_test_db();
+    let id = insert_work(&conn, "Old", "/nowhere/old.jpg", "image");
+    conn.execute(
+        "UPDATE works SET updated_at = '2000-01-01T00:00:00.000Z' WHERE id = ?1",
+        [id],
+    )
+    .unwrap();
+
+    let work = update_work(&mut conn, id, &edit("  New  ", Some("Artist"))).unwrap();
+    assert_eq!(work.title, "New");
+    assert_eq!(work.artist.as_deref(), Some("Artist"));
+    assert_eq!(work.year, Some(2021));
+    assert_eq!(work.genre, None);
+    assert_eq!(work.path, "/nowhere/old.jpg");
+    assert!(work.updated_at.as_str() > "2000-01-01T00:00:00.000Z");
+}
+
+#[test]
+fn update_work_rejects_empty_title() {
+    let mut conn = setup_test_db();
+    let id = insert_work(&conn, "Old", "/old.jpg", "image");
+    let result = update_work(&mut conn, id, &edit("   ", None));
+    assert!(matches!(result, Err(AppError::WorkError(_))));
+    assert_eq!(db::get_work(&conn, id).unwrap().title, "Old");
+}
+
+#[test]
+fn update_work_not_found() {
+    let mut conn = setup_test_db();
+    let result = update_work(&mut conn, 999, &edit("Title", None));
+    assert!(matches!(result, Err(AppError::NotFound)));
+}
+
+#[test]
+fn update_work_relocates_folder_to_template_path() {
+    let temp = std::env::temp_dir().join("sharaku_test_update_work_relocate");
+    let _ = std::fs::remove_dir_all(&temp);
+    let library_root = temp.join("library");
+    let old_path = library_root.join("Unknown").join("Old");
+    make_folder(&old_path);
+
+    let mut conn = setup_test_db();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    settings::set_directory_template(&conn, "{artist}/{title}").unwrap();
+    let id = insert_work(&conn, "Old", &old_path.to_string_lossy(), "folder");
+
+    let work = update_work(&mut conn, id, &edit("New", Some("Artist"))).unwrap();
+
+    let new_path = library_root.join("Artist").join("New");
+    assert_eq!(work.path, new_path.to_string_lossy());
+    assert_eq!(std::fs::read(new_path.join("01.jpg")).unwrap(), b"page one");
+    assert!(new_path.join("02.jpg").is_file());
+    assert!(!old_path.exists());
+    assert!(!library_root.join("Unknown").exists());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn update_work_keeps_existing_collision_suffix() {
+    let temp = std::env::temp_dir().join("sharaku_test_update_work_suffix");
+    let _ = std::fs::remove_dir_all(&temp);
+    let library_root = temp.join("library");
+    let taken = library_root.join("Same");
+    let current = library_root.join("Same_0001");
+    make_folder(&taken);
+    make_folder(&current);
+
+    let mut conn = setup_test_db();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    settings::set_directory_template(&conn, "{title}").unwrap();
+    insert_work(&conn, "Same", &taken.to_string_lossy(), "folder");
+    let id = insert_work(&conn, "Same", &current.to_string_lossy(), "folder");
+
+    let work = update_work(&mut conn, id, &edit("Same", Some("Artist"))).unwrap();
+    assert_eq!(work.path, current.to_string_lossy());
+    assert!(current.join("01.jpg").is_file());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn update_work_without_template_does_not_move() {
+    let temp = std::env::temp_dir().join("sharaku_test_update_work_no_template");
+    let _ = std::fs::remove_dir_all(&temp);
+    let old_path = temp.join("Old");
+    make_folder(&old_path);
+
+    let mut conn = setup_test_db();
+    let id = insert_work(&conn, "Old", &old_path.to_string_lossy(), "folder");
+
+    let work = update_work(&mut conn, id, &edit("New", None)).unwrap();
+    assert_eq!(work.title, "New");
+    assert_eq!(work.path, old_path.to_string_lossy());
+    assert!(old_path.join("01.jpg").is_file());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn external_works_are_never_moved_or_trashed() {
+    let temp = std::env::temp_dir().join("sharaku_test_external_work");
+    let _ = std::fs::remove_dir_all(&temp);
+    let library_root = temp.join("library");
+    let folder = temp.join("archive").join("Old");
+    make_folder(&folder);
+
+    let mut conn = setup_test_db();
+    settings::set_library_root(&conn, &library_root.to_string_lossy()).unwrap();
+    settings::set_directory_template(&conn, "{artist}/{title}").unwrap();
+    let id = insert_work(&conn, "Old", &folder.to_string_lossy(), "folder");
+    conn.execute("UPDATE works SET external = 1 WHERE id = ?1", [id])
+        .unwrap();
+
+    let work = update_work(&mut conn, id, &edit("New", Some("Artist"))).unwrap();
+    assert_eq!(work.title, "New");
+    assert!(work.external);
+    assert_eq!(work.path, folder.to_string_lossy());
+    assert!(!library_root.exists());
+
+    let summary = delete_works(&mut conn, &[id], DeleteMode::TrashFiles).unwrap();
+    assert_eq!(summary.deleted, 1);
+    assert!(summary.file_errors.is_empty());
+    assert!(folder.join("01.jpg").is_file());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn delete_works_keep_files_removes_rows_only() {
+    let temp = std::env::temp_dir().join("sharaku_test_delete_keep_files");
+    let _ = std::fs::remove_dir_all(&temp);
+    let folder = temp.join("Work");
+    make_folder(&folder);
+
+    let mut conn = setup_test_db();
+    let a = insert_work(&conn, "A", &folder.to_string_lossy(), "folder");
+    let b = insert_work(&conn, "B", "/b.jpg", "image");
+    let c = insert_work(&conn, "C", "/c.jpg", "image");
+
+    let summary = delete_works(&mut conn, &[a, b, a], DeleteMode::KeepFiles).unwrap();
+    assert_eq!(summary.deleted, 2);
+    assert!(summary.file_errors.is_empty());
+    assert!(matches!(db::get_work(&conn, a), Err(AppError::NotFound)));
+    assert!(matches!(db::get_work(&conn, b), Err(AppError::NotFound)));
+    assert!(db::get_work(&conn, c).is_ok());
+    assert!(folder.join("01.jpg").is_file());
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn delete_works_refuses_to_trash_works_on_unmounted_roots() {
+    let temp = std::env::temp_dir().join("sharaku_test_delete_offline");
+    let _ = std::fs::remove_dir_all(&temp);
+    let volume = temp.join("volume");
+    let folder = volume.join("Work");
+    make_folder(&folder);
+
+    let mut conn = setup_test_db();
+    let id = insert_work(&conn, "Work", &folder.to_string_lossy(), "folder");
+    crate::roots::create_root(&conn, "Volume", &volume.to_string_lossy()).unwrap();
+    let unmounted = temp.join("unmounted");
+    std::fs::rename(&volume, &unmounted).unwrap();
+    assert!(db::get_work(&conn, id).unwrap().offline);
+
+    let result = delete_works(&mut conn, &[id], DeleteMode::TrashFiles);
+    assert!(matches!(result, Err(AppError::WorkError(_))));
+    assert!(db::get_work(&conn, id).is_ok());
+    assert!(unmounted.join("Work").join("01.jpg").is_file());
+
+    let summary = delete_works(&mut conn, &[id], DeleteMode::KeepFiles).unwrap();
+    assert_eq!(summary.deleted, 1);
+
+    std::fs::remove_dir_all(&temp).unwrap();
+}
+
+#[test]
+fn delete_works_is_all_or_nothing_on_unknown_id() {
+    let mut conn = setup_test_db();
+    let a = insert_work(&conn, "A", "/a.jpg", "image");
+    let result = delete_works(&mut conn, &[a, 999], DeleteMode::KeepFiles);
+    assert!(matches!(result, Err(AppError::NotFound)));
+    assert!(db::get_work(&conn, a).is_ok());
+}
+
+#[test]
+fn delete_works_cascades_tags_and_compacts_playlists() {
+    let mut conn = setup_test_db();
+    let a = insert_work(&conn, "A", "/a.jpg", "image");
+    let b = insert_work(&conn, "B", "/b.jpg", "image");
+    let c = insert_work(&conn, "C", "/c.jpg", "image");
+    let tag = crate::tags::create_tag(&conn, "color", None).unwrap();
+    crate::tags::add_tag_to_work(&conn, b, tag.id).unwrap();
+    let playlist = playlists::create_playlist(&conn, "List").unwrap();
+    playlists::add_works_to_playlist(&mut conn, playlist.id, &[a, b, c]).unwrap();
+
+    delete_works(&mut conn, &[b], DeleteMode::KeepFiles).unwrap();
+
+    let tagged: i64 = conn
+        .query_row("SELECT COUNT(*) FROM works_tags", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(tagged, 0);
+    let positions: Vec<(i64, i64)> = conn
+        .prepare("SELECT work_id, position FROM playlist_items ORDER BY position")
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(positions, vec![(a, 0), (c, 1)]);
+}
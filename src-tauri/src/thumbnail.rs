@@ -1,16 +1,27 @@
 use std::path::Path;
 
 use image::imageops::FilterType;
-use image::GenericImageView;
+use image::{DynamicImage, GenericImageView};
 
 use crate::error::AppError;
 
 const MAX_WIDTH: u32 = 200;
 const MAX_HEIGHT: u32 = 280;
 const WEBP_QUALITY: f32 = 65.0;
+const DHASH_WIDTH: u32 = 9;
+const DHASH_HEIGHT: u32 = 8;
 
 pub fn generate_thumbnail(image_path: &Path) -> Result<Vec<u8>, AppError> {
     let img = image::open(image_path)?;
+    generate_thumbnail_from_image(&img)
+}
+
+pub fn generate_thumbnail_from_bytes(data: &[u8]) -> Result<Vec<u8>, AppError> {
+    let img = image::load_from_memory(data)?;
+    generate_thumbnail_from_image(&img)
+}
+
+pub fn generate_thumbnail_from_image(img: &DynamicImage) -> Result<Vec<u8>, AppError> {
     let (orig_w, orig_h) = img.dimensions();
 
     let scale = (MAX_WIDTH as f64 / orig_w as f64).min(MAX_HEIGHT as f64 / orig_h as f64);
@@ -33,3 +44,32 @@ pub fn generate_thumbnail(image_path: &Path) -> Result<Vec<u8>, AppError> {
 
     Ok(mem.to_vec())
 }
+
+/// 64-bit difference hash: each bit records whether a pixel is brighter than its
+/// right-hand neighbour in a 9x8 grayscale reduction. Rescans and re-encodes of
+/// the same page land within a few bits of each other.
+pub fn dhash(img: &DynamicImage) -> u64 {
+    let small = img
+        .resize_exact(DHASH_WIDTH, DHASH_HEIGHT, FilterType::Triangle)
+        .to_luma8();
+    let mut hash = 0u64;
+    for y in 0..DHASH_HEIGHT {
+        for x in 0..DHASH_WIDTH - 1 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    hash
+}
+
+/// dHash of a stored thumbnail, as stored in `works.perceptual_hash` (the bits
+/// reinterpreted as a signed SQLite integer).
+pub fn perceptual_hash(thumbnail: &[u8]) -> Result<i64, AppError> {
+    let img = image::load_from_memory(thumbnail)?;
+    Ok(dhash(&img) as i64)
+}
+
+#[cfg(test)]
+#[path = "tests/thumbnail.rs"]
+mod tests;
@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::error::AppError;
+use crate::hashing;
+
+/// How a work got to its new location.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum MoveStrategy {
+    /// Renamed in place on the same filesystem.
+    Rename,
+    /// Copied, verified, then the original deleted.
+    Copy,
+}
+
+impl MoveStrategy {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            MoveStrategy::Rename => "rename",
+            MoveStrategy::Copy => "copy",
+        }
+    }
+}
+
+#[cfg(unix)]
+fn device_id(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    path.ancestors()
+        .find_map(|ancestor| std::fs::metadata(ancestor).ok())
+        .map(|metadata| metadata.dev())
+}
+
+/// Whether `a` and `b` (or their nearest existing ancestors) are on the same
+/// filesystem. Off Unix this is not known up front, so a rename is attempted and
+/// its failure treated as crossing filesystems.
+pub(crate) fn same_filesystem(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        matches!((device_id(a), device_id(b)), (Some(x), Some(y)) if x == y)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (a, b);
+        true
+    }
+}
+
+/// Whether renaming the folder would move exactly the files a copy would, i.e.
+/// nothing outside `files` would be dragged along.
+pub(crate) fn carries_whole_folder(folder: &Path, files: &[std::path::PathBuf]) -> bool {
+    let entries = WalkDir::new(folder)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| !e.file_type().is_dir())
+        .count();
+    entries == files.len()
+}
+
+/// Renames `source` onto `target`, which may be an empty placeholder directory
+/// or file claimed for it.
+pub(crate) fn rename_onto(source: &Path, target: &Path) -> std::io::Result<()> {
+    match std::fs::rename(source, target) {
+        Ok(()) => Ok(()),
+        // Windows cannot rename a directory over an existing one, even an empty one.
+        Err(_) if cfg!(windows) && target.is_dir() => {
+            std::fs::remove_dir(target)?;
+            std::fs::rename(source, target).inspect_err(|_| {
+                let _ = std::fs::create_dir(target);
+            })
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Copies one file and checks the copy has the same size and SHA-256 as the
+/// original, for copies whose source is deleted afterwards.
+pub(crate) fn copy_verified(source: &Path, dest: &Path) -> Result<(), AppError> {
+    let copied = std::fs::copy(source, dest)?;
+    if copied != std::fs::metadata(source)?.len()
+        || hashing::hash_file(source)? != hashing::hash_file(dest)?
+    {
+        return Err(AppError::TransferError(format!(
+            "コピー後の内容が一致しません: {}",
+            dest.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "tests/transfer.rs"]
+mod tests;
@@ -1,7 +1,10 @@
 use std::path::Path;
 
-use crate::db;
+use crate::archive;
+use crate::db::{self, WorkDetail};
+use crate::error::AppError;
 use crate::importer;
+use crate::pdf;
 
 pub fn parse_view_uri(uri: &str) -> Option<(i64, usize)> {
     let idx = uri.find("view/")?;
@@ -39,27 +42,48 @@ fn load_image(
 ) -> Result<(Vec<u8>, &'static str), u16> {
     let conn = db::open_db(app_data_dir).map_err(|_| 500u16)?;
     let work = db::get_work(&conn, work_id).map_err(|_| 404u16)?;
+    if work.offline {
+        return Err(503);
+    }
+    read_page(&work, page_index)
+}
 
-    if work.work_type == "folder" {
-        let images = importer::list_images_in_folder(Path::new(&work.path)).map_err(|e| {
-            if let crate::error::AppError::Io(ref io_err) = e {
-                if io_err.kind() == std::io::ErrorKind::NotFound {
-                    return 404u16;
-                }
+fn read_page(work: &WorkDetail, page_index: usize) -> Result<(Vec<u8>, &'static str), u16> {
+    match work.work_type.as_str() {
+        "folder" => {
+            let images =
+                importer::list_images_in_folder(Path::new(&work.path)).map_err(error_status)?;
+            let file_path = images.get(page_index).ok_or(404u16)?;
+            let data = std::fs::read(file_path).map_err(|_| 404u16)?;
+            let content_type = content_type_from_path(&file_path.to_string_lossy());
+            Ok((data, content_type))
+        }
+        "archive" => {
+            let (name, data) = archive::read_archive_page(Path::new(&work.path), page_index)
+                .map_err(error_status)?;
+            Ok((data, content_type_from_path(&name)))
+        }
+        "pdf" => {
+            let data = pdf::render_page_as_jpeg(Path::new(&work.path), page_index)
+                .map_err(error_status)?;
+            Ok((data, "image/jpeg"))
+        }
+        _ => {
+            if page_index != 0 {
+                return Err(404);
             }
-            500u16
-        })?;
-        let file_path = images.get(page_index).ok_or(404u16)?;
-        let data = std::fs::read(file_path).map_err(|_| 404u16)?;
-        let content_type = content_type_from_path(&file_path.to_string_lossy());
-        Ok((data, content_type))
-    } else {
-        if page_index != 0 {
-            return Err(404);
+            let data = std::fs::read(&work.path).map_err(|_| 404u16)?;
+            let content_type = content_type_from_path(&work.path);
+            Ok((data, content_type))
         }
-        let data = std::fs::read(&work.path).map_err(|_| 404u16)?;
-        let content_type = content_type_from_path(&work.path);
-        Ok((data, content_type))
+    }
+}
+
+fn error_status(e: AppError) -> u16 {
+    match e {
+        AppError::NotFound => 404,
+        AppError::Io(ref io_err) if io_err.kind() == std::io::ErrorKind::NotFound => 404,
+        _ => 500,
     }
 }
 
@@ -0,0 +1,358 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+use notify_debouncer_mini::notify::{RecursiveMode, Watcher};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::db;
+use crate::dedup;
+use crate::doctor;
+use crate::error::AppError;
+use crate::hashing;
+use crate::importer;
+use crate::roots;
+use crate::scanner;
+use crate::settings;
+use crate::thumbnail;
+
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(750);
+/// How often roots that could not be watched are checked for coming back online.
+const REARM_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum WorkChange {
+    #[serde(rename_all = "camelCase")]
+    Updated {
+        work_id: i64,
+        page_count: usize,
+        thumbnail_updated: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    Missing { work_id: i64 },
+}
+
+struct WatchedWork {
+    id: i64,
+    path: PathBuf,
+    work_type: String,
+    page_count: i32,
+}
+
+/// Works whose files can be watched; those on an unmounted root are left out.
+fn list_watched_works(conn: &Connection) -> Result<Vec<WatchedWork>, AppError> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT w.id, w.path, w.type, IFNULL(w.page_count, 0), r.path FROM works w LEFT JOIN library_roots r ON r.id = w.root_id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let root_path: Option<String> = row.get(4)?;
+        let online = root_path.as_deref().is_none_or(roots::is_online);
+        Ok(online.then_some(WatchedWork {
+            id: row.get(0)?,
+            path: PathBuf::from(roots::resolve_location(
+                root_path.as_deref(),
+                &row.get::<_, String>(1)?,
+            )),
+            work_type: row.get(2)?,
+            page_count: row.get(3)?,
+        }))
+    })?;
+    let mut works = Vec::new();
+    for row in rows {
+        works.extend(row?);
+    }
+    Ok(works)
+}
+
+/// A page at or before the current first page (by natural order) was added,
+/// edited or removed, so the thumbnail may no longer match.
+fn first_page_changed(first_page: &Path, changed: &[&Path]) -> bool {
+    let first_name = first_page.file_name().unwrap_or_default().to_string_lossy();
+    changed.iter().any(|path| {
+        scanner::is_image_file(path)
+            && path.file_name().is_some_and(|name| {
+                natord::compare(&name.to_string_lossy(), &first_name) != std::cmp::Ordering::Greater
+            })
+    })
+}
+
+/// The newest modification time among `paths`.
+fn latest_mtime<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Option<SystemTime> {
+    paths
+        .into_iter()
+        .filter_map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .max()
+}
+
+fn refresh_work(
+    conn: &Connection,
+    work: &WatchedWork,
+    changed: &[&Path],
+    seen_mtimes: &mut HashMap<i64, SystemTime>,
+) -> Result<Option<WorkChange>, AppError> {
+    let exists = if work.work_type == "folder" {
+        work.path.is_dir()
+    } else {
+        work.path.is_file()
+    };
+    if !exists {
+        return Ok(Some(WorkChange::Missing { work_id: work.id }));
+    }
+
+    let images = if work.work_type == "folder" {
+        importer::list_images_in_folder(&work.path)?
+    } else {
+        Vec::new()
+    };
+    let mtime = latest_mtime(
+        std::iter::once(work.path.as_path()).chain(images.iter().map(PathBuf::as_path)),
+    );
+    let mtime_changed = mtime.is_none() || seen_mtimes.get(&work.id) != mtime.as_ref();
+    if !mtime_changed && work.work_type != "folder" {
+        return Ok(None);
+    }
+
+    let (page_count, thumb) = match work.work_type.as_str() {
+        "folder" => {
+            let thumb = match images.first() {
+                Some(first) if first_page_changed(first, changed) => {
+                    Some(thumbnail::generate_thumbnail(first)?)
+                }
+                _ => None,
+            };
+            (images.len(), thumb)
+        }
+        "archive" | "pdf" => {
+            let (page_count, thumb) = importer::inspect_file_work(&work.path, &work.work_type)?;
+            (page_count, Some(thumb))
+        }
+        _ => (
+            doctor::count_pages(&work.path, &work.work_type)?,
+            Some(thumbnail::generate_thumbnail(&work.path)?),
+        ),
+    };
+
+    let count_changed = page_count as i32 != work.page_count;
+    if !count_changed && thumb.is_none() && !mtime_changed {
+        return Ok(None);
+    }
+    if count_changed {
+        db::update_page_count(conn, work.id, page_count as i32)?;
+    }
+    if let Some(thumb) = &thumb {
+        db::update_thumbnail(conn, work.id, thumb)?;
+        db::set_perceptual_hash(conn, work.id, thumbnail::perceptual_hash(thumb).ok())?;
+    }
+    // Hashing reads every page, so only do it when the pages may differ.
+    if count_changed || mtime_changed {
+        dedup::store_work_hashes(
+            conn,
+            work.id,
+            &hashing::hash_work(&work.path, &work.work_type)?,
+        )?;
+    }
+    if let Some(mtime) = mtime {
+        seen_mtimes.insert(work.id, mtime);
+    }
+
+    if !count_changed && thumb.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(WorkChange::Updated {
+        work_id: work.id,
+        page_count,
+        thumbnail_updated: thumb.is_some(),
+    }))
+}
+
+/// Maps changed paths to the works that own them and brings each work's page
+/// count, thumbnail and hashes up to date. `seen_mtimes` remembers when each
+/// work's files were last hashed, so events that changed nothing are skipped.
+pub fn apply_changes(
+    conn: &Connection,
+    changed: &[PathBuf],
+    seen_mtimes: &mut HashMap<i64, SystemTime>,
+) -> Result<Vec<WorkChange>, AppError> {
+    let works = list_watched_works(conn)?;
+    let by_path: HashMap<&Path, usize> = works
+        .iter()
+        .enumerate()
+        .map(|(index, work)| (work.path.as_path(), index))
+        .collect();
+    let mut by_work: HashMap<usize, Vec<&Path>> = HashMap::new();
+    for path in changed {
+        // A folder work owns its directory and the files directly inside it;
+        // a single-file work owns only its file.
+        let owner = by_path.get(path.as_path()).copied().or_else(|| {
+            let index = *by_path.get(path.parent()?)?;
+            (works[index].work_type == "folder").then_some(index)
+        });
+        if let Some(index) = owner {
+            by_work.entry(index).or_default().push(path);
+        }
+    }
+
+    let mut indexes: Vec<usize> = by_work.keys().copied().collect();
+    indexes.sort_unstable();
+    let mut changes = Vec::new();
+    for index in indexes {
+        // One unreadable work must not stop the rest of the batch.
+        if let Ok(Some(change)) = refresh_work(conn, &works[index], &by_work[&index], seen_mtimes) {
+            changes.push(change);
+        }
+    }
+    Ok(changes)
+}
+
+/// Directories to watch: every root and the library root recursively, plus
+/// the folders (or, for single-file works, the parent directories) of works
+/// outside all of them. Roots are listed whether or not they are mounted.
+fn watch_targets(conn: &Connection) -> Result<BTreeMap<PathBuf, RecursiveMode>, AppError> {
+    let mut targets = BTreeMap::new();
+    for root in roots::list_roots(conn)? {
+        targets.insert(PathBuf::from(root.path), RecursiveMode::Recursive);
+    }
+    if let Some(library_root) = settings::get_library_root(conn)? {
+        let library_root = PathBuf::from(library_root);
+        if !targets.keys().any(|root| library_root.starts_with(root)) {
+            targets.insert(library_root, RecursiveMode::Recursive);
+        }
+    }
+    let recursive: Vec<PathBuf> = targets.keys().cloned().collect();
+    for work in list_watched_works(conn)? {
+        if recursive.iter().any(|root| work.path.starts_with(root)) {
+            continue;
+        }
+        let dir = if work.work_type == "folder" {
+            Some(work.path.as_path())
+        } else {
+            work.path.parent()
+        };
+        if let Some(dir) = dir {
+            targets
+                .entry(dir.to_path_buf())
+                .or_insert(RecursiveMode::NonRecursive);
+        }
+    }
+    Ok(targets)
+}
+
+/// Watches the targets that exist and are not watched yet. A target that
+/// cannot be watched is logged and skipped rather than stopping the rest.
+/// Targets that have disappeared are forgotten, so they are watched again once
+/// they are back, e.g. when a removable volume is remounted.
+fn arm(
+    watcher: &mut dyn Watcher,
+    targets: &BTreeMap<PathBuf, RecursiveMode>,
+    armed: &mut HashSet<PathBuf>,
+) {
+    for (path, mode) in targets {
+        if !path.exists() {
+            if armed.remove(path) {
+                let _ = watcher.unwatch(path);
+            }
+            continue;
+        }
+        if !armed.insert(path.clone()) {
+            continue;
+        }
+        if let Err(e) = watcher.watch(path, *mode) {
+            eprintln!("Failed to watch {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Keeps registered works in sync with the filesystem while alive. Dropping it
+/// stops watching.
+pub struct LibraryWatcher {
+    targets: BTreeMap<PathBuf, RecursiveMode>,
+    stop: Option<mpsc::Sender<()>>,
+    poller: Option<JoinHandle<()>>,
+}
+
+/// Whether `wanted` has a target that `watched` does not cover the same way.
+fn has_new_targets(
+    watched: &BTreeMap<PathBuf, RecursiveMode>,
+    wanted: &BTreeMap<PathBuf, RecursiveMode>,
+) -> bool {
+    wanted
+        .iter()
+        .any(|(path, mode)| watched.get(path) != Some(mode))
+}
+
+impl LibraryWatcher {
+    pub fn start(
+        app_data_dir: PathBuf,
+        on_change: impl Fn(Vec<WorkChange>) + Send + 'static,
+    ) -> Result<Self, AppError> {
+        let conn = db::open_db(&app_data_dir)?;
+        let targets = watch_targets(&conn)?;
+        drop(conn);
+
+        let mut seen_mtimes = HashMap::new();
+        let mut debouncer = new_debouncer(DEBOUNCE_TIMEOUT, move |result: DebounceEventResult| {
+            let Ok(events) = result else {
+                return;
+            };
+            let paths: Vec<PathBuf> = events.into_iter().map(|event| event.path).collect();
+            let Ok(conn) = db::open_db(&app_data_dir) else {
+                return;
+            };
+            if let Ok(changes) = apply_changes(&conn, &paths, &mut seen_mtimes) {
+                if !changes.is_empty() {
+                    on_change(changes);
+                }
+            }
+        })
+        .map_err(|e| AppError::WatchError(e.to_string()))?;
+
+        // Works that have gone missing are the doctor's job; only roots are
+        // checked again later.
+        let mut armed = HashSet::new();
+        arm(debouncer.watcher(), &targets, &mut armed);
+        let roots: BTreeMap<PathBuf, RecursiveMode> = targets
+            .iter()
+            .filter(|(_, mode)| **mode == RecursiveMode::Recursive)
+            .map(|(path, mode)| (path.clone(), *mode))
+            .collect();
+
+        let (stop, stopped) = mpsc::channel::<()>();
+        let poller = std::thread::spawn(move || {
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(REARM_INTERVAL) {
+                arm(debouncer.watcher(), &roots, &mut armed);
+            }
+        });
+
+        Ok(Self {
+            targets,
+            stop: Some(stop),
+            poller: Some(poller),
+        })
+    }
+
+    /// Whether works registered since the watcher started live somewhere it is
+    /// not watching, e.g. works referenced in place outside every root.
+    pub fn is_stale(&self, conn: &Connection) -> Result<bool, AppError> {
+        Ok(has_new_targets(&self.targets, &watch_targets(conn)?))
+    }
+}
+
+impl Drop for LibraryWatcher {
+    fn drop(&mut self) {
+        // Waits for the poller, which owns the debouncer, so nothing is still
+        // watching once this returns.
+        self.stop.take();
+        if let Some(poller) = self.poller.take() {
+            let _ = poller.join();
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/watcher.rs"]
+mod tests;
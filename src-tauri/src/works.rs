@@ -0,0 +1,185 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{self, WorkDetail, WorkEdit};
+use crate::error::AppError;
+use crate::playlists;
+use crate::relocator;
+use crate::settings;
+use crate::template::WorkMetadata;
+
+fn normalize_optional(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+fn normalize_edit(edit: &WorkEdit) -> Result<WorkEdit, AppError> {
+    let title = edit.title.trim();
+    if title.is_empty() {
+        return Err(AppError::WorkError("タイトルは空にできません".to_string()));
+    }
+    Ok(WorkEdit {
+        title: title.to_string(),
+        artist: normalize_optional(&edit.artist),
+        year: edit.year,
+        genre: normalize_optional(&edit.genre),
+        circle: normalize_optional(&edit.circle),
+        origin: normalize_optional(&edit.origin),
+    })
+}
+
+/// Returns the library root and new path when the edited work should move.
+/// Works are only relocated when a template applies to their type and
+/// `relocator::is_relocatable` allows it.
+fn relocation_target(
+    conn: &Connection,
+    work: &WorkDetail,
+    edit: &WorkEdit,
+) -> Result<Option<(PathBuf, PathBuf)>, AppError> {
+    let Some(library_root) = settings::get_library_root(conn)? else {
+        return Ok(None);
+    };
+    let library_root = PathBuf::from(library_root);
+    if !relocator::is_relocatable(work, &library_root) {
+        return Ok(None);
+    }
+    let current = Path::new(&work.path);
+    if !current.exists() {
+        return Ok(None);
+    }
+
+    let metadata = WorkMetadata {
+        title: edit.title.clone(),
+        artist: edit.artist.clone(),
+        year: edit.year,
+        genre: edit.genre.clone(),
+        circle: edit.circle.clone(),
+        origin: edit.origin.clone(),
+        work_type: None,
+    };
+    let placement = relocator::Placement::current(conn)?;
+    let Some(base) = placement.base_path(&library_root, &work.work_type, metadata) else {
+        return Ok(None);
+    };
+    let extension = relocator::work_extension(&work.work_type, current);
+    Ok(
+        relocator::resolve_relocation_target(&base, extension.as_deref(), current)
+            .map(|target| (library_root, target)),
+    )
+}
+
+/// Saves edited metadata and bumps `updated_at`. When a template applies to the
+/// work's type, the work is also moved to the path the template now yields.
+pub fn update_work(
+    conn: &mut Connection,
+    work_id: i64,
+    edit: &WorkEdit,
+) -> Result<WorkDetail, AppError> {
+    let edit = normalize_edit(edit)?;
+    let work = db::get_work(conn, work_id)?;
+
+    match relocation_target(conn, &work, &edit)? {
+        Some((library_root, new_path)) => {
+            let new_path_str = new_path.to_string_lossy().to_string();
+            relocator::move_work(
+                conn,
+                work_id,
+                &work.work_type,
+                Path::new(&work.path),
+                &new_path,
+                &library_root,
+                |tx| {
+                    db::update_work_metadata(tx, work_id, &edit)?;
+                    db::update_work_path(tx, work_id, &new_path_str)
+                },
+            )?;
+        }
+        None => db::update_work_metadata(conn, work_id, &edit)?,
+    }
+
+    db::get_work(conn, work_id)
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum DeleteMode {
+    /// Only unregister the works; files stay on disk.
+    KeepFiles,
+    /// Also move the work's folder or file to the OS trash.
+    TrashFiles,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteSummary {
+    pub deleted: usize,
+    pub file_errors: Vec<String>,
+}
+
+/// Removes the works from the library in one transaction. With `TrashFiles`, the
+/// files are trashed afterwards and empty template directories are pruned; a file
+/// that cannot be trashed is reported but does not bring its row back. Files of
+/// external works are never trashed, and works on an unmounted root cannot be
+/// trashed at all, so nothing is deleted if any are included.
+pub fn delete_works(
+    conn: &mut Connection,
+    work_ids: &[i64],
+    mode: DeleteMode,
+) -> Result<DeleteSummary, AppError> {
+    let work_ids: BTreeSet<i64> = work_ids.iter().copied().collect();
+    let mut works = Vec::with_capacity(work_ids.len());
+    for &work_id in &work_ids {
+        works.push(db::get_work(conn, work_id)?);
+    }
+    if mode == DeleteMode::TrashFiles {
+        if let Some(work) = works.iter().find(|work| work.offline && !work.external) {
+            return Err(AppError::WorkError(format!(
+                "ボリュームが接続されていないためゴミ箱に移せません: {}",
+                work.path
+            )));
+        }
+    }
+
+    let tx = conn.transaction()?;
+    let mut affected_playlists = BTreeSet::new();
+    for work in &works {
+        affected_playlists.extend(playlists::playlists_containing(&tx, work.id)?);
+        db::delete_work(&tx, work.id)?;
+    }
+    for playlist_id in affected_playlists {
+        playlists::compact_positions(&tx, playlist_id)?;
+    }
+    tx.commit()?;
+
+    let mut file_errors = Vec::new();
+    if mode == DeleteMode::TrashFiles {
+        for work in &works {
+            let path = Path::new(&work.path);
+            if work.external || !path.exists() {
+                continue;
+            }
+            if let Err(e) = trash::delete(path) {
+                file_errors.push(format!("{}: {}", work.path, e));
+                continue;
+            }
+            if let Some(root) = relocator::cleanup_boundary(conn, work.root_id)? {
+                relocator::cleanup_empty_ancestors(path, &root);
+            }
+        }
+    }
+
+    Ok(DeleteSummary {
+        deleted: works.len(),
+        file_errors,
+    })
+}
+
+#[cfg(test)]
+#[path = "tests/works.rs"]
+mod tests;
@@ -10,20 +10,86 @@ export interface WorkSummary {
   workType: string;
   pageCount: number;
   createdAt: string;
+  rootId: number | null;
+}
+
+export interface WorkCursor {
+  value: string | number | null;
+  id: number;
+}
+
+export interface WorkFilter {
+  workType?: string;
+  artist?: string;
+  circle?: string;
+  genre?: string;
+  yearFrom?: number;
+  yearTo?: number;
+  tagId?: number;
+}
+
+export interface WorkPage {
+  works: WorkSummary[];
+  nextCursor: WorkCursor | null;
+}
+
+export interface SimilarCluster {
+  works: WorkSummary[];
+  maxDistance: number;
 }
 
 export interface WorkDetail {
   id: number;
   title: string;
   path: string;
+  rootId: number | null;
+  offline: boolean;
   workType: string;
   pageCount: number;
   createdAt: string;
+  updatedAt: string;
   artist: string | null;
   year: number | null;
   genre: string | null;
   circle: string | null;
   origin: string | null;
+  external: boolean;
+  tags: TagGroup[];
+}
+
+export interface WorkEdit {
+  title: string;
+  artist: string | null;
+  year: number | null;
+  genre: string | null;
+  circle: string | null;
+  origin: string | null;
+}
+
+export type DeleteMode = "keepFiles" | "trashFiles";
+
+export interface DeleteSummary {
+  deleted: number;
+  fileErrors: string[];
+}
+
+export interface Tag {
+  id: number;
+  name: string;
+  category: string | null;
+}
+
+export interface TagGroup {
+  category: string | null;
+  tags: Tag[];
+}
+
+export interface Playlist {
+  id: number;
+  name: string;
+  itemCount: number;
+  createdAt: string;
+  updatedAt: string;
 }
 
 export type SortField = "title" | "created_at";
@@ -36,10 +102,18 @@ export type SlideshowMode = "page" | "work";
 export interface AppSettings {
   libraryRoot: string | null;
   directoryTemplate: string | null;
+  typeTemplates: Partial<Record<TemplateWorkType, string>>;
   typeLabelImage: string;
   typeLabelFolder: string;
+  importWorkers: number;
+  extraFilesMode: ExtraFilesMode;
+  extraFileExtensions: string[];
 }
 
+export type ExtraFilesMode = "none" | "all" | "listed";
+
+export type TemplateWorkType = "folder" | "image" | "archive" | "pdf";
+
 export interface TemplateValidation {
   valid: boolean;
   error: string | null;
@@ -54,7 +128,7 @@ export interface WorkMetadata {
   origin: string | null;
 }
 
-export type ImportMode = "copy" | "move";
+export type ImportMode = "copy" | "move" | "adopt" | "reference";
 
 export interface ImportRequest {
   sourcePath: string;
@@ -65,27 +139,94 @@ export interface ImportRequest {
   circle: string | null;
   origin: string | null;
   mode: ImportMode;
+  allowDuplicates?: boolean;
+}
+
+export interface DuplicateMatch {
+  workId: number;
+  title: string;
+  path: string;
+  matchedPages: number;
+  exact: boolean;
 }
 
 export interface ImportResult {
   destinationPath: string;
   pageCount: number;
+  duplicates: DuplicateMatch[];
 }
 
 export interface ParsedMetadata {
   title: string;
   artist: string | null;
+  circle: string | null;
+  year: number | null;
+  genre: string | null;
+  origin: string | null;
+  event: string | null;
+  language: string | null;
+}
+
+export interface NameRule {
+  id: number;
+  name: string;
+  pattern: string;
+  priority: number;
+  enabled: boolean;
+}
+
+export interface NameRuleInput {
+  name: string;
+  pattern: string;
+  priority: number;
+  enabled: boolean;
 }
 
+export interface LibraryRoot {
+  id: number;
+  name: string;
+  path: string;
+  online: boolean;
+  workCount: number;
+}
+
+export interface FolderNameMatch {
+  rule: string | null;
+  builtin: boolean;
+  metadata: ParsedMetadata;
+}
+
+export type MoveStrategy = "rename" | "copy";
+
 export type RelocationProgress =
   | { type: "started"; total: number }
   | { type: "moving"; current: number; total: number; title: string }
+  | {
+      type: "moved";
+      current: number;
+      total: number;
+      title: string;
+      strategy: MoveStrategy;
+    }
   | { type: "completed"; relocated: number; skipped: number; failed: number }
   | { type: "error"; message: string };
 
+export type LibraryMoveProgress =
+  | { type: "copied"; current: number; total: number; file: string }
+  | { type: "completed"; works: number; strategy: MoveStrategy };
+
+export interface LastRelocation {
+  workType: TemplateWorkType;
+  oldTemplate: string | null;
+  newTemplate: string;
+  workCount: number;
+  createdAt: string;
+}
+
 export interface RelocationPreview {
   workId: number;
   title: string;
+  workType: TemplateWorkType;
   oldPath: string;
   newPath: string;
 }
@@ -93,9 +234,11 @@ export interface RelocationPreview {
 export interface DiscoveredFolder {
   path: string;
   folderName: string;
+  workType: "folder" | "archive" | "pdf";
   imageCount: number;
   parsedMetadata: ParsedMetadata;
   alreadyRegistered: boolean;
+  duplicates: DuplicateMatch[];
 }
 
 export type DiscoverProgress =
@@ -103,12 +246,61 @@ export type DiscoverProgress =
   | { type: "completed"; found: number };
 
 export type BulkImportProgress =
-  | { type: "started"; total: number }
+  | { type: "started"; jobId: number; total: number }
   | { type: "importing"; current: number; total: number; title: string }
   | { type: "completed"; succeeded: number; failed: number }
+  | { type: "cancelled"; succeeded: number; failed: number; remaining: number }
   | { type: "error"; title: string; message: string };
 
 export interface BulkImportSummary {
+  jobId: number;
   succeeded: number;
   failed: number;
+  cancelled: boolean;
 }
+
+export interface ImportJob {
+  id: number;
+  status: "running" | "cancelled" | "completed";
+  active: boolean;
+  total: number;
+  succeeded: number;
+  failed: number;
+  createdAt: string;
+}
+
+export type DoctorProgress =
+  | { type: "checking"; current: number; total: number }
+  | { type: "scanning"; scannedDirs: number }
+  | { type: "completed"; missing: number; orphaned: number; mismatched: number };
+
+export interface MissingWork {
+  workId: number;
+  title: string;
+  path: string;
+  relinkCandidate: string | null;
+}
+
+export interface OrphanedEntry {
+  path: string;
+  workType: string;
+}
+
+export interface PageCountMismatch {
+  workId: number;
+  title: string;
+  path: string;
+  recorded: number;
+  actual: number;
+}
+
+export interface DoctorReport {
+  missing: MissingWork[];
+  orphaned: OrphanedEntry[];
+  pageCountMismatches: PageCountMismatch[];
+  unhashed: number[];
+}
+
+export type WorkChange =
+  | { type: "updated"; workId: number; pageCount: number; thumbnailUpdated: boolean }
+  | { type: "missing"; workId: number };
@@ -0,0 +1,51 @@
+export interface ViewerPosition {
+  workId: number;
+  currentPage: number;
+  pageCount: number;
+  workIds: number[];
+  playlistId: number | null;
+  loop: boolean;
+  /** Skip the remaining pages and go straight to the next work. */
+  byWork: boolean;
+}
+
+export type ViewerStep =
+  | { type: "page"; page: number }
+  | { type: "work"; workId: number }
+  | { type: "stop" };
+
+type Invoke = <T>(cmd: string, args?: Record<string, unknown>) => Promise<T>;
+
+/**
+ * Works out where "next" goes from the current page. Inside a playlist the
+ * backend decides which work follows; otherwise it is the next of `workIds`.
+ */
+export async function nextStep(
+  position: ViewerPosition,
+  invoke: Invoke,
+): Promise<ViewerStep> {
+  const { workId, workIds, playlistId } = position;
+  if (!position.byWork && position.currentPage < position.pageCount - 1) {
+    return { type: "page", page: position.currentPage + 1 };
+  }
+
+  if (playlistId !== null) {
+    const nextId = await invoke<number | null>("next_work", {
+      playlistId,
+      workId,
+    });
+    if (nextId === null) {
+      return { type: "stop" };
+    }
+    return { type: "work", workId: nextId };
+  }
+
+  const index = workIds.indexOf(workId);
+  if (index >= 0 && index < workIds.length - 1) {
+    return { type: "work", workId: workIds[index + 1] };
+  }
+  if (position.loop && workIds.length > 0) {
+    return { type: "work", workId: workIds[0] };
+  }
+  return { type: "stop" };
+}
@@ -0,0 +1,63 @@
+import assert from "node:assert/strict";
+import { test } from "node:test";
+
+import { nextStep } from "../src/lib/viewerNavigation.ts";
+import type { ViewerPosition } from "../src/lib/viewerNavigation.ts";
+
+function recordingInvoke(result: number | null) {
+  const calls: [string, Record<string, unknown> | undefined][] = [];
+  const invoke = async <T>(cmd: string, args?: Record<string, unknown>) => {
+    calls.push([cmd, args]);
+    return result as T;
+  };
+  return { calls, invoke };
+}
+
+const position: ViewerPosition = {
+  workId: 5,
+  currentPage: 0,
+  pageCount: 3,
+  workIds: [4, 5, 6],
+  playlistId: null,
+  loop: false,
+  byWork: false,
+};
+
+test("turns the page before the last one", async () => {
+  const { calls, invoke } = recordingInvoke(9);
+  const step = await nextStep({ ...position, playlistId: 7 }, invoke);
+  assert.deepEqual(step, { type: "page", page: 1 });
+  assert.deepEqual(calls, []);
+});
+
+test("asks next_work for the next playlist work", async () => {
+  const { calls, invoke } = recordingInvoke(9);
+  const step = await nextStep(
+    { ...position, currentPage: 2, playlistId: 7 },
+    invoke,
+  );
+  assert.deepEqual(step, { type: "work", workId: 9 });
+  assert.deepEqual(calls, [["next_work", { playlistId: 7, workId: 5 }]]);
+});
+
+test("stops at the end of a playlist", async () => {
+  const { invoke } = recordingInvoke(null);
+  const step = await nextStep(
+    { ...position, currentPage: 2, playlistId: 7, loop: true },
+    invoke,
+  );
+  assert.deepEqual(step, { type: "stop" });
+});
+
+test("moves through the library order outside a playlist", async () => {
+  const { calls, invoke } = recordingInvoke(9);
+  const last = { ...position, workId: 6, currentPage: 2 };
+
+  let step = await nextStep({ ...position, byWork: true }, invoke);
+  assert.deepEqual(step, { type: "work", workId: 6 });
+  step = await nextStep(last, invoke);
+  assert.deepEqual(step, { type: "stop" });
+  step = await nextStep({ ...last, loop: true }, invoke);
+  assert.deepEqual(step, { type: "work", workId: 4 });
+  assert.deepEqual(calls, []);
+});